@@ -0,0 +1,206 @@
+//! Capture backend selection and diagnostics
+//!
+//! The backend is picked once at runtime. macOS uses `screencapture` and
+//! xcap, X11 sessions use xcap, and Wayland sessions go through the desktop
+//! portal because nothing else can read the screen there. On Linux,
+//! `BETTERSHOT_CAPTURE_BACKEND=x11` or `=wayland_portal` overrides the
+//! detection.
+
+use serde::Serialize;
+use std::sync::LazyLock;
+
+use crate::ocr::available_engines;
+use crate::recording::ffmpeg_available;
+
+/// Environment variable that forces a Linux capture backend
+#[cfg(target_os = "linux")]
+const BACKEND_OVERRIDE_VAR: &str = "BETTERSHOT_CAPTURE_BACKEND";
+
+static BACKEND: LazyLock<CaptureBackend> = LazyLock::new(detect);
+
+/// How screens are captured on this system
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CaptureBackend {
+    MacOs,
+    X11,
+    WaylandPortal,
+    Windows,
+}
+
+/// Pick the Linux backend from the session environment
+///
+/// `XDG_SESSION_TYPE` decides when it's set; otherwise a `WAYLAND_DISPLAY`
+/// means a Wayland session.
+#[cfg(any(target_os = "linux", test))]
+fn linux_backend(
+    override_backend: Option<&str>,
+    session_type: Option<&str>,
+    wayland_display: Option<&str>,
+) -> CaptureBackend {
+    match override_backend.map(str::trim) {
+        Some("x11") => return CaptureBackend::X11,
+        Some("wayland_portal") => return CaptureBackend::WaylandPortal,
+        _ => {}
+    }
+
+    let wayland = match session_type.filter(|session| !session.is_empty()) {
+        Some(session) => session.eq_ignore_ascii_case("wayland"),
+        None => wayland_display.is_some_and(|display| !display.is_empty()),
+    };
+    if wayland {
+        CaptureBackend::WaylandPortal
+    } else {
+        CaptureBackend::X11
+    }
+}
+
+fn detect() -> CaptureBackend {
+    #[cfg(target_os = "macos")]
+    {
+        CaptureBackend::MacOs
+    }
+    #[cfg(target_os = "linux")]
+    {
+        let var = |name: &str| std::env::var(name).ok();
+        linux_backend(
+            var(BACKEND_OVERRIDE_VAR).as_deref(),
+            var("XDG_SESSION_TYPE").as_deref(),
+            var("WAYLAND_DISPLAY").as_deref(),
+        )
+    }
+    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
+    {
+        CaptureBackend::Windows
+    }
+}
+
+/// The capture backend in use
+pub fn capture_backend() -> CaptureBackend {
+    *BACKEND
+}
+
+/// Desktop portal interfaces and their versions, where available
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PortalVersions {
+    pub screenshot: Option<u32>,
+    pub screen_cast: Option<u32>,
+}
+
+/// What the app knows about the capture environment
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Diagnostics {
+    pub capture_backend: CaptureBackend,
+    pub os: &'static str,
+    /// `XDG_SESSION_TYPE`, on Linux
+    pub session_type: Option<String>,
+    /// `None` when there is no desktop portal to ask
+    pub portal: Option<PortalVersions>,
+    /// Whether deny-listed windows can be found and masked
+    pub window_masking: bool,
+    /// Usable OCR engines, the one in use first
+    pub ocr_engines: Vec<&'static str>,
+    pub ffmpeg_available: bool,
+    /// Whether `gst-launch-1.0` can be run, on Linux; Wayland recordings
+    /// decode the screen cast with it
+    pub gstreamer_available: Option<bool>,
+    /// Whether `xdotool` can be run, on Linux; synthetic scrolling in
+    /// scrolling captures sends wheel input with it
+    pub xdotool_available: Option<bool>,
+}
+
+#[cfg(target_os = "linux")]
+fn portal_versions() -> Option<PortalVersions> {
+    use crate::portal::{Portal, SCREENCAST_INTERFACE, SCREENSHOT_INTERFACE};
+
+    let portal = Portal::session().ok()?;
+    Some(PortalVersions {
+        screenshot: portal.version(SCREENSHOT_INTERFACE),
+        screen_cast: portal.version(SCREENCAST_INTERFACE),
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+fn portal_versions() -> Option<PortalVersions> {
+    None
+}
+
+#[cfg(target_os = "linux")]
+fn gstreamer_available() -> Option<bool> {
+    Some(crate::portal::gstreamer_available())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn gstreamer_available() -> Option<bool> {
+    None
+}
+
+#[cfg(target_os = "linux")]
+fn xdotool_available() -> Option<bool> {
+    Some(crate::scroll::xdotool_available())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn xdotool_available() -> Option<bool> {
+    None
+}
+
+/// Collect diagnostics; this talks to D-Bus and runs external tools, so it
+/// blocks
+pub fn diagnostics() -> Diagnostics {
+    let capture_backend = capture_backend();
+    Diagnostics {
+        capture_backend,
+        os: std::env::consts::OS,
+        session_type: std::env::var("XDG_SESSION_TYPE").ok(),
+        portal: portal_versions(),
+        window_masking: capture_backend != CaptureBackend::WaylandPortal,
+        ocr_engines: available_engines()
+            .iter()
+            .map(|engine| engine.name())
+            .collect(),
+        ffmpeg_available: ffmpeg_available(),
+        gstreamer_available: gstreamer_available(),
+        xdotool_available: xdotool_available(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_type_decides_backend() {
+        assert_eq!(
+            linux_backend(None, Some("wayland"), Some("wayland-0")),
+            CaptureBackend::WaylandPortal
+        );
+        assert_eq!(
+            linux_backend(None, Some("x11"), Some("wayland-0")),
+            CaptureBackend::X11
+        );
+        assert_eq!(
+            linux_backend(None, None, Some("wayland-0")),
+            CaptureBackend::WaylandPortal
+        );
+        assert_eq!(linux_backend(None, Some(""), None), CaptureBackend::X11);
+    }
+
+    #[test]
+    fn test_override_wins_over_session() {
+        assert_eq!(
+            linux_backend(Some("x11"), Some("wayland"), Some("wayland-0")),
+            CaptureBackend::X11
+        );
+        assert_eq!(
+            linux_backend(Some("wayland_portal"), Some("x11"), None),
+            CaptureBackend::WaylandPortal
+        );
+        assert_eq!(
+            linux_backend(Some("bogus"), Some("wayland"), None),
+            CaptureBackend::WaylandPortal
+        );
+    }
+}
@@ -0,0 +1,306 @@
+//! QR code and barcode decoding
+//!
+//! Finds QR codes, Data Matrix symbols and common 1D barcodes in a captured
+//! image with rxing, a Rust port of ZXing, so scanning works offline on
+//! every platform. Each image is scanned as captured and again inverted, to
+//! pick up light-on-dark codes from dark-mode UIs.
+
+use image::GrayImage;
+use rxing::helpers::detect_multiple_in_luma;
+use rxing::{BarcodeFormat, RXingResult};
+use serde::Serialize;
+use std::path::Path;
+
+use crate::ocr::PixelRect;
+use crate::utils::AppResult;
+
+/// Symbology of a decoded code
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BarcodeKind {
+    QrCode,
+    DataMatrix,
+    Aztec,
+    Pdf417,
+    Ean13,
+    Ean8,
+    UpcA,
+    UpcE,
+    Code128,
+    Code39,
+    Code93,
+    Codabar,
+    Itf,
+    Other,
+}
+
+impl From<&BarcodeFormat> for BarcodeKind {
+    fn from(format: &BarcodeFormat) -> Self {
+        match format {
+            BarcodeFormat::QR_CODE => BarcodeKind::QrCode,
+            BarcodeFormat::DATA_MATRIX => BarcodeKind::DataMatrix,
+            BarcodeFormat::AZTEC => BarcodeKind::Aztec,
+            BarcodeFormat::PDF_417 => BarcodeKind::Pdf417,
+            BarcodeFormat::EAN_13 => BarcodeKind::Ean13,
+            BarcodeFormat::EAN_8 => BarcodeKind::Ean8,
+            BarcodeFormat::UPC_A => BarcodeKind::UpcA,
+            BarcodeFormat::UPC_E => BarcodeKind::UpcE,
+            BarcodeFormat::CODE_128 => BarcodeKind::Code128,
+            BarcodeFormat::CODE_39 => BarcodeKind::Code39,
+            BarcodeFormat::CODE_93 => BarcodeKind::Code93,
+            BarcodeFormat::CODABAR => BarcodeKind::Codabar,
+            BarcodeFormat::ITF => BarcodeKind::Itf,
+            _ => BarcodeKind::Other,
+        }
+    }
+}
+
+/// A decoded code and where it was found
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Barcode {
+    pub kind: BarcodeKind,
+    pub text: String,
+    /// The decoder's reference points in image pixels: finder pattern
+    /// centers for QR codes, corners for Data Matrix, and the ends of the
+    /// scanned row for 1D barcodes
+    pub points: Vec<(f32, f32)>,
+    /// Bounding box of `points`
+    pub bbox: PixelRect,
+}
+
+impl Barcode {
+    fn from_result(result: &RXingResult, width: u32, height: u32) -> Self {
+        let points: Vec<(f32, f32)> = result
+            .getPoints()
+            .iter()
+            .map(|point| {
+                (
+                    point.x.clamp(0.0, width as f32),
+                    point.y.clamp(0.0, height as f32),
+                )
+            })
+            .collect();
+        Self {
+            kind: BarcodeKind::from(result.getBarcodeFormat()),
+            text: result.getText().to_string(),
+            bbox: bounding_box(&points),
+            points,
+        }
+    }
+}
+
+/// Smallest rect around the points, at least one pixel each way so the row
+/// of a 1D barcode still has an area
+fn bounding_box(points: &[(f32, f32)]) -> PixelRect {
+    if points.is_empty() {
+        return PixelRect::default();
+    }
+    let (mut left, mut top) = (f32::MAX, f32::MAX);
+    let (mut right, mut bottom) = (0.0f32, 0.0f32);
+    for (x, y) in points {
+        left = left.min(*x);
+        top = top.min(*y);
+        right = right.max(*x);
+        bottom = bottom.max(*y);
+    }
+    let x = left.floor() as u32;
+    let y = top.floor() as u32;
+    PixelRect {
+        x,
+        y,
+        width: (right.ceil() as u32).saturating_sub(x).max(1),
+        height: (bottom.ceil() as u32).saturating_sub(y).max(1),
+    }
+}
+
+fn overlaps(a: &PixelRect, b: &PixelRect) -> bool {
+    a.x < b.right() && b.x < a.right() && a.y < b.bottom() && b.y < a.bottom()
+}
+
+fn scan(image: &GrayImage) -> Vec<Barcode> {
+    let (width, height) = image.dimensions();
+    // rxing reports "not found" as an error
+    detect_multiple_in_luma(image.as_raw().clone(), width, height)
+        .map(|results| {
+            results
+                .iter()
+                .map(|result| Barcode::from_result(result, width, height))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Decode every code in the image, top to bottom and left to right
+pub fn decode_barcodes(image: &GrayImage) -> Vec<Barcode> {
+    let mut barcodes = scan(image);
+
+    let mut inverted = image.clone();
+    image::imageops::invert(&mut inverted);
+    for barcode in scan(&inverted) {
+        let seen = barcodes.iter().any(|found| {
+            found.kind == barcode.kind
+                && found.text == barcode.text
+                && overlaps(&found.bbox, &barcode.bbox)
+        });
+        if !seen {
+            barcodes.push(barcode);
+        }
+    }
+
+    barcodes.sort_by_key(|barcode| (barcode.bbox.y, barcode.bbox.x));
+    barcodes
+}
+
+/// Decode every code in an image file
+pub fn decode_barcodes_in_image(image_path: &Path) -> AppResult<Vec<Barcode>> {
+    let image = image::open(image_path)
+        .map_err(|e| format!("Failed to open image: {}", e))?
+        .to_luma8();
+    Ok(decode_barcodes(&image))
+}
+
+/// Decode an image file and join the payloads, one per line
+pub fn scan_text_from_image(image_path: &Path) -> AppResult<String> {
+    let barcodes = decode_barcodes_in_image(image_path)?;
+    if barcodes.is_empty() {
+        return Err("No QR code or barcode found".to_string());
+    }
+    Ok(barcodes
+        .into_iter()
+        .map(|barcode| barcode.text)
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{imageops, Luma};
+    use rxing::{MultiFormatWriter, Writer};
+
+    /// Render a code with rxing's encoder, with a white margin around it
+    fn fixture(format: BarcodeFormat, contents: &str, width: i32, height: i32) -> GrayImage {
+        let matrix = MultiFormatWriter
+            .encode(contents, &format, width, height)
+            .expect("encode fixture");
+        let margin = 24;
+        let mut image = GrayImage::from_pixel(
+            matrix.getWidth() + margin * 2,
+            matrix.getHeight() + margin * 2,
+            Luma([255]),
+        );
+        for y in 0..matrix.getHeight() {
+            for x in 0..matrix.getWidth() {
+                if matrix.get(x, y) {
+                    image.put_pixel(x + margin, y + margin, Luma([0]));
+                }
+            }
+        }
+        image
+    }
+
+    fn contains(rect: &PixelRect, x: u32, y: u32) -> bool {
+        x >= rect.x && x < rect.right() && y >= rect.y && y < rect.bottom()
+    }
+
+    #[test]
+    fn test_decodes_2d_and_1d_symbologies() {
+        let cases = [
+            (
+                BarcodeFormat::QR_CODE,
+                "https://bettershot.site/?ref=qr",
+                BarcodeKind::QrCode,
+                240,
+                240,
+            ),
+            (
+                BarcodeFormat::DATA_MATRIX,
+                "SN-2048-BETTER",
+                BarcodeKind::DataMatrix,
+                160,
+                160,
+            ),
+            (
+                BarcodeFormat::CODE_128,
+                "BETTER-SHOT-42",
+                BarcodeKind::Code128,
+                600,
+                120,
+            ),
+            (
+                BarcodeFormat::EAN_13,
+                "5901234123457",
+                BarcodeKind::Ean13,
+                480,
+                120,
+            ),
+        ];
+
+        for (format, contents, kind, width, height) in cases {
+            let barcodes = decode_barcodes(&fixture(format, contents, width, height));
+
+            assert_eq!(barcodes.len(), 1, "{:?}", kind);
+            assert_eq!(barcodes[0].kind, kind);
+            assert_eq!(barcodes[0].text, contents);
+        }
+    }
+
+    #[test]
+    fn test_reports_positions_of_several_codes() {
+        let qr = fixture(BarcodeFormat::QR_CODE, "left", 200, 200);
+        let code128 = fixture(BarcodeFormat::CODE_128, "right-1234", 400, 100);
+        let mut canvas = GrayImage::from_pixel(900, 400, Luma([255]));
+        imageops::overlay(&mut canvas, &qr, 20, 150);
+        imageops::overlay(&mut canvas, &code128, 420, 30);
+
+        let barcodes = decode_barcodes(&canvas);
+
+        assert_eq!(barcodes.len(), 2);
+        // The barcode sits higher, so it comes first
+        assert_eq!(barcodes[0].text, "right-1234");
+        assert_eq!(barcodes[1].text, "left");
+        let placed = PixelRect {
+            x: 420,
+            y: 30,
+            width: code128.width(),
+            height: code128.height(),
+        };
+        let bbox = barcodes[0].bbox;
+        assert!(contains(
+            &placed,
+            bbox.x + bbox.width / 2,
+            bbox.y + bbox.height / 2
+        ));
+        let placed = PixelRect {
+            x: 20,
+            y: 150,
+            width: qr.width(),
+            height: qr.height(),
+        };
+        let bbox = barcodes[1].bbox;
+        assert!(contains(
+            &placed,
+            bbox.x + bbox.width / 2,
+            bbox.y + bbox.height / 2
+        ));
+        assert!(barcodes[1].points.len() >= 3);
+    }
+
+    #[test]
+    fn test_decodes_light_on_dark_codes() {
+        let mut image = fixture(BarcodeFormat::QR_CODE, "dark mode", 200, 200);
+        imageops::invert(&mut image);
+
+        let barcodes = decode_barcodes(&image);
+
+        assert_eq!(barcodes.len(), 1);
+        assert_eq!(barcodes[0].text, "dark mode");
+    }
+
+    #[test]
+    fn test_blank_image_has_no_codes() {
+        let image = GrayImage::from_pixel(300, 200, Luma([255]));
+        assert!(decode_barcodes(&image).is_empty());
+    }
+}
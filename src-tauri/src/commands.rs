@@ -1,23 +1,71 @@
 //! Tauri commands module
 
-use std::path::PathBuf;
+use image::DynamicImage;
+use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
-use std::sync::Mutex;
 use tauri::{AppHandle, Emitter, Manager};
 
 #[cfg(target_os = "macos")]
 use objc2::msg_send;
+#[cfg(target_os = "macos")]
 use objc2_app_kit::NSWindow;
 
+use crate::backend::{capture_backend, diagnostics, CaptureBackend, Diagnostics};
+use crate::barcode::{decode_barcodes_in_image, scan_text_from_image, Barcode};
 use crate::clipboard::{copy_image_to_clipboard, copy_text_to_clipboard};
-use crate::image::{copy_screenshot_to_dir, crop_image, render_image_with_effects, save_base64_image, CropRegion, RenderSettings};
-use crate::ocr::recognize_text_from_image;
+use crate::contrast::{audit_image_contrast, ContrastOptions, ContrastReport};
+use crate::coordinator::{
+    cancel_captures, capture_status, CaptureJob, CaptureKind, CaptureState, CaptureStatus,
+};
+use crate::countdown::{cancel_pending_capture, wait_for_capture_delay};
+use crate::cursor::CursorOverlay;
+use crate::elements::detect_elements;
+use crate::events::{
+    abandon_deferred, take_deferred, CaptureError, CaptureErrorCode, CaptureMode, CaptureOutput,
+    CaptureTracker, ClipboardContent, ProcessingOperation,
+};
+use crate::frames::{get_frame, remove_frames};
+use crate::image::{
+    crop_frame, render_image_with_effects, save_base64_image, save_image, CropRegion,
+    RenderSettings,
+};
+use crate::interval::{
+    start_interval_session as start_session, stop_interval_session as stop_session,
+    IntervalSessionOptions, IntervalSessionStarted,
+};
+use crate::monitors::{track_selector_monitors, untrack_selector_monitors};
+use crate::ocr::{recognize_image, recognize_text_from_image, OcrOptions, OcrResult};
+use crate::ocr_export::{export_ocr_result, OcrExportFormat};
+use crate::ocr_layout::{OcrLayout, OcrTextFormat};
+use crate::ocr_table::{extract_table_from_image, OcrTable, TableFormat};
+use crate::privacy::{mask_primary_monitor_file, SensitiveWindows};
+use crate::recording::{
+    set_recording_paused, start_recording as start_recorder, stop_recording as stop_recorder,
+    RecordingOptions,
+};
+use crate::redaction::{
+    find_redactions_in_image, redact_image_file, RedactionRules, RedactionSuggestion,
+};
+use crate::regions::{
+    capture_remembered_region, delete_saved_region as delete_region, find_saved_region,
+    last_region, record_region, region_history, save_region as store_region, saved_regions,
+    CapturedRegion, SavedRegion,
+};
+use crate::scroll::{run_scroll_capture, stop_active_scroll_capture, ScrollCaptureOptions};
 use crate::screenshot::{
-    capture_all_monitors as capture_monitors, capture_primary_monitor, MonitorShot,
+    capture_all_monitors as capture_monitors, capture_monitor_frames, capture_primary_monitor,
+    monitor_layout, MonitorGeometry, MonitorShot,
+};
+use crate::selector::{
+    expect_ready, forget as forget_selector, stage_payload, take_when_ready, wait_until_delivered,
+};
+use crate::utils::{
+    ensure_dir, generate_filename, generate_id, get_desktop_path, get_timestamp, move_file,
+    private_capture_path,
+};
+use crate::window_capture::{
+    active_window, capture_window_transparent, list_windows as list_all_windows, WindowInfo,
 };
-use crate::utils::{generate_filename, get_desktop_path};
-
-static SCREENCAPTURE_LOCK: Mutex<()> = Mutex::new(());
 
 #[tauri::command]
 pub async fn move_window_to_active_space(app_handle: AppHandle) -> Result<(), String> {
@@ -42,6 +90,8 @@ pub async fn move_window_to_active_space(app_handle: AppHandle) -> Result<(), St
             })
             .map_err(|e| e.to_string())?;
     }
+    #[cfg(not(target_os = "macos"))]
+    let _ = app_handle;
     Ok(())
 }
 
@@ -56,14 +106,30 @@ pub async fn capture_once(
     app_handle: AppHandle,
     save_dir: String,
     copy_to_clip: bool,
-) -> Result<String, String> {
-    let screenshot_path = capture_primary_monitor(app_handle).await?;
-    let screenshot_path_str = screenshot_path.to_string_lossy().to_string();
-
-    let saved_path = copy_screenshot_to_dir(&screenshot_path_str, &save_dir)?;
+    delay_secs: Option<u32>,
+) -> Result<String, CaptureError> {
+    let tracker = CaptureTracker::start(&app_handle, CaptureMode::PrimaryMonitor)?;
+    let result: Result<String, CaptureError> = async {
+        wait_for_capture_delay(&app_handle, tracker.capture_id(), delay_secs).await?;
+
+        let sensitive = detect_sensitive_windows(&app_handle, &tracker)?;
+        let saved_path = tauri::async_runtime::spawn_blocking(move || {
+            let image = capture_primary_monitor(&sensitive, CursorOverlay::Hidden)?;
+            save_image(&DynamicImage::ImageRgba8(image), &save_dir, "shot")
+                .map_err(|e| CaptureError::new(CaptureErrorCode::SaveFailed, e))
+        })
+        .await
+        .map_err(|e| format!("Capture task failed: {}", e))??;
+        Ok(saved_path)
+    }
+    .await;
+    let saved_path = tracker.finish(result, |path| vec![CaptureOutput::from_file(path, None)])?;
 
     if copy_to_clip {
         copy_image_to_clipboard(&saved_path)?;
+        tracker.copied(ClipboardContent::Image {
+            path: saved_path.clone(),
+        });
     }
 
     Ok(saved_path)
@@ -72,29 +138,204 @@ pub async fn capture_once(
 /// Capture all monitors with geometry info
 #[tauri::command]
 pub async fn capture_all_monitors(
-    _app_handle: AppHandle,
+    app_handle: AppHandle,
     save_dir: String,
-) -> Result<Vec<MonitorShot>, String> {
-    capture_monitors(&save_dir)
+    delay_secs: Option<u32>,
+    include_cursor: Option<bool>,
+    cursor_halo: Option<bool>,
+) -> Result<Vec<MonitorShot>, CaptureError> {
+    let tracker = CaptureTracker::start(&app_handle, CaptureMode::AllMonitors)?;
+    let result: Result<_, CaptureError> = async {
+        wait_for_capture_delay(&app_handle, tracker.capture_id(), delay_secs).await?;
+
+        let sensitive = detect_sensitive_windows(&app_handle, &tracker)?;
+        let cursor = CursorOverlay::from_flags(include_cursor, cursor_halo);
+        Ok(tauri::async_runtime::spawn_blocking(move || {
+            capture_monitors(&save_dir, cursor, &sensitive)
+        })
+        .await
+        .map_err(|e| format!("Capture task failed: {}", e))??)
+    }
+    .await;
+
+    tracker.finish(result, |shots| {
+        shots
+            .iter()
+            .map(|shot| CaptureOutput {
+                path: shot.path.clone(),
+                width: shot.width,
+                height: shot.height,
+                scale_factor: Some(shot.scale_factor),
+                monitor_id: Some(shot.id),
+            })
+            .collect()
+    })
 }
 
-/// Crop a region from a screenshot
+/// Crop a region from an in-memory monitor frame and add it to the region history
+///
+/// `capture_id` is the id sent to the selector by `open_region_selector`;
+/// the capture's `capture-saved` or `capture-failed` event is emitted here.
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn capture_region(
-    screenshot_path: String,
+    app_handle: AppHandle,
+    frame_id: String,
     x: u32,
     y: u32,
     width: u32,
     height: u32,
     save_dir: String,
-) -> Result<String, String> {
-    let region = CropRegion {
-        x,
-        y,
-        width,
-        height,
+    capture_id: Option<String>,
+) -> Result<String, CaptureError> {
+    let tracker = capture_id.as_deref().and_then(take_deferred);
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        let frame =
+            get_frame(&frame_id).map_err(|e| CaptureError::new(CaptureErrorCode::NotFound, e))?;
+        let region = CropRegion {
+            x,
+            y,
+            width,
+            height,
+        };
+        let (path, cropped) = crop_frame(&frame.image, region, &save_dir)?;
+        Ok((frame, path, cropped))
+    })
+    .await
+    .map_err(|e| CaptureError::from(format!("Crop task failed: {}", e)))
+    .and_then(|result| result);
+    let (frame, path, cropped) = match tracker {
+        Some(tracker) => tracker.finish(result, |(frame, path, cropped)| {
+            vec![CaptureOutput {
+                path: path.clone(),
+                width: cropped.width,
+                height: cropped.height,
+                scale_factor: Some(frame.scale_factor),
+                monitor_id: Some(frame.monitor_id),
+            }]
+        })?,
+        None => result?,
+    };
+
+    let history_entry = CapturedRegion {
+        monitor_id: frame.monitor_id,
+        x: cropped.x,
+        y: cropped.y,
+        width: cropped.width,
+        height: cropped.height,
+        scale_factor: frame.scale_factor,
+        captured_at: get_timestamp()?,
     };
-    crop_image(&screenshot_path, region, &save_dir)
+    if let Err(e) = record_region(&app_handle, history_entry) {
+        eprintln!("Failed to record region history: {}", e);
+    }
+
+    Ok(path)
+}
+
+/// Capture the most recently selected region again, without the selector
+#[tauri::command]
+pub async fn recapture_last_region(
+    app_handle: AppHandle,
+    save_dir: String,
+    delay_secs: Option<u32>,
+    include_cursor: Option<bool>,
+    cursor_halo: Option<bool>,
+) -> Result<String, CaptureError> {
+    let tracker = CaptureTracker::start(&app_handle, CaptureMode::LastRegion)?;
+    let result: Result<_, CaptureError> = async {
+        let region = last_region(&app_handle)
+            .map_err(|e| CaptureError::new(CaptureErrorCode::NotFound, e))?;
+        wait_for_capture_delay(&app_handle, tracker.capture_id(), delay_secs).await?;
+
+        let sensitive = detect_sensitive_windows(&app_handle, &tracker)?;
+        let cursor = CursorOverlay::from_flags(include_cursor, cursor_halo);
+        let save_dir = save_dir.clone();
+        Ok(tauri::async_runtime::spawn_blocking(move || {
+            let path = capture_remembered_region(&region, &save_dir, cursor, &sensitive)?;
+            Ok::<_, String>((region, path))
+        })
+        .await
+        .map_err(|e| format!("Capture task failed: {}", e))??)
+    }
+    .await;
+
+    tracker
+        .finish(result, remembered_region_outputs)
+        .map(|(_, path)| path)
+}
+
+/// Capture a named saved region
+#[tauri::command]
+pub async fn capture_saved_region(
+    app_handle: AppHandle,
+    name: String,
+    save_dir: String,
+    delay_secs: Option<u32>,
+    include_cursor: Option<bool>,
+    cursor_halo: Option<bool>,
+) -> Result<String, CaptureError> {
+    let tracker = CaptureTracker::start(&app_handle, CaptureMode::SavedRegion)?;
+    let result: Result<_, CaptureError> = async {
+        let region = find_saved_region(&app_handle, &name)
+            .map_err(|e| CaptureError::new(CaptureErrorCode::NotFound, e))?;
+        wait_for_capture_delay(&app_handle, tracker.capture_id(), delay_secs).await?;
+
+        let sensitive = detect_sensitive_windows(&app_handle, &tracker)?;
+        let cursor = CursorOverlay::from_flags(include_cursor, cursor_halo);
+        let save_dir = save_dir.clone();
+        Ok(tauri::async_runtime::spawn_blocking(move || {
+            let path = capture_remembered_region(&region, &save_dir, cursor, &sensitive)?;
+            Ok::<_, String>((region, path))
+        })
+        .await
+        .map_err(|e| format!("Capture task failed: {}", e))??)
+    }
+    .await;
+
+    tracker
+        .finish(result, remembered_region_outputs)
+        .map(|(_, path)| path)
+}
+
+/// The `capture-saved` outputs of a remembered region capture
+fn remembered_region_outputs((region, path): &(CapturedRegion, String)) -> Vec<CaptureOutput> {
+    vec![CaptureOutput {
+        monitor_id: Some(region.monitor_id),
+        ..CaptureOutput::from_file(path, Some(region.scale_factor))
+    }]
+}
+
+/// Recently captured regions, newest first
+#[tauri::command]
+pub async fn get_region_history(app_handle: AppHandle) -> Result<Vec<CapturedRegion>, String> {
+    region_history(&app_handle)
+}
+
+/// All named saved regions
+#[tauri::command]
+pub async fn list_saved_regions(app_handle: AppHandle) -> Result<Vec<SavedRegion>, String> {
+    saved_regions(&app_handle)
+}
+
+/// Save a region under a name; saves the last captured region if none is given
+#[tauri::command]
+pub async fn save_region(
+    app_handle: AppHandle,
+    name: String,
+    region: Option<CapturedRegion>,
+) -> Result<(), String> {
+    let region = match region {
+        Some(region) => region,
+        None => last_region(&app_handle)?,
+    };
+    store_region(&app_handle, &name, region)
+}
+
+/// Delete a named saved region
+#[tauri::command]
+pub async fn delete_saved_region(app_handle: AppHandle, name: String) -> Result<(), String> {
+    delete_region(&app_handle, &name)
 }
 
 /// Render image with effects using Rust (optimized for blur)
@@ -141,19 +382,6 @@ pub async fn get_temp_directory() -> Result<String, String> {
         .ok_or_else(|| "Failed to convert temp directory path to string".to_string())
 }
 
-/// Check if screencapture is already running
-fn is_screencapture_running() -> bool {
-    let output = Command::new("pgrep")
-        .arg("-x")
-        .arg("screencapture")
-        .output();
-
-    match output {
-        Ok(o) => o.status.success(),
-        Err(_) => false,
-    }
-}
-
 /// Check screen recording permission by attempting a minimal test
 /// This helps macOS recognize the permission is already granted
 fn check_and_activate_permission() -> Result<(), String> {
@@ -196,97 +424,289 @@ fn check_and_activate_permission() -> Result<(), String> {
     }
 }
 
-/// Capture screenshot using macOS native screencapture with interactive selection
-/// This properly handles Screen Recording permissions through the system
-#[tauri::command]
-pub async fn native_capture_interactive(save_dir: String) -> Result<String, String> {
-    let _lock = SCREENCAPTURE_LOCK
-        .lock()
-        .map_err(|e| format!("Failed to acquire lock: {}", e))?;
-
-    if is_screencapture_running() {
-        return Err("Another screenshot capture is already in progress".to_string());
+/// Run the permission check off the async runtime
+async fn ensure_screen_recording_permission() -> Result<(), CaptureError> {
+    // Elsewhere access is granted per capture by the backend itself
+    if capture_backend() != CaptureBackend::MacOs {
+        return Ok(());
     }
 
-    check_and_activate_permission().map_err(|e| {
-        format!("Permission check failed: {}. Please ensure Screen Recording permission is granted in System Settings > Privacy & Security > Screen Recording.", e)
-    })?;
+    tauri::async_runtime::spawn_blocking(check_and_activate_permission)
+        .await
+        .map_err(|e| format!("Permission check failed: {}", e))?
+        .map_err(|e| {
+            CaptureError::new(
+                CaptureErrorCode::PermissionDenied,
+                format!("Permission check failed: {}. Please ensure Screen Recording permission is granted in System Settings > Privacy & Security > Screen Recording.", e),
+            )
+        })
+}
 
-    let filename = generate_filename("screenshot", "png")?;
-    let save_path = PathBuf::from(&save_dir);
-    let screenshot_path = save_path.join(&filename);
-    let path_str = screenshot_path.to_string_lossy().to_string();
+/// `screencapture` exits without writing a file when the user presses Escape
+fn screenshot_cancelled() -> CaptureError {
+    CaptureError::new(
+        CaptureErrorCode::Cancelled,
+        "Screenshot was cancelled or failed",
+    )
+}
 
+/// Run `screencapture` for a coordinated capture and check it wrote the file
+///
+/// Any partial output is removed on failure.
+#[cfg(not(target_os = "linux"))]
+async fn run_screencapture(
+    job: &CaptureJob,
+    args: &[&str],
+    screenshot_path: &Path,
+) -> Result<(), CaptureError> {
     let child = Command::new("screencapture")
-        .arg("-i")
-        .arg("-x")
-        .arg(&path_str)
+        .args(args)
+        .arg(screenshot_path)
         .stdin(Stdio::null())
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
         .spawn()
         .map_err(|e| format!("Failed to run screencapture: {}", e))?;
 
-    let output = child
-        .wait_with_output()
-        .map_err(|e| format!("Failed to wait for screencapture: {}", e))?;
+    let output = match job.wait_for_process(child).await {
+        Ok(output) => output,
+        Err(e) => {
+            let _ = std::fs::remove_file(screenshot_path);
+            return Err(e);
+        }
+    };
 
     if !output.status.success() {
         if screenshot_path.exists() {
-            let _ = std::fs::remove_file(&screenshot_path);
+            let _ = std::fs::remove_file(screenshot_path);
         }
         let stderr = String::from_utf8_lossy(&output.stderr);
         if stderr.contains("permission")
             || stderr.contains("denied")
             || stderr.contains("not authorized")
         {
-            return Err("Screen Recording permission required. Please grant permission in System Settings > Privacy & Security > Screen Recording and restart the app.".to_string());
+            return Err(CaptureError::new(
+                CaptureErrorCode::PermissionDenied,
+                "Screen Recording permission required. Please grant permission in System Settings > Privacy & Security > Screen Recording and restart the app.",
+            ));
         }
-        return Err("Screenshot was cancelled or failed".to_string());
+        return Err(screenshot_cancelled());
     }
 
     if screenshot_path.exists() {
-        Ok(path_str)
+        Ok(())
     } else {
-        Err("Screenshot was cancelled or failed".to_string())
+        Err(screenshot_cancelled())
     }
 }
 
-/// Capture full screen using macOS native screencapture
-#[tauri::command]
-pub async fn native_capture_fullscreen(save_dir: String) -> Result<String, String> {
-    let _lock = SCREENCAPTURE_LOCK
-        .lock()
-        .map_err(|e| format!("Failed to acquire lock: {}", e))?;
+/// Take a coordinated capture with the Linux equivalent of `screencapture`
+///
+/// Takes the same arguments, so callers don't need to know the backend.
+#[cfg(target_os = "linux")]
+async fn run_screencapture(
+    job: &CaptureJob,
+    args: &[&str],
+    screenshot_path: &Path,
+) -> Result<(), CaptureError> {
+    match capture_backend() {
+        CaptureBackend::WaylandPortal => run_portal_screenshot(job, args, screenshot_path).await,
+        _ => run_x11_screenshot(args, screenshot_path).await,
+    }
+}
 
-    if is_screencapture_running() {
-        return Err("Another screenshot capture is already in progress".to_string());
+/// `screencapture` arguments that let the user pick what to capture
+#[cfg(target_os = "linux")]
+fn is_interactive(args: &[&str]) -> bool {
+    args.iter().any(|arg| matches!(*arg, "-i" | "-w" | "-W"))
+}
+
+/// Take a coordinated capture on X11
+///
+/// Only the primary monitor can be taken this way. There is no picker for
+/// a region or window outside the region selector, so those captures fail
+/// with `Unsupported`.
+#[cfg(target_os = "linux")]
+async fn run_x11_screenshot(args: &[&str], screenshot_path: &Path) -> Result<(), CaptureError> {
+    if is_interactive(args) {
+        return Err(CaptureError::new(
+            CaptureErrorCode::Unsupported,
+            "Picking a region or window for this capture isn't available on X11; use a Wayland session",
+        ));
     }
 
-    check_and_activate_permission().map_err(|e| {
-        format!("Permission check failed: {}. Please ensure Screen Recording permission is granted in System Settings > Privacy & Security > Screen Recording.", e)
-    })?;
+    let cursor = if args.contains(&"-C") {
+        CursorOverlay::Visible
+    } else {
+        CursorOverlay::Hidden
+    };
+    let path = screenshot_path.to_path_buf();
+    tauri::async_runtime::spawn_blocking(move || {
+        let image = capture_primary_monitor(&SensitiveWindows::default(), cursor)?;
+        image.save(&path).map_err(|e| {
+            CaptureError::new(
+                CaptureErrorCode::SaveFailed,
+                format!("Failed to save screenshot: {}", e),
+            )
+        })
+    })
+    .await
+    .map_err(|e| format!("Capture task failed: {}", e))?
+}
 
-    let filename = generate_filename("screenshot", "png")?;
-    let save_path = PathBuf::from(&save_dir);
-    let screenshot_path = save_path.join(&filename);
-    let path_str = screenshot_path.to_string_lossy().to_string();
+/// Take a coordinated capture through the desktop portal instead
+///
+/// `screencapture` arguments that let the user pick what to capture make the
+/// portal request interactive; the rest have no portal equivalent.
+#[cfg(target_os = "linux")]
+async fn run_portal_screenshot(
+    job: &CaptureJob,
+    args: &[&str],
+    screenshot_path: &Path,
+) -> Result<(), CaptureError> {
+    use crate::portal::RequestError;
+
+    let interactive = is_interactive(args);
+    let mut request = tauri::async_runtime::spawn_blocking(move || {
+        crate::portal::Portal::session()?.screenshot(interactive)
+    })
+    .await
+    .map_err(|e| format!("Portal screenshot failed: {}", e))??;
+
+    // Dropping an unanswered request on cancel or timeout dismisses its dialog
+    let portal_path = job
+        .wait_until(move || {
+            request.try_response().map_err(|e| match e {
+                RequestError::Cancelled => {
+                    CaptureError::new(CaptureErrorCode::Cancelled, e.to_string())
+                }
+                RequestError::Failed(message) => CaptureError::from(message),
+            })
+        })
+        .await?;
+    Ok(crate::portal::move_screenshot(
+        &portal_path,
+        screenshot_path,
+    )?)
+}
 
-    let status = Command::new("screencapture")
-        .arg("-x")
-        .arg(&path_str)
-        .status()
-        .map_err(|e| format!("Failed to run screencapture: {}", e))?;
+/// Capture screenshot using macOS native screencapture with interactive selection
+/// This properly handles Screen Recording permissions through the system
+#[tauri::command]
+pub async fn native_capture_interactive(
+    app_handle: AppHandle,
+    save_dir: String,
+    delay_secs: Option<u32>,
+) -> Result<String, CaptureError> {
+    let tracker = CaptureTracker::start(&app_handle, CaptureMode::NativeInteractive)?;
+    let result: Result<String, CaptureError> = async {
+        wait_for_capture_delay(&app_handle, tracker.capture_id(), delay_secs).await?;
+
+        let job = CaptureJob::acquire(&app_handle, CaptureKind::Interactive).await?;
+        ensure_screen_recording_permission().await?;
 
-    if !status.success() {
-        return Err("Screenshot failed".to_string());
+        let filename = generate_filename("screenshot", "png")?;
+        let screenshot_path = PathBuf::from(&save_dir).join(&filename);
+
+        run_screencapture(&job, &["-i", "-x"], &screenshot_path).await?;
+        Ok(screenshot_path.to_string_lossy().to_string())
     }
+    .await;
 
-    if screenshot_path.exists() {
+    tracker.finish(result, |path| vec![CaptureOutput::from_file(path, None)])
+}
+
+/// Capture a region and cover the sensitive text OCR finds in it before
+/// returning the file
+///
+/// Fails rather than hand back an unredacted capture if recognition fails.
+/// The capture is redacted in a private temporary file, so the unredacted
+/// image never reaches `save_dir`.
+#[tauri::command]
+pub async fn native_capture_redacted(
+    app_handle: AppHandle,
+    save_dir: String,
+    delay_secs: Option<u32>,
+) -> Result<String, CaptureError> {
+    let tracker = CaptureTracker::start(&app_handle, CaptureMode::RedactedRegion)?;
+    let result: Result<String, CaptureError> = async {
+        let options = OcrOptions::load(&app_handle)?;
+        let rules = RedactionRules::load(&app_handle)?;
+        wait_for_capture_delay(&app_handle, tracker.capture_id(), delay_secs).await?;
+
+        let job = CaptureJob::acquire(&app_handle, CaptureKind::Interactive).await?;
+        ensure_screen_recording_permission().await?;
+
+        let capture_path = private_capture_path("screenshot", "png")?;
+        let _temp_file = TempFile(capture_path.clone());
+
+        run_screencapture(&job, &["-i", "-x"], &capture_path).await?;
+        job.set_state(CaptureState::Processing);
+
+        let redact_path = capture_path.clone();
+        tauri::async_runtime::spawn_blocking(move || {
+            redact_image_file(&redact_path, &options, &rules)
+        })
+        .await
+        .map_err(|e| format!("Redaction task failed: {}", e))?
+        .map_err(|e| format!("Redaction failed: {}", e))?;
+
+        let path_str = move_to_save_dir(&capture_path, &save_dir, "screenshot")?;
+        tracker.processed(
+            ProcessingOperation::Redaction,
+            Some(path_str.clone()),
+            Some(path_str.clone()),
+        );
+        Ok(path_str)
+    }
+    .await;
+
+    tracker.finish(result, |path| vec![CaptureOutput::from_file(path, None)])
+}
+
+/// Capture full screen using macOS native screencapture
+#[tauri::command]
+pub async fn native_capture_fullscreen(
+    app_handle: AppHandle,
+    save_dir: String,
+    delay_secs: Option<u32>,
+    include_cursor: Option<bool>,
+) -> Result<String, CaptureError> {
+    let tracker = CaptureTracker::start(&app_handle, CaptureMode::NativeFullscreen)?;
+    let result: Result<String, CaptureError> = async {
+        wait_for_capture_delay(&app_handle, tracker.capture_id(), delay_secs).await?;
+
+        let job = CaptureJob::acquire(&app_handle, CaptureKind::Fullscreen).await?;
+        ensure_screen_recording_permission().await?;
+
+        let sensitive = detect_sensitive_windows(&app_handle, &tracker)?;
+        let capture_path = private_capture_path("screenshot", "png")?;
+        let _temp_file = TempFile(capture_path.clone());
+
+        let mut args = vec!["-x"];
+        if include_cursor.unwrap_or(false) {
+            args.push("-C");
+        }
+        run_screencapture(&job, &args, &capture_path).await?;
+
+        // Masked before it is moved, so the save directory never sees it unmasked
+        if !sensitive.is_empty() {
+            job.set_state(CaptureState::Processing);
+            mask_primary_monitor_file(&capture_path.to_string_lossy(), &sensitive)?;
+        }
+        let path_str = move_to_save_dir(&capture_path, &save_dir, "screenshot")?;
+        if !sensitive.is_empty() {
+            tracker.processed(
+                ProcessingOperation::SensitiveWindowMask,
+                Some(path_str.clone()),
+                Some(path_str.clone()),
+            );
+        }
         Ok(path_str)
-    } else {
-        Err("Screenshot failed".to_string())
     }
+    .await;
+
+    tracker.finish(result, |path| vec![CaptureOutput::from_file(path, None)])
 }
 
 /// Play the macOS screenshot sound using CoreAudio
@@ -391,137 +811,321 @@ pub async fn get_mouse_position() -> Result<(f64, f64), String> {
     Ok((x, y))
 }
 
-/// Capture specific window using macOS native screencapture
+/// Capture a window the user picks with macOS native screencapture
+///
+/// The Wayland portal lets the user pick the window too. X11 and Windows
+/// have no picker, so the active window is captured with xcap instead.
 #[tauri::command]
-pub async fn native_capture_window(save_dir: String) -> Result<String, String> {
-    let _lock = SCREENCAPTURE_LOCK
-        .lock()
-        .map_err(|e| format!("Failed to acquire lock: {}", e))?;
-
-    if is_screencapture_running() {
-        return Err("Another screenshot capture is already in progress".to_string());
-    }
-
-    check_and_activate_permission().map_err(|e| {
-        format!("Permission check failed: {}. Please ensure Screen Recording permission is granted in System Settings > Privacy & Security > Screen Recording.", e)
-    })?;
-
-    let filename = generate_filename("screenshot", "png")?;
-    let save_path = PathBuf::from(&save_dir);
-    let screenshot_path = save_path.join(&filename);
-    let path_str = screenshot_path.to_string_lossy().to_string();
-
-    let child = Command::new("screencapture")
-        .arg("-w")
-        .arg("-x")
-        .arg(&path_str)
-        .stdin(Stdio::null())
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn()
-        .map_err(|e| format!("Failed to run screencapture: {}", e))?;
+pub async fn native_capture_window(
+    app_handle: AppHandle,
+    save_dir: String,
+    delay_secs: Option<u32>,
+    window_shadow: Option<bool>,
+) -> Result<String, CaptureError> {
+    let mut tracker = CaptureTracker::start(&app_handle, CaptureMode::NativeWindow)?;
+    let result: Result<String, CaptureError> = async {
+        wait_for_capture_delay(&app_handle, tracker.capture_id(), delay_secs).await?;
+
+        let job = CaptureJob::acquire(&app_handle, CaptureKind::Window).await?;
+        ensure_screen_recording_permission().await?;
+
+        if matches!(
+            capture_backend(),
+            CaptureBackend::X11 | CaptureBackend::Windows
+        ) {
+            let window =
+                active_window().map_err(|e| CaptureError::new(CaptureErrorCode::NotFound, e))?;
+            tracker.set_window((&window).into());
+            let shadow = window_shadow.unwrap_or(true);
+            let save_dir = save_dir.clone();
+            return Ok(tauri::async_runtime::spawn_blocking(move || {
+                capture_window_transparent(window.id, shadow, &save_dir)
+            })
+            .await
+            .map_err(|e| format!("Capture task failed: {}", e))??);
+        }
 
-    let output = child
-        .wait_with_output()
-        .map_err(|e| format!("Failed to wait for screencapture: {}", e))?;
+        let filename = generate_filename("screenshot", "png")?;
+        let screenshot_path = PathBuf::from(&save_dir).join(&filename);
 
-    if !output.status.success() {
-        if screenshot_path.exists() {
-            let _ = std::fs::remove_file(&screenshot_path);
+        let mut args = vec!["-w", "-x"];
+        if !window_shadow.unwrap_or(true) {
+            args.push("-o");
         }
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        if stderr.contains("permission")
-            || stderr.contains("denied")
-            || stderr.contains("not authorized")
-        {
-            return Err("Screen Recording permission required. Please grant permission in System Settings > Privacy & Security > Screen Recording and restart the app.".to_string());
-        }
-        return Err("Screenshot was cancelled or failed".to_string());
+        run_screencapture(&job, &args, &screenshot_path).await?;
+        Ok(screenshot_path.to_string_lossy().to_string())
     }
+    .await;
 
-    if screenshot_path.exists() {
-        Ok(path_str)
-    } else {
-        Err("Screenshot was cancelled or failed".to_string())
-    }
+    tracker.finish(result, |path| vec![CaptureOutput::from_file(path, None)])
 }
 
-/// Capture region and perform OCR, copying text to clipboard
-#[tauri::command]
-pub async fn native_capture_ocr_region(save_dir: String) -> Result<String, String> {
-    {
-        let _lock = SCREENCAPTURE_LOCK
-            .lock()
-            .map_err(|e| format!("Failed to acquire lock: {}", e))?;
-
-        if is_screencapture_running() {
-            return Err("Another screenshot capture is already in progress".to_string());
-        }
+/// Let the user select a region, turn the capture into text and copy the
+/// text to the clipboard
+///
+/// `recognizer` loads the settings it needs once the capture is tracked and
+/// returns the function that recognizes the text.
+async fn capture_region_text<R>(
+    app_handle: &AppHandle,
+    save_dir: &str,
+    delay_secs: Option<u32>,
+    mode: CaptureMode,
+    operation: ProcessingOperation,
+    recognizer: impl FnOnce(&AppHandle) -> Result<R, String>,
+) -> Result<String, CaptureError>
+where
+    R: FnOnce(&str) -> Result<String, String> + Send + 'static,
+{
+    let tracker = CaptureTracker::start(app_handle, mode)?;
+    let result: Result<String, CaptureError> = async {
+        let recognize = recognizer(app_handle)?;
+        wait_for_capture_delay(app_handle, tracker.capture_id(), delay_secs).await?;
+
+        let job = CaptureJob::acquire(app_handle, CaptureKind::OcrRegion).await?;
+        ensure_screen_recording_permission().await?;
+
+        let filename = generate_filename("ocr_temp", "png")?;
+        let screenshot_path = PathBuf::from(save_dir).join(&filename);
+        let path_str = screenshot_path.to_string_lossy().to_string();
+        let _temp_file = TempFile(screenshot_path.clone());
+
+        run_screencapture(&job, &["-i", "-x"], &screenshot_path).await?;
+        job.set_state(CaptureState::Processing);
+
+        play_screenshot_sound().await.ok();
+
+        let recognize_path = path_str.clone();
+        let recognized_text =
+            tauri::async_runtime::spawn_blocking(move || recognize(&recognize_path))
+                .await
+                .map_err(|e| format!("Text recognition task failed: {}", e))??;
+        tracker.processed(operation, Some(path_str), None);
+
+        copy_text_to_clipboard(&recognized_text)
+            .map_err(|e| format!("Failed to copy text to clipboard: {}", e))?;
+        tracker.copied(ClipboardContent::Text {
+            characters: recognized_text.chars().count(),
+        });
 
-        check_and_activate_permission().map_err(|e| {
-            format!("Permission check failed: {}. Please ensure Screen Recording permission is granted in System Settings > Privacy & Security > Screen Recording.", e)
-        })?;
+        Ok(recognized_text)
     }
+    .await;
 
-    let filename = generate_filename("ocr_temp", "png")?;
-    let save_path = PathBuf::from(&save_dir);
-    let screenshot_path = save_path.join(&filename);
-    let path_str = screenshot_path.to_string_lossy().to_string();
-
-    let child = Command::new("screencapture")
-        .arg("-i")
-        .arg("-x")
-        .arg(&path_str)
-        .stdin(Stdio::null())
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn()
-        .map_err(|e| format!("Failed to run screencapture: {}", e))?;
-
-    let output = child
-        .wait_with_output()
-        .map_err(|e| format!("Failed to wait for screencapture: {}", e))?;
-
-    if !output.status.success() {
-        if screenshot_path.exists() {
-            let _ = std::fs::remove_file(&screenshot_path);
-        }
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        if stderr.contains("permission")
-            || stderr.contains("denied")
-            || stderr.contains("not authorized")
-        {
-            return Err("Screen Recording permission required. Please grant permission in System Settings > Privacy & Security > Screen Recording and restart the app.".to_string());
-        }
-        return Err("Screenshot was cancelled or failed".to_string());
+    if let Err(e) = &result {
+        tracker.failed(e);
     }
+    result
+}
 
-    if !screenshot_path.exists() {
-        return Err("Screenshot was cancelled or failed".to_string());
+/// Move a finished capture from its private temporary path into `save_dir`
+fn move_to_save_dir(
+    capture_path: &Path,
+    save_dir: &str,
+    prefix: &str,
+) -> Result<String, CaptureError> {
+    let save_failed = |e| CaptureError::new(CaptureErrorCode::SaveFailed, e);
+    let dir = PathBuf::from(save_dir);
+    ensure_dir(&dir).map_err(save_failed)?;
+    let path = dir.join(generate_filename(prefix, "png")?);
+    move_file(capture_path, &path).map_err(save_failed)?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+/// Find the sensitive windows to mask, reporting through `capture-processed`
+/// when masking is enabled but can't be done
+fn detect_sensitive_windows(
+    app_handle: &AppHandle,
+    tracker: &CaptureTracker,
+) -> Result<SensitiveWindows, String> {
+    let sensitive = SensitiveWindows::detect(app_handle)?;
+    if let Some(reason) = sensitive.unavailable() {
+        tracker.skipped(ProcessingOperation::SensitiveWindowMask, reason);
     }
+    Ok(sensitive)
+}
 
-    play_screenshot_sound().await.ok();
+/// Deletes a temporary capture when dropped, however the capture ended
+struct TempFile(PathBuf);
 
-    let recognized_text = recognize_text_from_image(&path_str)
-        .map_err(|e| format!("OCR failed: {}", e))?;
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
 
-    copy_text_to_clipboard(&recognized_text)
-        .map_err(|e| format!("Failed to copy text to clipboard: {}", e))?;
+/// Capture region and perform OCR, copying text to clipboard
+#[tauri::command]
+pub async fn native_capture_ocr_region(
+    app_handle: AppHandle,
+    save_dir: String,
+    delay_secs: Option<u32>,
+) -> Result<String, CaptureError> {
+    capture_region_text(
+        &app_handle,
+        &save_dir,
+        delay_secs,
+        CaptureMode::OcrRegion,
+        ProcessingOperation::Ocr,
+        |app_handle| {
+            let options = OcrOptions::load(app_handle)?;
+            let format = OcrTextFormat::load(app_handle)?;
+            Ok(move |path: &str| {
+                recognize_text_from_image(path, &options, format)
+                    .map_err(|e| format!("OCR failed: {}", e))
+            })
+        },
+    )
+    .await
+}
 
-    let _ = std::fs::remove_file(&screenshot_path);
+/// Capture region and extract a table from it, copying it as TSV, CSV or
+/// Markdown
+///
+/// `format` defaults to the table format setting.
+#[tauri::command]
+pub async fn native_capture_ocr_table(
+    app_handle: AppHandle,
+    save_dir: String,
+    delay_secs: Option<u32>,
+    format: Option<TableFormat>,
+) -> Result<String, CaptureError> {
+    capture_region_text(
+        &app_handle,
+        &save_dir,
+        delay_secs,
+        CaptureMode::OcrTable,
+        ProcessingOperation::Ocr,
+        |app_handle| {
+            let options = OcrOptions::load(app_handle)?;
+            let format = match format {
+                Some(format) => format,
+                None => TableFormat::load(app_handle)?,
+            };
+            Ok(move |path: &str| {
+                let table = extract_table_from_image(Path::new(path), &options)
+                    .map_err(|e| format!("Table extraction failed: {}", e))?;
+                Ok(table.to_format(format))
+            })
+        },
+    )
+    .await
+}
 
-    Ok(recognized_text)
+/// Capture region and decode the QR codes and barcodes in it, copying
+/// their text to clipboard
+#[tauri::command]
+pub async fn native_capture_scan_qr_region(
+    app_handle: AppHandle,
+    save_dir: String,
+    delay_secs: Option<u32>,
+) -> Result<String, CaptureError> {
+    capture_region_text(
+        &app_handle,
+        &save_dir,
+        delay_secs,
+        CaptureMode::ScanQrRegion,
+        ProcessingOperation::BarcodeScan,
+        |_| Ok(|path: &str| scan_text_from_image(Path::new(path))),
+    )
+    .await
 }
 
 /// Open region selector window with captured screenshots
-/// Captures all monitors and opens a fullscreen region selector window
+/// Captures all monitors into memory and opens a fullscreen region selector window
 #[tauri::command]
 pub async fn open_region_selector(
     app_handle: AppHandle,
     save_dir: String,
-) -> Result<(), String> {
-    // Capture all monitors
-    let monitor_shots = capture_monitors(&save_dir)?;
+    delay_secs: Option<u32>,
+    include_cursor: Option<bool>,
+    cursor_halo: Option<bool>,
+) -> Result<(), CaptureError> {
+    start_region_selector(
+        &app_handle,
+        CaptureMode::Region,
+        save_dir,
+        delay_secs,
+        CursorOverlay::from_flags(include_cursor, cursor_halo),
+    )
+    .await
+}
+
+/// Freeze the screens and open the region selector over the frozen frames
+///
+/// All monitors are grabbed the moment this is called, before any window is
+/// shown or focused, so menus, tooltips and hover states that vanish on a
+/// focus change are captured as they were. Call it before hiding the main
+/// window.
+#[tauri::command]
+pub async fn open_frozen_region_selector(
+    app_handle: AppHandle,
+    save_dir: String,
+    include_cursor: Option<bool>,
+    cursor_halo: Option<bool>,
+) -> Result<(), CaptureError> {
+    start_region_selector(
+        &app_handle,
+        CaptureMode::FrozenRegion,
+        save_dir,
+        None,
+        CursorOverlay::from_flags(include_cursor, cursor_halo),
+    )
+    .await
+}
+
+/// Called by the region selector window once it can receive its payload
+///
+/// Returns the payload, waiting for it if the frames or snap targets are
+/// still being prepared.
+#[tauri::command]
+pub async fn region_selector_ready(
+    window: tauri::WebviewWindow,
+) -> Result<serde_json::Value, String> {
+    take_when_ready(window.label()).await
+}
+
+async fn start_region_selector(
+    app_handle: &AppHandle,
+    mode: CaptureMode,
+    save_dir: String,
+    delay_secs: Option<u32>,
+    cursor: CursorOverlay,
+) -> Result<(), CaptureError> {
+    let tracker = CaptureTracker::start(app_handle, mode)?;
+    let capture_id = tracker.capture_id().to_string();
+    // The selector finishes the capture through `capture_region`, or cancels
+    // it by closing; this copy only reports on the way there
+    let reporter = tracker.clone();
+    tracker.defer();
+
+    let result = show_region_selector(app_handle, &reporter, save_dir, delay_secs, cursor).await;
+    if let Err(e) = &result {
+        if let Some(tracker) = take_deferred(&capture_id) {
+            tracker.failed(e);
+        }
+    }
+    result
+}
+
+async fn show_region_selector(
+    app_handle: &AppHandle,
+    tracker: &CaptureTracker,
+    save_dir: String,
+    delay_secs: Option<u32>,
+    cursor: CursorOverlay,
+) -> Result<(), CaptureError> {
+    wait_for_capture_delay(app_handle, tracker.capture_id(), delay_secs).await?;
+
+    // Capture all monitors into the frame store
+    let capture_id = tracker.capture_id();
+    let sensitive = detect_sensitive_windows(app_handle, tracker)?;
+    let monitor_frames = capture_monitor_frames(cursor, &sensitive)?;
+    let frame_ids: Vec<String> = monitor_frames.iter().map(|m| m.frame_id.clone()).collect();
+
+    // Find snap targets while the selector window loads
+    let detection_frames = monitor_frames.clone();
+    let detection =
+        tauri::async_runtime::spawn_blocking(move || detect_elements(&detection_frames));
 
     // Create the region selector window if it doesn't exist
     let window_label = "region-selector";
@@ -530,10 +1134,11 @@ pub async fn open_region_selector(
         // Close existing window if any
         existing_window.close().ok();
     }
+    expect_ready(window_label, capture_id);
 
     // Create new fullscreen window for region selection
     let window = tauri::WebviewWindowBuilder::new(
-        &app_handle,
+        app_handle,
         window_label,
         tauri::WebviewUrl::App("index.html?region-selector=1".into()),
     )
@@ -544,30 +1149,341 @@ pub async fn open_region_selector(
     .always_on_top(true)
     .visible(false) // Start hidden, will show after setup
     .build()
-    .map_err(|e| format!("Failed to create region selector window: {}", e))?;
+    .map_err(|e| {
+        remove_frames(&frame_ids);
+        forget_selector(window_label, capture_id);
+        format!("Failed to create region selector window: {}", e)
+    })?;
+
+    // Release the frames once the selector is gone, whether it cropped or was cancelled
+    let selector_capture_id = capture_id.to_string();
+    window.on_window_event(move |event| {
+        if let tauri::WindowEvent::Destroyed = event {
+            remove_frames(&frame_ids);
+            forget_selector(window_label, &selector_capture_id);
+            untrack_selector_monitors(&selector_capture_id);
+            abandon_deferred(&selector_capture_id);
+        }
+    });
+    track_selector_monitors(capture_id, monitor_frames.iter().map(|m| m.id).collect());
 
     // Show the window first
     window
         .show()
         .map_err(|e| format!("Failed to show region selector window: {}", e))?;
 
-    // Give the React component time to mount and set up event listeners
-    // Increased from 100ms to 800ms to ensure reliable event delivery
-    // Use async sleep to avoid blocking the runtime thread
-    tauri::async_runtime::sleep(std::time::Duration::from_millis(800)).await;
+    let elements = detection.await.unwrap_or_else(|e| {
+        eprintln!("Element detection failed: {}", e);
+        Vec::new()
+    });
+    stage_payload(
+        window_label,
+        capture_id,
+        serde_json::json!({
+            "saveDir": save_dir,
+            "monitorShots": monitor_frames,
+            "captureId": capture_id,
+            "elements": elements,
+        }),
+    )?;
+
+    // The window picks the payload up with `region_selector_ready`
+    if let Err(e) = wait_until_delivered(window_label, capture_id).await {
+        window.close().ok();
+        return Err(e.into());
+    }
+    Ok(())
+}
 
-    // Emit event with screenshot data to the window
-    window
-        .emit(
-            "region-selector-show",
-            serde_json::json!({
-                "screenshotPath": monitor_shots.first().map(|m| m.path.clone()).unwrap_or_default(),
-                "monitorShots": monitor_shots,
-            }),
+/// Capture a scrolling region and stitch the frames into one tall image
+#[tauri::command]
+pub async fn scroll_capture(
+    app_handle: AppHandle,
+    options: ScrollCaptureOptions,
+    save_dir: String,
+    delay_secs: Option<u32>,
+) -> Result<String, CaptureError> {
+    let tracker = CaptureTracker::start(&app_handle, CaptureMode::Scrolling)?;
+    let result: Result<String, CaptureError> = async {
+        wait_for_capture_delay(&app_handle, tracker.capture_id(), delay_secs).await?;
+
+        let path = tauri::async_runtime::spawn_blocking(move || {
+            run_scroll_capture(&app_handle, &options, &save_dir)
+        })
+        .await
+        .map_err(|e| format!("Scrolling capture task failed: {}", e))??;
+        Ok(path)
+    }
+    .await;
+
+    tracker.finish(result, |path| vec![CaptureOutput::from_file(path, None)])
+}
+
+/// Finish the running scrolling capture and stitch the frames grabbed so far
+#[tauri::command]
+pub async fn stop_scroll_capture() -> Result<(), String> {
+    stop_active_scroll_capture()
+}
+
+/// Start recording a monitor, region or window; returns the output path
+#[tauri::command]
+pub async fn start_recording(
+    app_handle: AppHandle,
+    options: RecordingOptions,
+    save_dir: String,
+    delay_secs: Option<u32>,
+) -> Result<String, String> {
+    wait_for_capture_delay(&app_handle, &generate_id()?, delay_secs).await?;
+    start_recorder(&app_handle, options, &save_dir)
+}
+
+/// Pause the active recording
+#[tauri::command]
+pub async fn pause_recording(app_handle: AppHandle) -> Result<(), String> {
+    set_recording_paused(&app_handle, true)
+}
+
+/// Resume the active recording
+#[tauri::command]
+pub async fn resume_recording(app_handle: AppHandle) -> Result<(), String> {
+    set_recording_paused(&app_handle, false)
+}
+
+/// Stop the active recording and wait for the file to be encoded
+#[tauri::command]
+pub async fn stop_recording(app_handle: AppHandle) -> Result<String, String> {
+    tauri::async_runtime::spawn_blocking(move || stop_recorder(&app_handle))
+        .await
+        .map_err(|e| format!("Recording task failed: {}", e))?
+}
+
+/// Start capturing a source every few seconds into a new session folder
+#[tauri::command]
+pub async fn start_interval_session(
+    app_handle: AppHandle,
+    options: IntervalSessionOptions,
+    save_dir: String,
+    delay_secs: Option<u32>,
+) -> Result<IntervalSessionStarted, String> {
+    wait_for_capture_delay(&app_handle, &generate_id()?, delay_secs).await?;
+    start_session(&app_handle, options, &save_dir)
+}
+
+/// Stop a running interval session; its output is assembled in the background
+#[tauri::command]
+pub async fn stop_interval_session(session_id: String) -> Result<(), String> {
+    stop_session(&session_id)
+}
+
+/// List the windows available for capture
+#[tauri::command]
+pub async fn list_windows() -> Result<Vec<WindowInfo>, String> {
+    list_all_windows()
+}
+
+/// Geometry of every connected monitor, as sent with `monitors-changed`
+#[tauri::command]
+pub async fn list_monitors() -> Result<Vec<MonitorGeometry>, String> {
+    monitor_layout()
+}
+
+/// The capture backend in use and what it supports, for bug reports
+#[tauri::command]
+pub async fn get_diagnostics() -> Result<Diagnostics, String> {
+    tauri::async_runtime::spawn_blocking(diagnostics)
+        .await
+        .map_err(|e| format!("Failed to collect diagnostics: {}", e))
+}
+
+/// Recognize text in an image, keeping block, line and word boxes
+///
+/// `options` default to the OCR settings.
+#[tauri::command]
+pub async fn recognize_text(
+    app_handle: AppHandle,
+    image_path: String,
+    options: Option<OcrOptions>,
+) -> Result<OcrResult, String> {
+    let options = match options {
+        Some(options) => options,
+        None => OcrOptions::load(&app_handle)?,
+    };
+    tauri::async_runtime::spawn_blocking(move || recognize_image(Path::new(&image_path), &options))
+        .await
+        .map_err(|e| format!("OCR task failed: {}", e))?
+}
+
+/// Recognize text in an image and rebuild its columns, paragraphs and code
+#[tauri::command]
+pub async fn recognize_layout(
+    app_handle: AppHandle,
+    image_path: String,
+    options: Option<OcrOptions>,
+) -> Result<OcrLayout, String> {
+    let options = match options {
+        Some(options) => options,
+        None => OcrOptions::load(&app_handle)?,
+    };
+    tauri::async_runtime::spawn_blocking(move || {
+        let result = recognize_image(Path::new(&image_path), &options)?;
+        Ok(OcrLayout::from_result(&result))
+    })
+    .await
+    .map_err(|e| format!("OCR task failed: {}", e))?
+}
+
+/// Recognize a table in an image, one string per cell
+#[tauri::command]
+pub async fn extract_table(
+    app_handle: AppHandle,
+    image_path: String,
+    options: Option<OcrOptions>,
+) -> Result<OcrTable, String> {
+    let options = match options {
+        Some(options) => options,
+        None => OcrOptions::load(&app_handle)?,
+    };
+    tauri::async_runtime::spawn_blocking(move || {
+        extract_table_from_image(Path::new(&image_path), &options)
+    })
+    .await
+    .map_err(|e| format!("OCR task failed: {}", e))?
+}
+
+/// Find sensitive text in an image and suggest rects to redact
+#[tauri::command]
+pub async fn suggest_redactions(
+    app_handle: AppHandle,
+    image_path: String,
+    options: Option<OcrOptions>,
+) -> Result<Vec<RedactionSuggestion>, String> {
+    let options = match options {
+        Some(options) => options,
+        None => OcrOptions::load(&app_handle)?,
+    };
+    let rules = RedactionRules::load(&app_handle)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        find_redactions_in_image(Path::new(&image_path), &options, &rules)
+    })
+    .await
+    .map_err(|e| format!("OCR task failed: {}", e))?
+}
+
+/// Check the text in an image against WCAG contrast and return the runs that fail
+///
+/// With `annotated_dir`, a copy with the failing runs outlined is saved there.
+#[tauri::command]
+pub async fn audit_contrast(
+    app_handle: AppHandle,
+    image_path: String,
+    options: Option<ContrastOptions>,
+    annotated_dir: Option<String>,
+) -> Result<ContrastReport, String> {
+    let ocr_options = OcrOptions::load(&app_handle)?;
+    let options = options.unwrap_or_default();
+    tauri::async_runtime::spawn_blocking(move || {
+        audit_image_contrast(
+            Path::new(&image_path),
+            &ocr_options,
+            &options,
+            annotated_dir.as_deref(),
         )
-        .map_err(|e| format!("Failed to emit region selector event: {}", e))?;
+    })
+    .await
+    .map_err(|e| format!("Contrast audit failed: {}", e))?
+}
 
-    Ok(())
+/// Decode the QR codes and barcodes in an image, with their positions
+#[tauri::command]
+pub async fn scan_barcodes(image_path: String) -> Result<Vec<Barcode>, String> {
+    tauri::async_runtime::spawn_blocking(move || decode_barcodes_in_image(Path::new(&image_path)))
+        .await
+        .map_err(|e| format!("Barcode scan failed: {}", e))?
+}
+
+/// Recognize text in an image and export it as hOCR or ALTO XML
+#[tauri::command]
+pub async fn export_ocr(
+    app_handle: AppHandle,
+    image_path: String,
+    format: OcrExportFormat,
+    options: Option<OcrOptions>,
+) -> Result<String, String> {
+    let options = match options {
+        Some(options) => options,
+        None => OcrOptions::load(&app_handle)?,
+    };
+    tauri::async_runtime::spawn_blocking(move || {
+        let path = Path::new(&image_path);
+        let result = recognize_image(path, &options)?;
+        let image_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        Ok(export_ocr_result(&result, &image_name, format))
+    })
+    .await
+    .map_err(|e| format!("OCR task failed: {}", e))?
+}
+
+/// Capture a window with a transparent background and optional drop shadow
+#[tauri::command]
+pub async fn capture_window_image(
+    app_handle: AppHandle,
+    window_id: u32,
+    save_dir: String,
+    delay_secs: Option<u32>,
+    window_shadow: Option<bool>,
+) -> Result<String, CaptureError> {
+    let mut tracker = CaptureTracker::start(&app_handle, CaptureMode::Window)?;
+    // Only a successful listing can tell that the window is gone
+    let listed = tauri::async_runtime::spawn_blocking(move || {
+        list_all_windows().map(|windows| windows.into_iter().find(|window| window.id == window_id))
+    })
+    .await
+    .map_err(|e| format!("Window listing task failed: {}", e))?;
+    if let Ok(Some(window)) = &listed {
+        tracker.set_window(window.into());
+    }
+
+    let result: Result<String, CaptureError> = async {
+        if let Ok(None) = listed {
+            return Err(CaptureError::new(
+                CaptureErrorCode::NotFound,
+                format!("Window {} not found", window_id),
+            ));
+        }
+        wait_for_capture_delay(&app_handle, tracker.capture_id(), delay_secs).await?;
+        let shadow = window_shadow.unwrap_or(true);
+        Ok(tauri::async_runtime::spawn_blocking(move || {
+            capture_window_transparent(window_id, shadow, &save_dir)
+        })
+        .await
+        .map_err(|e| format!("Capture task failed: {}", e))??)
+    }
+    .await;
+
+    tracker.finish(result, |path| vec![CaptureOutput::from_file(path, None)])
+}
+
+/// Cancel a pending delayed capture by the id from its `capture-countdown` events
+#[tauri::command]
+pub async fn cancel_delayed_capture(capture_id: String) -> Result<(), String> {
+    cancel_pending_capture(&capture_id)
+}
+
+/// Current state of the native capture coordinator
+#[tauri::command]
+pub async fn get_capture_state() -> Result<CaptureStatus, String> {
+    Ok(capture_status())
+}
+
+/// Cancel the running native capture and any queued ones
+///
+/// Returns whether anything was cancelled.
+#[tauri::command]
+pub async fn cancel_native_capture() -> Result<bool, String> {
+    Ok(cancel_captures())
 }
 
 /// Emit capture complete event to main window
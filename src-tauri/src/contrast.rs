@@ -0,0 +1,383 @@
+//! Text contrast audit
+//!
+//! Estimates the text and background colour of each OCR line and checks
+//! the WCAG 2.x contrast ratio between them. The background is the most
+//! common colour in and around the line's box, and the text colour the most
+//! common one among the pixels least like it, which skips anti-aliased
+//! edges. Each line counts as one run of text.
+
+use image::{DynamicImage, Rgb, RgbImage, Rgba, RgbaImage};
+use imageproc::drawing::draw_hollow_rect_mut;
+use imageproc::rect::Rect;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::image::save_image;
+use crate::ocr::{recognize_image, OcrOptions, OcrResult, PixelRect};
+use crate::utils::AppResult;
+
+/// Text at least this tall, in CSS pixels, counts as large (18pt)
+const LARGE_TEXT_PX: f64 = 24.0;
+
+/// Lines whose darkest and lightest colours are closer than this, in RGB
+/// distance, have no legible text to measure
+const MIN_INK_DISTANCE: f64 = 24.0;
+
+/// Pixels at least this share of the way from the background to the
+/// farthest colour count as text
+const FOREGROUND_DISTANCE: f64 = 0.6;
+
+/// Percentile of the distances taken as the farthest, so a few stray
+/// pixels don't set the text colour
+const FARTHEST_PERCENTILE: usize = 98;
+
+const OUTLINE_COLOR: Rgba<u8> = Rgba([255, 0, 64, 255]);
+const OUTLINE_WIDTH: u32 = 3;
+
+/// WCAG conformance level to check against
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum WcagLevel {
+    #[default]
+    Aa,
+    Aaa,
+}
+
+impl WcagLevel {
+    fn required_ratio(self, large_text: bool) -> f64 {
+        match (self, large_text) {
+            (WcagLevel::Aa, false) => 4.5,
+            (WcagLevel::Aa, true) => 3.0,
+            (WcagLevel::Aaa, false) => 7.0,
+            (WcagLevel::Aaa, true) => 4.5,
+        }
+    }
+}
+
+/// Audit settings; omitted fields use the defaults
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ContrastOptions {
+    pub level: WcagLevel,
+    /// Image pixels per CSS pixel, such as 2 for a Retina capture; used to
+    /// tell large text apart
+    pub pixel_ratio: f64,
+}
+
+impl Default for ContrastOptions {
+    fn default() -> Self {
+        Self {
+            level: WcagLevel::default(),
+            pixel_ratio: 1.0,
+        }
+    }
+}
+
+/// A run of text below the required contrast
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ContrastIssue {
+    pub text: String,
+    pub bbox: PixelRect,
+    /// Estimated colours as `#rrggbb`
+    pub foreground: String,
+    pub background: String,
+    /// Rounded down to two decimals, as WCAG ratios must not be rounded up
+    pub ratio: f64,
+    pub required: f64,
+    /// Whether the line is tall enough for the large-text threshold
+    pub large_text: bool,
+}
+
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ContrastReport {
+    /// Lines whose colours could be measured
+    pub runs_checked: usize,
+    pub failures: Vec<ContrastIssue>,
+    /// Copy of the image with the failures outlined, when requested
+    pub annotated_path: Option<String>,
+}
+
+/// WCAG relative luminance of an sRGB colour
+fn relative_luminance(color: Rgb<u8>) -> f64 {
+    let linear = |channel: u8| {
+        let value = channel as f64 / 255.0;
+        if value <= 0.04045 {
+            value / 12.92
+        } else {
+            ((value + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    let [r, g, b] = color.0;
+    0.2126 * linear(r) + 0.7152 * linear(g) + 0.0722 * linear(b)
+}
+
+/// WCAG contrast ratio, from 1 to 21
+pub fn contrast_ratio(a: Rgb<u8>, b: Rgb<u8>) -> f64 {
+    let (a, b) = (relative_luminance(a), relative_luminance(b));
+    (a.max(b) + 0.05) / (a.min(b) + 0.05)
+}
+
+fn hex(color: Rgb<u8>) -> String {
+    let [r, g, b] = color.0;
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+fn distance(a: &Rgb<u8>, b: &Rgb<u8>) -> f64 {
+    a.0.iter()
+        .zip(b.0)
+        .map(|(x, y)| (*x as f64 - y as f64).powi(2))
+        .sum::<f64>()
+        .sqrt()
+}
+
+/// Most common colour, averaged over the pixels in its 4-bit-per-channel bin
+fn dominant_color<'a>(pixels: impl Iterator<Item = &'a Rgb<u8>>) -> Option<Rgb<u8>> {
+    let mut bins = vec![(0u64, [0u64; 3]); 1 << 12];
+    for pixel in pixels {
+        let [r, g, b] = pixel.0.map(|channel| channel as usize >> 4);
+        let (count, sum) = &mut bins[(r << 8) | (g << 4) | b];
+        *count += 1;
+        for (total, channel) in sum.iter_mut().zip(pixel.0) {
+            *total += channel as u64;
+        }
+    }
+    let (count, sum) = bins.into_iter().max_by_key(|(count, _)| *count)?;
+    (count > 0).then(|| Rgb(sum.map(|total| (total / count) as u8)))
+}
+
+/// Estimated text and background colour of a line
+fn run_colors(image: &RgbImage, bbox: &PixelRect) -> Option<(Rgb<u8>, Rgb<u8>)> {
+    let (width, height) = image.dimensions();
+    let pad = bbox.height / 4;
+    let x0 = bbox.x.saturating_sub(pad);
+    let y0 = bbox.y.saturating_sub(pad);
+    let x1 = (bbox.right() + pad).min(width);
+    let y1 = (bbox.bottom() + pad).min(height);
+    let pixels: Vec<&Rgb<u8>> = (y0..y1)
+        .flat_map(|y| (x0..x1).map(move |x| image.get_pixel(x, y)))
+        .collect();
+
+    let background = dominant_color(pixels.iter().copied())?;
+    let mut distances: Vec<f64> = pixels
+        .iter()
+        .map(|pixel| distance(pixel, &background))
+        .collect();
+    distances.sort_by(f64::total_cmp);
+    let farthest = distances[(distances.len() - 1) * FARTHEST_PERCENTILE / 100];
+    if farthest < MIN_INK_DISTANCE {
+        return None;
+    }
+
+    let foreground = dominant_color(
+        pixels
+            .iter()
+            .copied()
+            .filter(|pixel| distance(pixel, &background) >= farthest * FOREGROUND_DISTANCE),
+    )?;
+    Some((foreground, background))
+}
+
+/// Check every line of an OCR result against the image it came from
+pub fn audit_contrast(
+    result: &OcrResult,
+    image: &RgbImage,
+    options: &ContrastOptions,
+) -> ContrastReport {
+    let mut report = ContrastReport::default();
+    for line in result.lines() {
+        if line.text.trim().is_empty() || line.bbox.width == 0 || line.bbox.height == 0 {
+            continue;
+        }
+        let Some((foreground, background)) = run_colors(image, &line.bbox) else {
+            continue;
+        };
+        report.runs_checked += 1;
+
+        let css_height = line.bbox.height as f64 / options.pixel_ratio.max(0.1);
+        let large_text = css_height >= LARGE_TEXT_PX;
+        let required = options.level.required_ratio(large_text);
+        let ratio = contrast_ratio(foreground, background);
+        if ratio < required {
+            report.failures.push(ContrastIssue {
+                text: line.text.clone(),
+                bbox: line.bbox,
+                foreground: hex(foreground),
+                background: hex(background),
+                ratio: (ratio * 100.0).floor() / 100.0,
+                required,
+                large_text,
+            });
+        }
+    }
+    report
+}
+
+/// Outline each failing run
+pub fn annotate_failures(image: &mut RgbaImage, failures: &[ContrastIssue]) {
+    for issue in failures {
+        // Just outside the box, so the text itself stays visible
+        for grow in 1..=OUTLINE_WIDTH {
+            let rect = Rect::at(
+                issue.bbox.x as i32 - grow as i32,
+                issue.bbox.y as i32 - grow as i32,
+            )
+            .of_size(issue.bbox.width + grow * 2, issue.bbox.height + grow * 2);
+            draw_hollow_rect_mut(image, rect, OUTLINE_COLOR);
+        }
+    }
+}
+
+/// Recognize an image and audit its text contrast
+///
+/// With `annotated_dir`, a copy with the failures outlined is saved there.
+pub fn audit_image_contrast(
+    image_path: &Path,
+    ocr_options: &OcrOptions,
+    options: &ContrastOptions,
+    annotated_dir: Option<&str>,
+) -> AppResult<ContrastReport> {
+    let result = recognize_image(image_path, ocr_options)?;
+    let image = image::open(image_path).map_err(|e| format!("Failed to open image: {}", e))?;
+    let mut report = audit_contrast(&result, &image.to_rgb8(), options);
+
+    if let Some(dir) = annotated_dir {
+        let mut annotated = image.to_rgba8();
+        annotate_failures(&mut annotated, &report.failures);
+        report.annotated_path = Some(save_image(
+            &DynamicImage::ImageRgba8(annotated),
+            dir,
+            "contrast",
+        )?);
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ocr::{OcrBlock, OcrLine, OcrWord};
+
+    const IMAGE: (u32, u32) = (400, 240);
+    const WHITE: Rgb<u8> = Rgb([255, 255, 255]);
+
+    /// Draw a line of fake glyphs: bars of the text colour with a blended
+    /// edge like anti-aliasing, returning the OCR line over them
+    fn draw_line(image: &mut RgbImage, text: &str, y: u32, height: u32, color: Rgb<u8>) -> OcrLine {
+        let bbox = PixelRect {
+            x: 20,
+            y,
+            width: 200,
+            height,
+        };
+        let blend = Rgb([0, 1, 2].map(|i| ((color.0[i] as u16 + 255) / 2) as u8));
+        for glyph in 0..20 {
+            let left = bbox.x + glyph * 10;
+            for yy in y..y + height {
+                image.put_pixel(left, yy, blend);
+                for x in left + 1..left + 4 {
+                    image.put_pixel(x, yy, color);
+                }
+                image.put_pixel(left + 4, yy, blend);
+            }
+        }
+        OcrLine::from_words(
+            vec![OcrWord::new(text.to_string(), bbox, 0.9, IMAGE)],
+            IMAGE,
+        )
+    }
+
+    fn fixture() -> (OcrResult, RgbImage) {
+        let mut image = RgbImage::from_pixel(IMAGE.0, IMAGE.1, WHITE);
+        let lines = vec![
+            draw_line(&mut image, "Body text", 20, 16, Rgb([0x33, 0x33, 0x33])),
+            draw_line(&mut image, "Muted hint", 60, 16, Rgb([0xaa, 0xaa, 0xaa])),
+            draw_line(&mut image, "Big heading", 120, 60, Rgb([0x88, 0x88, 0x88])),
+        ];
+        let result = OcrResult {
+            engine: "test",
+            width: IMAGE.0,
+            height: IMAGE.1,
+            blocks: vec![OcrBlock::from_lines(lines, IMAGE)],
+        };
+        (result, image)
+    }
+
+    #[test]
+    fn test_contrast_ratio_matches_wcag() {
+        assert!((contrast_ratio(Rgb([0, 0, 0]), WHITE) - 21.0).abs() < 1e-9);
+        assert!((contrast_ratio(WHITE, WHITE) - 1.0).abs() < 1e-9);
+        // #777 on white is the classic near miss for AA
+        let ratio = contrast_ratio(Rgb([0x77, 0x77, 0x77]), WHITE);
+        assert!((ratio - 4.48).abs() < 0.01, "{}", ratio);
+    }
+
+    #[test]
+    fn test_reports_runs_below_the_level() {
+        let (result, image) = fixture();
+
+        let report = audit_contrast(&result, &image, &ContrastOptions::default());
+
+        assert_eq!(report.runs_checked, 3);
+        assert_eq!(report.failures.len(), 1);
+        let issue = &report.failures[0];
+        assert_eq!(issue.text, "Muted hint");
+        assert_eq!(issue.foreground, "#aaaaaa");
+        assert_eq!(issue.background, "#ffffff");
+        assert_eq!(issue.ratio, 2.32);
+        assert_eq!(issue.required, 4.5);
+        assert!(!issue.large_text);
+
+        // #888 passes as large text at AA but not at AAA, and at 3x the
+        // heading is no longer large
+        let aaa = ContrastOptions {
+            level: WcagLevel::Aaa,
+            ..ContrastOptions::default()
+        };
+        let failing: Vec<_> = audit_contrast(&result, &image, &aaa)
+            .failures
+            .into_iter()
+            .map(|issue| (issue.text, issue.large_text))
+            .collect();
+        assert_eq!(
+            failing,
+            vec![
+                ("Muted hint".to_string(), false),
+                ("Big heading".to_string(), true)
+            ]
+        );
+        let retina = ContrastOptions {
+            pixel_ratio: 3.0,
+            ..ContrastOptions::default()
+        };
+        assert_eq!(audit_contrast(&result, &image, &retina).failures.len(), 2);
+    }
+
+    #[test]
+    fn test_blank_lines_are_skipped() {
+        let image = RgbImage::from_pixel(IMAGE.0, IMAGE.1, WHITE);
+        let (result, _) = fixture();
+        assert_eq!(
+            audit_contrast(&result, &image, &ContrastOptions::default()),
+            ContrastReport::default()
+        );
+    }
+
+    #[test]
+    fn test_annotation_outlines_failures_outside_the_box() {
+        let (result, image) = fixture();
+        let report = audit_contrast(&result, &image, &ContrastOptions::default());
+        let mut annotated = DynamicImage::ImageRgb8(image).to_rgba8();
+
+        annotate_failures(&mut annotated, &report.failures);
+
+        // The failing line's box is (20, 60) to (220, 76)
+        assert_eq!(*annotated.get_pixel(17, 70), OUTLINE_COLOR);
+        assert_eq!(*annotated.get_pixel(19, 70), OUTLINE_COLOR);
+        assert_ne!(*annotated.get_pixel(21, 70), OUTLINE_COLOR);
+        assert_eq!(*annotated.get_pixel(100, 78), OUTLINE_COLOR);
+        assert_ne!(*annotated.get_pixel(16, 70), OUTLINE_COLOR);
+        assert_ne!(*annotated.get_pixel(17, 30), OUTLINE_COLOR);
+    }
+}
@@ -0,0 +1,553 @@
+//! Capture coordinator
+//!
+//! Native captures go through a single coordinator instead of a global lock
+//! and a `pgrep` check. One capture runs at a time; further requests wait in
+//! a short queue or are rejected with a typed reason. The running capture
+//! moves through selecting, capturing and processing, can be cancelled, and
+//! its `screencapture` process is killed if it outlives its timeout.
+
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::fmt;
+use std::process::{Child, Output};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, LazyLock, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+use crate::events::{CaptureError, CaptureErrorCode};
+use crate::utils::generate_id;
+
+/// Event emitted whenever the coordinator's state changes
+pub const CAPTURE_STATE_EVENT: &str = "capture-state-changed";
+
+/// How often queued requests and running processes are checked
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Requests allowed to wait behind the running capture
+const MAX_QUEUED: usize = 2;
+
+/// Extra time a queued request waits on top of the timeouts of the captures
+/// ahead of it, for their processing such as OCR
+///
+/// A request behind an interactive selection can wait for the whole
+/// selection, so the queue never gives up before the running capture would.
+const QUEUE_GRACE: Duration = Duration::from_secs(15);
+
+static COORDINATOR: LazyLock<Mutex<Coordinator>> =
+    LazyLock::new(|| Mutex::new(Coordinator::default()));
+
+/// What the coordinator is doing
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CaptureState {
+    Idle,
+    Selecting,
+    Capturing,
+    Processing,
+}
+
+/// The kind of native capture being requested
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CaptureKind {
+    Interactive,
+    Fullscreen,
+    Window,
+    OcrRegion,
+}
+
+impl CaptureKind {
+    /// Whether the user picks what to capture
+    fn is_interactive(self) -> bool {
+        !matches!(self, CaptureKind::Fullscreen)
+    }
+
+    fn initial_state(self) -> CaptureState {
+        if self.is_interactive() {
+            CaptureState::Selecting
+        } else {
+            CaptureState::Capturing
+        }
+    }
+
+    /// How long the capture process may run before it is killed
+    fn timeout(self) -> Duration {
+        if self.is_interactive() {
+            Duration::from_secs(120)
+        } else {
+            Duration::from_secs(20)
+        }
+    }
+}
+
+/// Why a capture request didn't complete
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CaptureRejection {
+    /// An interactive selection is already open or queued
+    SelectionInProgress,
+    /// Too many requests are already waiting
+    QueueFull,
+    /// The request waited too long for the running capture
+    QueueTimeout,
+    /// The request was cancelled
+    Cancelled,
+    /// The capture process ran past its timeout
+    TimedOut,
+}
+
+impl fmt::Display for CaptureRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            CaptureRejection::SelectionInProgress => {
+                "Another screenshot selection is already in progress"
+            }
+            CaptureRejection::QueueFull => "Too many screenshot captures are already waiting",
+            CaptureRejection::QueueTimeout => {
+                "Timed out waiting for the previous capture to finish"
+            }
+            CaptureRejection::Cancelled => "Screenshot capture was cancelled",
+            CaptureRejection::TimedOut => "Screenshot capture timed out",
+        };
+        f.write_str(message)
+    }
+}
+
+impl CaptureRejection {
+    pub fn code(self) -> CaptureErrorCode {
+        match self {
+            CaptureRejection::SelectionInProgress
+            | CaptureRejection::QueueFull
+            | CaptureRejection::QueueTimeout => CaptureErrorCode::Busy,
+            CaptureRejection::Cancelled => CaptureErrorCode::Cancelled,
+            CaptureRejection::TimedOut => CaptureErrorCode::Timeout,
+        }
+    }
+}
+
+impl From<CaptureRejection> for CaptureError {
+    fn from(rejection: CaptureRejection) -> Self {
+        CaptureError {
+            rejection: Some(rejection),
+            ..CaptureError::new(rejection.code(), rejection.to_string())
+        }
+    }
+}
+
+/// Payload of the `capture-state-changed` event
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptureStatus {
+    pub state: CaptureState,
+    pub capture_id: Option<String>,
+    pub kind: Option<CaptureKind>,
+    pub queued: usize,
+}
+
+struct Entry {
+    id: String,
+    kind: CaptureKind,
+    cancelled: Arc<AtomicBool>,
+}
+
+struct Active {
+    entry: Entry,
+    state: CaptureState,
+}
+
+/// The running capture and the requests waiting behind it
+#[derive(Default)]
+struct Coordinator {
+    active: Option<Active>,
+    queue: VecDeque<Entry>,
+}
+
+impl Coordinator {
+    fn status(&self) -> CaptureStatus {
+        CaptureStatus {
+            state: self
+                .active
+                .as_ref()
+                .map_or(CaptureState::Idle, |active| active.state),
+            capture_id: self.active.as_ref().map(|active| active.entry.id.clone()),
+            kind: self.active.as_ref().map(|active| active.entry.kind),
+            queued: self.queue.len(),
+        }
+    }
+
+    /// Queue a request, or reject it outright
+    ///
+    /// Returns how long the request may wait for the captures ahead of it.
+    fn enqueue(&mut self, entry: Entry) -> Result<Duration, CaptureRejection> {
+        let interactive_pending = self
+            .active
+            .iter()
+            .map(|active| &active.entry)
+            .chain(self.queue.iter())
+            .any(|pending| pending.kind.is_interactive());
+        if entry.kind.is_interactive() && interactive_pending {
+            return Err(CaptureRejection::SelectionInProgress);
+        }
+        if self.active.is_some() && self.queue.len() >= MAX_QUEUED {
+            return Err(CaptureRejection::QueueFull);
+        }
+
+        let ahead: Duration = self
+            .active
+            .iter()
+            .map(|active| &active.entry)
+            .chain(self.queue.iter())
+            .map(|pending| pending.kind.timeout())
+            .sum();
+        self.queue.push_back(entry);
+        Ok(ahead + QUEUE_GRACE)
+    }
+
+    /// Start the request if nothing is running and it's next in line
+    fn try_start(&mut self, id: &str) -> bool {
+        if self.active.is_some() || self.queue.front().map(|entry| entry.id.as_str()) != Some(id) {
+            return false;
+        }
+
+        let entry = self.queue.pop_front().expect("queue front checked above");
+        self.active = Some(Active {
+            state: entry.kind.initial_state(),
+            entry,
+        });
+        true
+    }
+
+    fn set_state(&mut self, id: &str, state: CaptureState) {
+        if let Some(active) = self.active.as_mut().filter(|active| active.entry.id == id) {
+            active.state = state;
+        }
+    }
+
+    /// Drop a request whether it is running or still queued
+    ///
+    /// Returns whether it was the running capture.
+    fn release(&mut self, id: &str) -> bool {
+        if self
+            .active
+            .as_ref()
+            .is_some_and(|active| active.entry.id == id)
+        {
+            self.active = None;
+            return true;
+        }
+        self.queue.retain(|entry| entry.id != id);
+        false
+    }
+
+    /// Flag the running capture and every queued request as cancelled
+    fn cancel_all(&self) -> bool {
+        let pending: Vec<&Entry> = self
+            .active
+            .iter()
+            .map(|active| &active.entry)
+            .chain(self.queue.iter())
+            .collect();
+        for entry in &pending {
+            entry.cancelled.store(true, Ordering::SeqCst);
+        }
+        !pending.is_empty()
+    }
+}
+
+fn coordinator() -> MutexGuard<'static, Coordinator> {
+    // Every update leaves the coordinator consistent, so a panic elsewhere
+    // while holding the lock is no reason to stop capturing.
+    COORDINATOR
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn emit_status(app_handle: &AppHandle, status: CaptureStatus) {
+    let _ = app_handle.emit(CAPTURE_STATE_EVENT, status);
+}
+
+/// A capture admitted by the coordinator
+///
+/// Dropping the job, on success or failure, frees the coordinator for the
+/// next request.
+pub struct CaptureJob {
+    app_handle: AppHandle,
+    id: String,
+    kind: CaptureKind,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CaptureJob {
+    /// Wait for the coordinator to run a capture of this kind
+    pub async fn acquire(app_handle: &AppHandle, kind: CaptureKind) -> Result<Self, CaptureError> {
+        let job = Self {
+            app_handle: app_handle.clone(),
+            id: generate_id()?,
+            kind,
+            cancelled: Arc::new(AtomicBool::new(false)),
+        };
+        let queue_timeout = coordinator().enqueue(Entry {
+            id: job.id.clone(),
+            kind,
+            cancelled: job.cancelled.clone(),
+        })?;
+
+        let deadline = Instant::now() + queue_timeout;
+        loop {
+            if job.is_cancelled() {
+                return Err(CaptureRejection::Cancelled.into());
+            }
+
+            let started = {
+                let mut coordinator = coordinator();
+                coordinator.try_start(&job.id).then(|| coordinator.status())
+            };
+            if let Some(status) = started {
+                emit_status(app_handle, status);
+                return Ok(job);
+            }
+
+            if Instant::now() >= deadline {
+                return Err(CaptureRejection::QueueTimeout.into());
+            }
+            tauri::async_runtime::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Move the running capture to a new state
+    pub fn set_state(&self, state: CaptureState) {
+        let status = {
+            let mut coordinator = coordinator();
+            coordinator.set_state(&self.id, state);
+            coordinator.status()
+        };
+        emit_status(&self.app_handle, status);
+    }
+
+    /// Wait for a capture process without blocking the async runtime
+    ///
+    /// The process is killed if the job is cancelled or runs past its timeout.
+    pub async fn wait_for_process(&self, mut child: Child) -> Result<Output, CaptureError> {
+        let deadline = Instant::now() + self.kind.timeout();
+
+        loop {
+            let exited = child
+                .try_wait()
+                .map_err(|e| format!("Failed to wait for capture process: {}", e))?
+                .is_some();
+            if exited {
+                break;
+            }
+
+            let rejection = if self.is_cancelled() {
+                Some(CaptureRejection::Cancelled)
+            } else if Instant::now() >= deadline {
+                Some(CaptureRejection::TimedOut)
+            } else {
+                None
+            };
+            if let Some(rejection) = rejection {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(rejection.into());
+            }
+
+            tauri::async_runtime::sleep(POLL_INTERVAL).await;
+        }
+
+        child
+            .wait_with_output()
+            .map_err(|e| format!("Failed to read capture process output: {}", e).into())
+    }
+
+    /// Poll for a capture that doesn't run as a child process
+    ///
+    /// `poll` returns `None` until the capture finishes. Waiting stops if the
+    /// job is cancelled or runs past its timeout.
+    #[cfg(target_os = "linux")]
+    pub async fn wait_until<T>(
+        &self,
+        mut poll: impl FnMut() -> Result<Option<T>, CaptureError>,
+    ) -> Result<T, CaptureError> {
+        let deadline = Instant::now() + self.kind.timeout();
+        loop {
+            if let Some(value) = poll()? {
+                return Ok(value);
+            }
+            if self.is_cancelled() {
+                return Err(CaptureRejection::Cancelled.into());
+            }
+            if Instant::now() >= deadline {
+                return Err(CaptureRejection::TimedOut.into());
+            }
+            tauri::async_runtime::sleep(POLL_INTERVAL).await;
+        }
+    }
+}
+
+impl Drop for CaptureJob {
+    fn drop(&mut self) {
+        let released = {
+            let mut coordinator = coordinator();
+            coordinator.release(&self.id).then(|| coordinator.status())
+        };
+        if let Some(status) = released {
+            emit_status(&self.app_handle, status);
+        }
+    }
+}
+
+/// The coordinator's current state
+pub fn capture_status() -> CaptureStatus {
+    coordinator().status()
+}
+
+/// Cancel the running capture and every queued request
+///
+/// Returns whether there was anything to cancel.
+pub fn cancel_captures() -> bool {
+    coordinator().cancel_all()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: &str, kind: CaptureKind) -> Entry {
+        Entry {
+            id: id.to_string(),
+            kind,
+            cancelled: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    #[test]
+    fn test_requests_start_in_order() {
+        let mut coordinator = Coordinator::default();
+        coordinator
+            .enqueue(entry("a", CaptureKind::Window))
+            .unwrap();
+        coordinator
+            .enqueue(entry("b", CaptureKind::Fullscreen))
+            .unwrap();
+
+        assert!(!coordinator.try_start("b"));
+        assert!(coordinator.try_start("a"));
+        assert_eq!(coordinator.status().state, CaptureState::Selecting);
+        assert!(!coordinator.try_start("b"));
+
+        assert!(coordinator.release("a"));
+        assert!(coordinator.try_start("b"));
+        assert_eq!(coordinator.status().state, CaptureState::Capturing);
+        assert_eq!(coordinator.status().queued, 0);
+    }
+
+    #[test]
+    fn test_second_selection_is_rejected() {
+        let mut coordinator = Coordinator::default();
+        coordinator
+            .enqueue(entry("a", CaptureKind::Interactive))
+            .unwrap();
+        coordinator.try_start("a");
+
+        assert_eq!(
+            coordinator
+                .enqueue(entry("b", CaptureKind::OcrRegion))
+                .unwrap_err(),
+            CaptureRejection::SelectionInProgress
+        );
+        assert!(coordinator
+            .enqueue(entry("c", CaptureKind::Fullscreen))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_queue_is_bounded() {
+        let mut coordinator = Coordinator::default();
+        coordinator
+            .enqueue(entry("running", CaptureKind::Fullscreen))
+            .unwrap();
+        coordinator.try_start("running");
+        for i in 0..MAX_QUEUED {
+            coordinator
+                .enqueue(entry(&i.to_string(), CaptureKind::Fullscreen))
+                .unwrap();
+        }
+
+        assert_eq!(
+            coordinator
+                .enqueue(entry("extra", CaptureKind::Fullscreen))
+                .unwrap_err(),
+            CaptureRejection::QueueFull
+        );
+    }
+
+    #[test]
+    fn test_queue_waits_out_the_captures_ahead() {
+        let mut coordinator = Coordinator::default();
+        assert_eq!(
+            coordinator
+                .enqueue(entry("a", CaptureKind::Interactive))
+                .unwrap(),
+            QUEUE_GRACE
+        );
+        coordinator.try_start("a");
+
+        let behind_selection = coordinator
+            .enqueue(entry("b", CaptureKind::Fullscreen))
+            .unwrap();
+        assert_eq!(
+            behind_selection,
+            CaptureKind::Interactive.timeout() + QUEUE_GRACE
+        );
+        assert!(behind_selection > CaptureKind::Interactive.timeout());
+
+        assert_eq!(
+            coordinator
+                .enqueue(entry("c", CaptureKind::Fullscreen))
+                .unwrap(),
+            CaptureKind::Interactive.timeout() + CaptureKind::Fullscreen.timeout() + QUEUE_GRACE
+        );
+    }
+
+    #[test]
+    fn test_rejection_reaches_the_frontend_with_its_code() {
+        let error = CaptureError::from(CaptureRejection::SelectionInProgress);
+        let json = serde_json::to_value(&error).unwrap();
+
+        assert_eq!(json["code"], "busy");
+        assert_eq!(json["rejection"], "selection_in_progress");
+        assert_eq!(
+            CaptureError::from(CaptureRejection::Cancelled).code,
+            CaptureErrorCode::Cancelled
+        );
+        assert_eq!(
+            CaptureError::from(CaptureRejection::TimedOut).code,
+            CaptureErrorCode::Timeout
+        );
+    }
+
+    #[test]
+    fn test_cancel_all_flags_running_and_queued() {
+        let mut coordinator = Coordinator::default();
+        assert!(!coordinator.cancel_all());
+
+        let running = entry("a", CaptureKind::Fullscreen);
+        let queued = entry("b", CaptureKind::Fullscreen);
+        let flags = [running.cancelled.clone(), queued.cancelled.clone()];
+        coordinator.enqueue(running).unwrap();
+        coordinator.try_start("a");
+        coordinator.enqueue(queued).unwrap();
+
+        assert!(coordinator.cancel_all());
+        assert!(flags.iter().all(|flag| flag.load(Ordering::SeqCst)));
+
+        assert!(!coordinator.release("b"));
+        assert_eq!(coordinator.status().queued, 0);
+    }
+}
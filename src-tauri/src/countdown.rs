@@ -0,0 +1,180 @@
+//! Delayed capture countdown
+//!
+//! Lets any capture command wait a few seconds before grabbing the screen
+//! so hover states, open menus and tooltips can be set up first. Each
+//! pending countdown is keyed by its capture id and can be cancelled from
+//! the frontend. The remaining seconds are also shown as the tray title.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+use crate::events::{CaptureError, CaptureErrorCode};
+use crate::utils::AppResult;
+
+/// Event emitted once per second while a delayed capture is pending
+pub const CAPTURE_COUNTDOWN_EVENT: &str = "capture-countdown";
+
+/// Id of the tray icon whose title shows the countdown
+pub const TRAY_ID: &str = "main";
+
+/// How often the countdown checks for cancellation
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+static PENDING_CAPTURES: LazyLock<Mutex<HashMap<String, Arc<AtomicBool>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Payload of the `capture-countdown` event
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptureCountdown {
+    pub capture_id: String,
+    pub remaining_secs: u32,
+    pub cancelled: bool,
+}
+
+/// Removes a pending capture from the registry when the countdown ends
+struct PendingCapture {
+    id: String,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl PendingCapture {
+    fn register(capture_id: &str) -> AppResult<Self> {
+        let id = capture_id.to_string();
+        let cancelled = Arc::new(AtomicBool::new(false));
+
+        PENDING_CAPTURES
+            .lock()
+            .map_err(|e| format!("Failed to lock pending captures: {}", e))?
+            .insert(id.clone(), cancelled.clone());
+
+        Ok(Self { id, cancelled })
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+impl Drop for PendingCapture {
+    fn drop(&mut self) {
+        if let Ok(mut pending) = PENDING_CAPTURES.lock() {
+            pending.remove(&self.id);
+        }
+    }
+}
+
+/// Tray title for a countdown; cleared once it ends
+fn tray_title(remaining_secs: u32, cancelled: bool) -> Option<String> {
+    (remaining_secs > 0 && !cancelled).then(|| format!("{}s", remaining_secs))
+}
+
+fn emit_countdown(app_handle: &AppHandle, capture_id: &str, remaining_secs: u32, cancelled: bool) {
+    if let Some(tray) = app_handle.tray_by_id(TRAY_ID) {
+        let _ = tray.set_title(tray_title(remaining_secs, cancelled));
+    }
+    let _ = app_handle.emit(
+        CAPTURE_COUNTDOWN_EVENT,
+        CaptureCountdown {
+            capture_id: capture_id.to_string(),
+            remaining_secs,
+            cancelled,
+        },
+    );
+}
+
+/// Wait out an optional capture delay, emitting a countdown event every second
+///
+/// The countdown is keyed by `capture_id`, the id reported in the capture's
+/// own events. Returns an error if the countdown is cancelled via
+/// [`cancel_pending_capture`]. A delay of `None` or zero returns immediately
+/// without emitting anything.
+pub async fn wait_for_capture_delay(
+    app_handle: &AppHandle,
+    capture_id: &str,
+    delay_secs: Option<u32>,
+) -> Result<(), CaptureError> {
+    let delay_secs = match delay_secs {
+        Some(secs) if secs > 0 => secs,
+        _ => return Ok(()),
+    };
+
+    let pending = PendingCapture::register(capture_id)?;
+    let ticks_per_second =
+        (Duration::from_secs(1).as_millis() / CANCEL_POLL_INTERVAL.as_millis()) as u32;
+
+    for remaining in (1..=delay_secs).rev() {
+        emit_countdown(app_handle, &pending.id, remaining, false);
+
+        for _ in 0..ticks_per_second {
+            if pending.is_cancelled() {
+                emit_countdown(app_handle, &pending.id, remaining, true);
+                return Err(CaptureError::new(
+                    CaptureErrorCode::Cancelled,
+                    "Delayed capture was cancelled",
+                ));
+            }
+            tauri::async_runtime::sleep(CANCEL_POLL_INTERVAL).await;
+        }
+    }
+
+    emit_countdown(app_handle, &pending.id, 0, false);
+    Ok(())
+}
+
+/// Cancel a pending delayed capture by id
+pub fn cancel_pending_capture(capture_id: &str) -> AppResult<()> {
+    let pending = PENDING_CAPTURES
+        .lock()
+        .map_err(|e| format!("Failed to lock pending captures: {}", e))?;
+
+    let cancelled = pending
+        .get(capture_id)
+        .ok_or_else(|| format!("No pending capture with id {}", capture_id))?;
+    cancelled.store(true, Ordering::SeqCst);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cancel_pending_capture_sets_flag() {
+        let pending = PendingCapture::register("countdown-cancel").unwrap();
+        assert!(!pending.is_cancelled());
+
+        cancel_pending_capture(&pending.id).unwrap();
+        assert!(pending.is_cancelled());
+    }
+
+    #[test]
+    fn test_pending_capture_unregisters_on_drop() {
+        let id = {
+            let pending = PendingCapture::register("countdown-drop").unwrap();
+            pending.id.clone()
+        };
+
+        assert!(cancel_pending_capture(&id).is_err());
+    }
+
+    #[test]
+    fn test_pending_capture_uses_the_capture_id() {
+        let pending = PendingCapture::register("countdown-capture-id").unwrap();
+        assert_eq!(pending.id, "countdown-capture-id");
+        cancel_pending_capture("countdown-capture-id").unwrap();
+        assert!(pending.is_cancelled());
+    }
+
+    #[test]
+    fn test_tray_title_is_cleared_when_the_countdown_ends() {
+        assert_eq!(tray_title(3, false).as_deref(), Some("3s"));
+        assert_eq!(tray_title(0, false), None);
+        assert_eq!(tray_title(2, true), None);
+    }
+}
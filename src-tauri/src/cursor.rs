@@ -0,0 +1,354 @@
+//! Mouse cursor state queries and cursor compositing
+//!
+//! xcap never includes the cursor in its captures, so when a capture asks
+//! for it the current cursor image is queried separately and drawn onto
+//! the frame at its hotspot.
+
+use image::{imageops, Rgba, RgbaImage};
+
+use crate::image::blend_circle;
+use crate::utils::AppResult;
+
+/// Radius of the cursor highlight halo, in screen coordinate units
+pub const CURSOR_HALO_RADIUS: i32 = 18;
+
+/// Colour of the cursor highlight halo
+pub const CURSOR_HALO_COLOR: Rgba<u8> = Rgba([255, 214, 10, 90]);
+
+/// Cursor position in global screen coordinates, as used by xcap
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CursorState {
+    pub x: f64,
+    pub y: f64,
+    /// Whether the primary mouse button is currently held down
+    pub pressed: bool,
+}
+
+/// The current cursor image and where it is on screen
+pub struct CursorImage {
+    /// Straight (not premultiplied) RGBA pixels
+    pub image: RgbaImage,
+    /// Hotspot within `image`, in image pixels
+    pub hotspot_x: u32,
+    pub hotspot_y: u32,
+    /// Hotspot position in global screen coordinates, as used by xcap
+    pub x: f64,
+    pub y: f64,
+    /// Image pixels per screen coordinate unit (2.0 for a retina cursor)
+    pub image_scale: f64,
+}
+
+/// Whether and how the cursor should appear in a capture
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CursorOverlay {
+    Hidden,
+    Visible,
+    /// The cursor drawn over a highlight halo
+    Highlighted,
+}
+
+impl CursorOverlay {
+    /// Build from the optional `includeCursor` / `cursorHalo` command flags
+    pub fn from_flags(include_cursor: Option<bool>, cursor_halo: Option<bool>) -> Self {
+        match (
+            include_cursor.unwrap_or(false),
+            cursor_halo.unwrap_or(false),
+        ) {
+            (false, _) => CursorOverlay::Hidden,
+            (true, false) => CursorOverlay::Visible,
+            (true, true) => CursorOverlay::Highlighted,
+        }
+    }
+
+    /// Query the cursor image if this overlay needs it
+    ///
+    /// A failed query is logged and treated as "no cursor" so that the
+    /// capture itself still succeeds.
+    pub fn query(self) -> Option<CursorImage> {
+        if self == CursorOverlay::Hidden {
+            return None;
+        }
+        query_cursor_image()
+            .map_err(|e| eprintln!("Failed to query cursor image: {}", e))
+            .ok()
+    }
+}
+
+/// Draw the cursor onto a captured frame
+///
+/// `origin_x`/`origin_y` is the frame's top-left corner in global screen
+/// coordinates and `pixel_ratio` the number of frame pixels per coordinate
+/// unit (see [`pixel_ratio`]).
+pub fn composite_cursor(
+    frame: &mut RgbaImage,
+    cursor: &CursorImage,
+    origin_x: i32,
+    origin_y: i32,
+    pixel_ratio: f64,
+    overlay: CursorOverlay,
+) {
+    if overlay == CursorOverlay::Hidden {
+        return;
+    }
+
+    let hotspot_x = ((cursor.x - origin_x as f64) * pixel_ratio).round() as i64;
+    let hotspot_y = ((cursor.y - origin_y as f64) * pixel_ratio).round() as i64;
+
+    if overlay == CursorOverlay::Highlighted {
+        let radius = (CURSOR_HALO_RADIUS as f64 * pixel_ratio).round() as i32;
+        blend_circle(
+            frame,
+            hotspot_x as i32,
+            hotspot_y as i32,
+            radius,
+            0,
+            CURSOR_HALO_COLOR,
+        );
+    }
+
+    // Draw the cursor at the same physical size it has on screen
+    let scale = pixel_ratio / cursor.image_scale.max(f64::EPSILON);
+    let (image, hot_x, hot_y) = if (scale - 1.0).abs() < 0.01 {
+        (
+            cursor.image.clone(),
+            cursor.hotspot_x as f64,
+            cursor.hotspot_y as f64,
+        )
+    } else {
+        let width = ((cursor.image.width() as f64 * scale).round() as u32).max(1);
+        let height = ((cursor.image.height() as f64 * scale).round() as u32).max(1);
+        (
+            imageops::resize(&cursor.image, width, height, imageops::FilterType::Triangle),
+            cursor.hotspot_x as f64 * scale,
+            cursor.hotspot_y as f64 * scale,
+        )
+    };
+
+    imageops::overlay(
+        frame,
+        &image,
+        hotspot_x - hot_x.round() as i64,
+        hotspot_y - hot_y.round() as i64,
+    );
+}
+
+/// Ratio between captured pixels and cursor/xcap coordinates for a monitor
+///
+/// macOS reports geometry in points while captures are in pixels; the
+/// other platforms report physical pixels throughout.
+pub fn pixel_ratio(scale_factor: f32) -> f64 {
+    if cfg!(target_os = "macos") {
+        scale_factor as f64
+    } else {
+        1.0
+    }
+}
+
+/// Query the current cursor position and primary button state
+#[cfg(target_os = "macos")]
+pub fn query_cursor() -> AppResult<CursorState> {
+    use objc2_core_graphics::{CGEvent, CGEventSource, CGEventSourceStateID, CGMouseButton};
+
+    let event = CGEvent::new(None).ok_or("Failed to create cursor event")?;
+    let location = CGEvent::location(Some(&event));
+    let pressed = CGEventSource::button_state(
+        CGEventSourceStateID::CombinedSessionState,
+        CGMouseButton::Left,
+    );
+
+    Ok(CursorState {
+        x: location.x,
+        y: location.y,
+        pressed,
+    })
+}
+
+/// Query the current cursor position and primary button state
+#[cfg(target_os = "linux")]
+pub fn query_cursor() -> AppResult<CursorState> {
+    use x11rb::connection::Connection;
+    use x11rb::protocol::xproto::{ConnectionExt, KeyButMask};
+
+    let (conn, screen_num) =
+        x11rb::connect(None).map_err(|e| format!("Failed to connect to X server: {}", e))?;
+    let root = conn.setup().roots[screen_num].root;
+
+    let pointer = conn
+        .query_pointer(root)
+        .map_err(|e| format!("Failed to query pointer: {}", e))?
+        .reply()
+        .map_err(|e| format!("Failed to query pointer: {}", e))?;
+
+    Ok(CursorState {
+        x: pointer.root_x as f64,
+        y: pointer.root_y as f64,
+        pressed: pointer.mask.contains(KeyButMask::BUTTON1),
+    })
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+pub fn query_cursor() -> AppResult<CursorState> {
+    Err("Cursor queries are not supported on this platform".to_string())
+}
+
+/// Query the current cursor image and hotspot
+#[cfg(target_os = "macos")]
+pub fn query_cursor_image() -> AppResult<CursorImage> {
+    use objc2_app_kit::{NSBitmapImageFileType, NSBitmapImageRep, NSCursor};
+    use objc2_foundation::NSDictionary;
+
+    let position = query_cursor()?;
+
+    // The system cursor (rather than this app's) is what appears on screen
+    #[allow(deprecated)]
+    let cursor = NSCursor::currentSystemCursor().ok_or("Failed to get the system cursor")?;
+    let ns_image = cursor.image();
+    let size = ns_image.size();
+    let hotspot = cursor.hotSpot();
+
+    // Round-trip through PNG to get straight alpha regardless of the backing representation
+    let tiff = ns_image
+        .TIFFRepresentation()
+        .ok_or("Failed to read cursor image")?;
+    let bitmap = NSBitmapImageRep::imageRepWithData(&tiff).ok_or("Failed to read cursor image")?;
+    let png = unsafe {
+        bitmap.representationUsingType_properties(NSBitmapImageFileType::PNG, &NSDictionary::new())
+    }
+    .ok_or("Failed to encode cursor image")?;
+    let image = image::load_from_memory(&png.to_vec())
+        .map_err(|e| format!("Failed to decode cursor image: {}", e))?
+        .to_rgba8();
+
+    let image_scale = if size.width > 0.0 {
+        image.width() as f64 / size.width
+    } else {
+        1.0
+    };
+
+    Ok(CursorImage {
+        hotspot_x: (hotspot.x * image_scale).round() as u32,
+        hotspot_y: (hotspot.y * image_scale).round() as u32,
+        image,
+        x: position.x,
+        y: position.y,
+        image_scale,
+    })
+}
+
+/// Query the current cursor image and hotspot via XFixes
+#[cfg(target_os = "linux")]
+pub fn query_cursor_image() -> AppResult<CursorImage> {
+    use x11rb::protocol::xfixes::ConnectionExt;
+
+    let (conn, _) =
+        x11rb::connect(None).map_err(|e| format!("Failed to connect to X server: {}", e))?;
+
+    // XFixes requires the client to announce its version before other requests
+    conn.xfixes_query_version(5, 0)
+        .map_err(|e| format!("XFixes is not available: {}", e))?
+        .reply()
+        .map_err(|e| format!("XFixes is not available: {}", e))?;
+
+    let reply = conn
+        .xfixes_get_cursor_image()
+        .map_err(|e| format!("Failed to get cursor image: {}", e))?
+        .reply()
+        .map_err(|e| format!("Failed to get cursor image: {}", e))?;
+
+    let image = argb_to_rgba(reply.width as u32, reply.height as u32, &reply.cursor_image)?;
+
+    Ok(CursorImage {
+        image,
+        hotspot_x: reply.xhot as u32,
+        hotspot_y: reply.yhot as u32,
+        x: reply.x as f64,
+        y: reply.y as f64,
+        image_scale: 1.0,
+    })
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+pub fn query_cursor_image() -> AppResult<CursorImage> {
+    Err("Cursor image queries are not supported on this platform".to_string())
+}
+
+/// Convert premultiplied ARGB pixels (as returned by XFixes) to straight RGBA
+#[cfg(any(target_os = "linux", test))]
+fn argb_to_rgba(width: u32, height: u32, pixels: &[u32]) -> AppResult<RgbaImage> {
+    let mut raw = Vec::with_capacity(pixels.len() * 4);
+    for &pixel in pixels {
+        let alpha = (pixel >> 24) as u8;
+        let unpremultiply = |channel: u32| -> u8 {
+            let channel = (channel & 0xff) as u8;
+            if alpha == 0 {
+                0
+            } else {
+                ((channel as u32 * 255 + alpha as u32 / 2) / alpha as u32).min(255) as u8
+            }
+        };
+        raw.extend_from_slice(&[
+            unpremultiply(pixel >> 16),
+            unpremultiply(pixel >> 8),
+            unpremultiply(pixel),
+            alpha,
+        ]);
+    }
+
+    RgbaImage::from_raw(width, height, raw).ok_or_else(|| "Cursor image size mismatch".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn arrow() -> CursorImage {
+        CursorImage {
+            image: RgbaImage::from_pixel(4, 4, Rgba([0, 0, 0, 255])),
+            hotspot_x: 1,
+            hotspot_y: 1,
+            x: 110.0,
+            y: 60.0,
+            image_scale: 1.0,
+        }
+    }
+
+    #[test]
+    fn test_argb_to_rgba_unpremultiplies() {
+        // 50% alpha white, premultiplied
+        let image = argb_to_rgba(1, 1, &[0x80_80_80_80]).unwrap();
+        assert_eq!(*image.get_pixel(0, 0), Rgba([255, 255, 255, 128]));
+
+        let transparent = argb_to_rgba(1, 1, &[0x00_12_34_56]).unwrap();
+        assert_eq!(transparent.get_pixel(0, 0)[3], 0);
+    }
+
+    #[test]
+    fn test_composite_cursor_places_hotspot() {
+        let mut frame = RgbaImage::from_pixel(40, 40, Rgba([255, 255, 255, 255]));
+        composite_cursor(&mut frame, &arrow(), 100, 50, 1.0, CursorOverlay::Visible);
+
+        // Hotspot (1, 1) lands on (10, 10), so the image spans 9..13
+        assert_eq!(*frame.get_pixel(9, 9), Rgba([0, 0, 0, 255]));
+        assert_eq!(*frame.get_pixel(12, 12), Rgba([0, 0, 0, 255]));
+        assert_eq!(*frame.get_pixel(13, 13), Rgba([255, 255, 255, 255]));
+        assert_eq!(*frame.get_pixel(8, 8), Rgba([255, 255, 255, 255]));
+    }
+
+    #[test]
+    fn test_composite_cursor_scales_to_frame() {
+        let mut frame = RgbaImage::from_pixel(60, 60, Rgba([255, 255, 255, 255]));
+        composite_cursor(&mut frame, &arrow(), 100, 50, 2.0, CursorOverlay::Visible);
+
+        // Hotspot lands on (20, 20) and the cursor is drawn at 8x8 pixels
+        assert_eq!(frame.get_pixel(18, 18)[0], 0);
+        assert_eq!(frame.get_pixel(25, 25)[0], 0);
+        assert_eq!(frame.get_pixel(26, 26)[0], 255);
+    }
+
+    #[test]
+    fn test_composite_cursor_hidden_is_noop() {
+        let mut frame = RgbaImage::from_pixel(20, 20, Rgba([255, 255, 255, 255]));
+        composite_cursor(&mut frame, &arrow(), 100, 50, 1.0, CursorOverlay::Hidden);
+        assert!(frame.pixels().all(|p| *p == Rgba([255, 255, 255, 255])));
+    }
+}
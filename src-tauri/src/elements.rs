@@ -0,0 +1,375 @@
+//! UI element detection for snap-to-element region selection
+//!
+//! Candidate rectangles come from two places: window bounds reported by
+//! xcap, and closed outlines found in the captured frames (panels, buttons,
+//! cards). Outlines are found with Canny edges, a one pixel dilation to
+//! close small gaps, and connected components; a component is kept if its
+//! edges run along most of its bounding box, then each side is snapped to
+//! the strongest intensity step so the rect is pixel exact.
+
+use image::{imageops, GrayImage, Luma, RgbaImage};
+use imageproc::distance_transform::Norm;
+use imageproc::edges::canny;
+use imageproc::morphology::dilate;
+use imageproc::region_labelling::{connected_components, Connectivity};
+use serde::Serialize;
+use std::thread;
+
+use crate::frames::get_frame;
+use crate::screenshot::MonitorFrame;
+use crate::window_capture::list_windows;
+
+const CANNY_LOW: f32 = 20.0;
+const CANNY_HIGH: f32 = 50.0;
+
+/// Radius of the dilation used to close gaps in outlines
+const DILATION: u8 = 1;
+
+/// Smallest element side, in points
+const MIN_ELEMENT_SIDE: f32 = 12.0;
+
+/// Fraction of the bounding box perimeter that must lie on an edge
+const MIN_PERIMETER_COVERAGE: f32 = 0.75;
+
+/// How far each side may move when snapping to the strongest intensity step
+const SNAP_DISTANCE: i64 = 2;
+
+/// Rects that differ by at most this many pixels on every side are duplicates
+const DUPLICATE_TOLERANCE: u32 = 2;
+
+/// Cap on edge-detected rects per monitor, largest first
+const MAX_RECTS_PER_MONITOR: usize = 400;
+
+/// Where a candidate rect came from
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ElementSource {
+    Window,
+    Edges,
+}
+
+/// A candidate rect in the region selector's coordinate space
+///
+/// That is global screen coordinates in the units xcap reports monitor
+/// positions in; multiplying a monitor-local offset by the monitor's scale
+/// factor gives frame pixels.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ElementRect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    pub source: ElementSource,
+}
+
+/// A rect in frame pixels
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct PixelRect {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
+impl PixelRect {
+    fn area(&self) -> u64 {
+        self.width as u64 * self.height as u64
+    }
+
+    fn is_near(&self, other: &PixelRect) -> bool {
+        let close = |a: u32, b: u32| a.abs_diff(b) <= DUPLICATE_TOLERANCE;
+        close(self.x, other.x)
+            && close(self.y, other.y)
+            && close(self.x + self.width, other.x + other.width)
+            && close(self.y + self.height, other.y + other.height)
+    }
+}
+
+/// Fraction of a rect's outline that is set in the edge map
+fn perimeter_coverage(edges: &GrayImage, rect: &PixelRect) -> f32 {
+    let (x0, y0) = (rect.x, rect.y);
+    let (x1, y1) = (rect.x + rect.width - 1, rect.y + rect.height - 1);
+    let is_edge = |x: u32, y: u32| edges.get_pixel(x, y)[0] > 0;
+
+    let mut total = 0u32;
+    let mut hits = 0u32;
+    for x in x0..=x1 {
+        for y in [y0, y1] {
+            total += 1;
+            hits += is_edge(x, y) as u32;
+        }
+    }
+    for y in y0..=y1 {
+        for x in [x0, x1] {
+            total += 1;
+            hits += is_edge(x, y) as u32;
+        }
+    }
+    hits as f32 / total as f32
+}
+
+/// Snap each side of a rect to the strongest intensity step near it
+///
+/// Canny's blur spreads edges over a couple of pixels; the actual boundary
+/// is where neighbouring rows or columns differ most. Ties go to the
+/// outermost step so a one pixel border stays inside the rect.
+fn snap_to_steps(gray: &GrayImage, rect: PixelRect) -> PixelRect {
+    let (width, height) = gray.dimensions();
+    let value = |x: u32, y: u32| gray.get_pixel(x, y)[0] as i64;
+
+    // Strength of the step between column `b - 1` and column `b`
+    let column_step = |b: i64| -> i64 {
+        if b <= 0 || b >= width as i64 {
+            return 0;
+        }
+        (rect.y..rect.y + rect.height)
+            .map(|y| (value(b as u32, y) - value(b as u32 - 1, y)).abs())
+            .sum()
+    };
+    let row_step = |b: i64| -> i64 {
+        if b <= 0 || b >= height as i64 {
+            return 0;
+        }
+        (rect.x..rect.x + rect.width)
+            .map(|x| (value(x, b as u32) - value(x, b as u32 - 1)).abs())
+            .sum()
+    };
+    // Candidates are ordered outermost first, and only a stronger step wins
+    let strongest = |candidates: &mut dyn Iterator<Item = i64>, step: &dyn Fn(i64) -> i64| {
+        let mut best = (None, 0);
+        for b in candidates {
+            let score = step(b);
+            if score > best.1 {
+                best = (Some(b), score);
+            }
+        }
+        best.0
+    };
+
+    let left = rect.x as i64;
+    let top = rect.y as i64;
+    let right = (rect.x + rect.width) as i64;
+    let bottom = (rect.y + rect.height) as i64;
+
+    let left = strongest(
+        &mut (left - SNAP_DISTANCE..=left + SNAP_DISTANCE),
+        &column_step,
+    )
+    .unwrap_or(left);
+    let top = strongest(&mut (top - SNAP_DISTANCE..=top + SNAP_DISTANCE), &row_step).unwrap_or(top);
+    let right = strongest(
+        &mut (right - SNAP_DISTANCE..=right + SNAP_DISTANCE).rev(),
+        &column_step,
+    )
+    .unwrap_or(right);
+    let bottom = strongest(
+        &mut (bottom - SNAP_DISTANCE..=bottom + SNAP_DISTANCE).rev(),
+        &row_step,
+    )
+    .unwrap_or(bottom);
+
+    if right <= left || bottom <= top {
+        return rect;
+    }
+    PixelRect {
+        x: left as u32,
+        y: top as u32,
+        width: (right - left) as u32,
+        height: (bottom - top) as u32,
+    }
+}
+
+/// Find rectangular outlines in a frame
+fn detect_edge_rects(gray: &GrayImage, min_side: u32) -> Vec<PixelRect> {
+    let (width, height) = gray.dimensions();
+    let edges = dilate(&canny(gray, CANNY_LOW, CANNY_HIGH), Norm::LInf, DILATION);
+    let labels = connected_components(&edges, Connectivity::Eight, Luma([0u8]));
+
+    // Bounding box of every component: (min_x, min_y, max_x, max_y)
+    let mut boxes: Vec<Option<(u32, u32, u32, u32)>> = Vec::new();
+    for (x, y, label) in labels.enumerate_pixels() {
+        let label = label[0] as usize;
+        if label == 0 {
+            continue;
+        }
+        if boxes.len() <= label {
+            boxes.resize(label + 1, None);
+        }
+        let bounds = boxes[label].get_or_insert((x, y, x, y));
+        bounds.0 = bounds.0.min(x);
+        bounds.1 = bounds.1.min(y);
+        bounds.2 = bounds.2.max(x);
+        bounds.3 = bounds.3.max(y);
+    }
+
+    let mut rects: Vec<PixelRect> = boxes
+        .into_iter()
+        .flatten()
+        .map(|(min_x, min_y, max_x, max_y)| PixelRect {
+            x: min_x,
+            y: min_y,
+            width: max_x - min_x + 1,
+            height: max_y - min_y + 1,
+        })
+        .filter(|rect| rect.width >= min_side && rect.height >= min_side)
+        // The whole screen isn't an element
+        .filter(|rect| rect.width < width * 98 / 100 || rect.height < height * 98 / 100)
+        .filter(|rect| perimeter_coverage(&edges, rect) >= MIN_PERIMETER_COVERAGE)
+        .map(|rect| snap_to_steps(gray, rect))
+        .collect();
+
+    rects.sort_by_key(|rect| std::cmp::Reverse(rect.area()));
+    let mut unique: Vec<PixelRect> = Vec::new();
+    for rect in rects {
+        if !unique.iter().any(|kept| kept.is_near(&rect)) {
+            unique.push(rect);
+        }
+        if unique.len() == MAX_RECTS_PER_MONITOR {
+            break;
+        }
+    }
+    unique
+}
+
+/// Edge-detected rects of one monitor frame in selector coordinates
+fn frame_elements(frame: &MonitorFrame, image: &RgbaImage) -> Vec<ElementRect> {
+    let scale = frame.scale_factor.max(0.1) as f64;
+    let min_side = (MIN_ELEMENT_SIDE * frame.scale_factor).round().max(1.0) as u32;
+    let gray = imageops::grayscale(image);
+
+    detect_edge_rects(&gray, min_side)
+        .into_iter()
+        .map(|rect| ElementRect {
+            x: frame.x as f64 + rect.x as f64 / scale,
+            y: frame.y as f64 + rect.y as f64 / scale,
+            width: rect.width as f64 / scale,
+            height: rect.height as f64 / scale,
+            source: ElementSource::Edges,
+        })
+        .collect()
+}
+
+/// Find candidate elements on the captured monitors
+///
+/// Best effort: monitors whose frame is gone and a failing window list are
+/// skipped rather than failing the selector. Window rects come first.
+pub fn detect_elements(frames: &[MonitorFrame]) -> Vec<ElementRect> {
+    let mut elements: Vec<ElementRect> = list_windows()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|window| !window.is_minimized && window.width > 0 && window.height > 0)
+        .map(|window| ElementRect {
+            x: window.x as f64,
+            y: window.y as f64,
+            width: window.width as f64,
+            height: window.height as f64,
+            source: ElementSource::Window,
+        })
+        .collect();
+
+    // Edge detection is the slow part, so monitors are processed in parallel
+    thread::scope(|scope| {
+        let handles: Vec<_> = frames
+            .iter()
+            .map(|frame| {
+                scope.spawn(move || match get_frame(&frame.frame_id) {
+                    Ok(stored) => frame_elements(frame, &stored.image),
+                    Err(_) => Vec::new(),
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            elements.extend(handle.join().unwrap_or_default());
+        }
+    });
+
+    elements
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    fn draw_filled(image: &mut GrayImage, rect: PixelRect, value: u8) {
+        for y in rect.y..rect.y + rect.height {
+            for x in rect.x..rect.x + rect.width {
+                image.put_pixel(x, y, Luma([value]));
+            }
+        }
+    }
+
+    #[test]
+    fn test_detects_panel_bounds() {
+        let mut image = GrayImage::from_pixel(200, 150, Luma([30]));
+        let panel = PixelRect {
+            x: 40,
+            y: 30,
+            width: 100,
+            height: 60,
+        };
+        draw_filled(&mut image, panel, 220);
+
+        let rects = detect_edge_rects(&image, 12);
+        assert!(rects.contains(&panel), "panel not found in {:?}", rects);
+    }
+
+    #[test]
+    fn test_ignores_small_and_open_shapes() {
+        let mut image = GrayImage::from_pixel(200, 150, Luma([30]));
+        // A dot and a single long line: neither is a rectangular element
+        draw_filled(
+            &mut image,
+            PixelRect {
+                x: 10,
+                y: 10,
+                width: 4,
+                height: 4,
+            },
+            220,
+        );
+        draw_filled(
+            &mut image,
+            PixelRect {
+                x: 20,
+                y: 100,
+                width: 150,
+                height: 1,
+            },
+            220,
+        );
+
+        assert!(detect_edge_rects(&image, 12).is_empty());
+    }
+
+    #[test]
+    fn test_frame_elements_map_to_selector_coordinates() {
+        let mut image = RgbaImage::from_pixel(200, 160, Rgba([20, 20, 20, 255]));
+        for y in 40..100 {
+            for x in 20..120 {
+                image.put_pixel(x, y, Rgba([240, 240, 240, 255]));
+            }
+        }
+        let frame = MonitorFrame {
+            id: 1,
+            x: 1000,
+            y: -50,
+            width: 100,
+            height: 80,
+            scale_factor: 2.0,
+            frame_id: "test".to_string(),
+            captured_at: 0,
+        };
+
+        let elements = frame_elements(&frame, &image);
+        let panel = elements
+            .iter()
+            .find(|e| (e.width - 50.0).abs() <= 1.0 && (e.height - 30.0).abs() <= 1.0)
+            .expect("panel element");
+        assert!((panel.x - 1010.0).abs() <= 1.0);
+        assert!((panel.y - -30.0).abs() <= 1.0);
+        assert_eq!(panel.source, ElementSource::Edges);
+    }
+}
@@ -0,0 +1,478 @@
+//! Typed capture lifecycle events
+//!
+//! Every capture gets an id when it starts and emits `capture-started`,
+//! then exactly one of `capture-saved`, `capture-cancelled` or
+//! `capture-failed`. Copying to the clipboard and post-processing such as
+//! OCR emit `capture-copied` and `capture-processed` under the same id; OCR
+//! captures keep no image, so they end with `capture-processed` instead of
+//! `capture-saved`.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{LazyLock, Mutex};
+use std::time::Instant;
+use tauri::{AppHandle, Emitter};
+
+use crate::coordinator::CaptureRejection;
+use crate::utils::{generate_id, get_timestamp, AppResult};
+use crate::window_capture::WindowInfo;
+
+pub const CAPTURE_STARTED_EVENT: &str = "capture-started";
+pub const CAPTURE_CANCELLED_EVENT: &str = "capture-cancelled";
+pub const CAPTURE_FAILED_EVENT: &str = "capture-failed";
+pub const CAPTURE_SAVED_EVENT: &str = "capture-saved";
+pub const CAPTURE_COPIED_EVENT: &str = "capture-copied";
+pub const CAPTURE_PROCESSED_EVENT: &str = "capture-processed";
+
+/// Captures started in one command and finished in another, by id
+static PENDING_CAPTURES: LazyLock<Mutex<HashMap<String, CaptureTracker>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// How a capture was taken
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CaptureMode {
+    PrimaryMonitor,
+    AllMonitors,
+    Region,
+    FrozenRegion,
+    LastRegion,
+    SavedRegion,
+    Window,
+    Scrolling,
+    NativeInteractive,
+    NativeFullscreen,
+    NativeWindow,
+    OcrRegion,
+    OcrTable,
+    ScanQrRegion,
+    RedactedRegion,
+}
+
+/// Broad reason a capture failed
+///
+/// `Cancelled` ends a capture with `capture-cancelled` rather than
+/// `capture-failed`.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CaptureErrorCode {
+    PermissionDenied,
+    Busy,
+    Timeout,
+    NotFound,
+    SaveFailed,
+    CaptureFailed,
+    Cancelled,
+    /// The capture isn't possible with this platform or backend
+    Unsupported,
+}
+
+/// A capture error and its code, set where the failure happens
+///
+/// Capture commands return it as is, so the frontend can branch on `code`.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptureError {
+    pub code: CaptureErrorCode,
+    /// Set when the capture coordinator turned the request away
+    pub rejection: Option<CaptureRejection>,
+    pub message: String,
+}
+
+impl CaptureError {
+    pub fn new(code: CaptureErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            rejection: None,
+            message: message.into(),
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.code == CaptureErrorCode::Cancelled
+    }
+}
+
+impl fmt::Display for CaptureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+/// Errors without a more specific code are capture failures
+impl From<String> for CaptureError {
+    fn from(message: String) -> Self {
+        Self::new(CaptureErrorCode::CaptureFailed, message)
+    }
+}
+
+impl From<&str> for CaptureError {
+    fn from(message: &str) -> Self {
+        Self::new(CaptureErrorCode::CaptureFailed, message)
+    }
+}
+
+/// For commands outside the capture lifecycle, such as recordings
+impl From<CaptureError> for String {
+    fn from(error: CaptureError) -> Self {
+        error.message
+    }
+}
+
+/// Window a capture was taken of
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptureWindow {
+    pub id: u32,
+    pub app_name: String,
+    pub title: String,
+}
+
+impl From<&WindowInfo> for CaptureWindow {
+    fn from(window: &WindowInfo) -> Self {
+        Self {
+            id: window.id,
+            app_name: window.app_name.clone(),
+            title: window.title.clone(),
+        }
+    }
+}
+
+/// An image written by a capture
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptureOutput {
+    pub path: String,
+    pub width: u32,
+    pub height: u32,
+    pub scale_factor: Option<f32>,
+    pub monitor_id: Option<u32>,
+}
+
+impl CaptureOutput {
+    /// Describe a saved image, reading its dimensions from the file header
+    pub fn from_file(path: &str, scale_factor: Option<f32>) -> Self {
+        let (width, height) = image::image_dimensions(path).unwrap_or_else(|e| {
+            eprintln!("Failed to read capture dimensions: {}", e);
+            (0, 0)
+        });
+
+        Self {
+            path: path.to_string(),
+            width,
+            height,
+            scale_factor,
+            monitor_id: None,
+        }
+    }
+}
+
+/// What was put on the clipboard
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClipboardContent {
+    Image { path: String },
+    Text { characters: usize },
+}
+
+/// Post-processing applied to a capture
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ProcessingOperation {
+    Ocr,
+    SensitiveWindowMask,
+    BarcodeScan,
+    Redaction,
+}
+
+/// Payload of `capture-started`
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptureStarted {
+    pub capture_id: String,
+    pub mode: CaptureMode,
+    /// Unix timestamp in milliseconds
+    pub started_at: u64,
+}
+
+/// Payload of `capture-cancelled`
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptureCancelled {
+    pub capture_id: String,
+    pub mode: CaptureMode,
+    pub duration_ms: u64,
+}
+
+/// Payload of `capture-failed`
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptureFailed {
+    pub capture_id: String,
+    pub mode: CaptureMode,
+    pub code: CaptureErrorCode,
+    pub message: String,
+    pub duration_ms: u64,
+}
+
+/// Payload of `capture-saved`
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptureSaved {
+    pub capture_id: String,
+    pub mode: CaptureMode,
+    pub window: Option<CaptureWindow>,
+    pub outputs: Vec<CaptureOutput>,
+    pub duration_ms: u64,
+}
+
+/// Payload of `capture-copied`
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptureCopied {
+    pub capture_id: String,
+    pub mode: CaptureMode,
+    pub content: ClipboardContent,
+    pub duration_ms: u64,
+}
+
+/// Payload of `capture-processed`
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptureProcessed {
+    pub capture_id: String,
+    pub mode: CaptureMode,
+    pub operation: ProcessingOperation,
+    pub input_path: Option<String>,
+    pub output_path: Option<String>,
+    /// Why the operation was skipped; the capture went ahead without it
+    pub skipped: Option<String>,
+    pub duration_ms: u64,
+}
+
+/// Emits the lifecycle events of one capture
+#[derive(Clone)]
+pub struct CaptureTracker {
+    app_handle: AppHandle,
+    capture_id: String,
+    mode: CaptureMode,
+    window: Option<CaptureWindow>,
+    started: Instant,
+}
+
+impl CaptureTracker {
+    /// Assign a capture id and emit `capture-started`
+    pub fn start(app_handle: &AppHandle, mode: CaptureMode) -> AppResult<Self> {
+        let tracker = Self {
+            app_handle: app_handle.clone(),
+            capture_id: generate_id()?,
+            mode,
+            window: None,
+            started: Instant::now(),
+        };
+        tracker.emit(
+            CAPTURE_STARTED_EVENT,
+            CaptureStarted {
+                capture_id: tracker.capture_id.clone(),
+                mode,
+                started_at: get_timestamp()?,
+            },
+        );
+        Ok(tracker)
+    }
+
+    pub fn capture_id(&self) -> &str {
+        &self.capture_id
+    }
+
+    /// Record the window being captured, reported with `capture-saved`
+    pub fn set_window(&mut self, window: CaptureWindow) {
+        self.window = Some(window);
+    }
+
+    fn duration_ms(&self) -> u64 {
+        self.started.elapsed().as_millis() as u64
+    }
+
+    fn emit<S: Serialize + Clone>(&self, event: &str, payload: S) {
+        if let Err(e) = self.app_handle.emit(event, payload) {
+            eprintln!("Failed to emit {}: {}", event, e);
+        }
+    }
+
+    pub fn saved(&self, outputs: Vec<CaptureOutput>) {
+        self.emit(
+            CAPTURE_SAVED_EVENT,
+            CaptureSaved {
+                capture_id: self.capture_id.clone(),
+                mode: self.mode,
+                window: self.window.clone(),
+                outputs,
+                duration_ms: self.duration_ms(),
+            },
+        );
+    }
+
+    pub fn copied(&self, content: ClipboardContent) {
+        self.emit(
+            CAPTURE_COPIED_EVENT,
+            CaptureCopied {
+                capture_id: self.capture_id.clone(),
+                mode: self.mode,
+                content,
+                duration_ms: self.duration_ms(),
+            },
+        );
+    }
+
+    pub fn processed(
+        &self,
+        operation: ProcessingOperation,
+        input_path: Option<String>,
+        output_path: Option<String>,
+    ) {
+        self.emit(
+            CAPTURE_PROCESSED_EVENT,
+            CaptureProcessed {
+                capture_id: self.capture_id.clone(),
+                mode: self.mode,
+                operation,
+                input_path,
+                output_path,
+                skipped: None,
+                duration_ms: self.duration_ms(),
+            },
+        );
+    }
+
+    /// Report that a processing step the user enabled couldn't run
+    pub fn skipped(&self, operation: ProcessingOperation, reason: &str) {
+        self.emit(
+            CAPTURE_PROCESSED_EVENT,
+            CaptureProcessed {
+                capture_id: self.capture_id.clone(),
+                mode: self.mode,
+                operation,
+                input_path: None,
+                output_path: None,
+                skipped: Some(reason.to_string()),
+                duration_ms: self.duration_ms(),
+            },
+        );
+    }
+
+    pub fn cancelled(&self) {
+        self.emit(
+            CAPTURE_CANCELLED_EVENT,
+            CaptureCancelled {
+                capture_id: self.capture_id.clone(),
+                mode: self.mode,
+                duration_ms: self.duration_ms(),
+            },
+        );
+    }
+
+    /// Emit `capture-failed`, or `capture-cancelled` if the error is a cancellation
+    pub fn failed(&self, error: &CaptureError) {
+        if error.is_cancelled() {
+            self.cancelled();
+            return;
+        }
+
+        self.emit(
+            CAPTURE_FAILED_EVENT,
+            CaptureFailed {
+                capture_id: self.capture_id.clone(),
+                mode: self.mode,
+                code: error.code,
+                message: error.message.clone(),
+                duration_ms: self.duration_ms(),
+            },
+        );
+    }
+
+    /// Emit the terminal event for a capture's result and pass it through
+    ///
+    /// `outputs` describes what a successful capture saved.
+    pub fn finish<T>(
+        &self,
+        result: Result<T, CaptureError>,
+        outputs: impl FnOnce(&T) -> Vec<CaptureOutput>,
+    ) -> Result<T, CaptureError> {
+        match &result {
+            Ok(value) => self.saved(outputs(value)),
+            Err(e) => self.failed(e),
+        }
+        result
+    }
+
+    /// Keep the capture open until another command finishes it
+    pub fn defer(self) {
+        if let Ok(mut pending) = PENDING_CAPTURES.lock() {
+            pending.insert(self.capture_id.clone(), self);
+        }
+    }
+}
+
+/// Take back a deferred capture to finish it
+pub fn take_deferred(capture_id: &str) -> Option<CaptureTracker> {
+    PENDING_CAPTURES
+        .lock()
+        .ok()
+        .and_then(|mut pending| pending.remove(capture_id))
+}
+
+/// Emit `capture-cancelled` for a deferred capture nobody finished
+pub fn abandon_deferred(capture_id: &str) {
+    if let Some(tracker) = take_deferred(capture_id) {
+        tracker.cancelled();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_untyped_errors_are_capture_failures() {
+        let error = CaptureError::from("Failed to capture monitor: boom".to_string());
+        assert_eq!(error.code, CaptureErrorCode::CaptureFailed);
+        assert!(!error.is_cancelled());
+
+        // The code comes from where the error was raised, not its wording
+        let error = CaptureError::new(CaptureErrorCode::NotFound, "No region was cancelled");
+        assert_eq!(error.code, CaptureErrorCode::NotFound);
+        assert!(CaptureError::new(CaptureErrorCode::Cancelled, "Stopped").is_cancelled());
+
+        let json = serde_json::to_value(&error).unwrap();
+        assert_eq!(json["code"], "not_found");
+        assert_eq!(json["rejection"], serde_json::Value::Null);
+    }
+
+    #[test]
+    fn test_payloads_serialize_camel_case() {
+        let saved = CaptureSaved {
+            capture_id: "1-0".to_string(),
+            mode: CaptureMode::LastRegion,
+            window: None,
+            outputs: vec![CaptureOutput {
+                path: "/tmp/a.png".to_string(),
+                width: 300,
+                height: 200,
+                scale_factor: Some(2.0),
+                monitor_id: Some(1),
+            }],
+            duration_ms: 12,
+        };
+        let json = serde_json::to_value(&saved).unwrap();
+
+        assert_eq!(json["captureId"], "1-0");
+        assert_eq!(json["mode"], "last_region");
+        assert_eq!(json["outputs"][0]["scaleFactor"], 2.0);
+        assert_eq!(json["durationMs"], 12);
+
+        let copied = serde_json::to_value(ClipboardContent::Text { characters: 5 }).unwrap();
+        assert_eq!(copied["type"], "text");
+    }
+}
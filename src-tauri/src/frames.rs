@@ -0,0 +1,128 @@
+//! In-memory frame store for captured monitor images
+//!
+//! Frames are kept as raw RGBA so the region selector can be shown
+//! without encoding anything to disk. The selector reads them through
+//! the `frame://` custom protocol and `capture_region` crops them in place.
+
+use image::{codecs::bmp::BmpEncoder, ExtendedColorType, ImageEncoder, RgbaImage};
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, Mutex};
+use tauri::http::{header, Request, Response, StatusCode};
+
+use crate::utils::{generate_id, AppResult};
+
+/// URI scheme used to serve stored frames to webviews
+pub const FRAME_PROTOCOL: &str = "frame";
+
+static FRAMES: LazyLock<Mutex<HashMap<String, Arc<StoredFrame>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// A captured monitor image and the monitor it came from
+pub struct StoredFrame {
+    pub monitor_id: u32,
+    pub scale_factor: f32,
+    pub image: RgbaImage,
+}
+
+/// Store a frame and return its unique id
+pub fn insert_frame(frame: StoredFrame) -> AppResult<String> {
+    let id = generate_id()?;
+
+    FRAMES
+        .lock()
+        .map_err(|e| format!("Failed to lock frame store: {}", e))?
+        .insert(id.clone(), Arc::new(frame));
+
+    Ok(id)
+}
+
+/// Get a stored frame by id
+pub fn get_frame(id: &str) -> AppResult<Arc<StoredFrame>> {
+    FRAMES
+        .lock()
+        .map_err(|e| format!("Failed to lock frame store: {}", e))?
+        .get(id)
+        .cloned()
+        .ok_or_else(|| format!("Frame not found: {}", id))
+}
+
+/// Drop a set of frames from the store
+pub fn remove_frames(ids: &[String]) {
+    if let Ok(mut frames) = FRAMES.lock() {
+        for id in ids {
+            frames.remove(id);
+        }
+    }
+}
+
+/// Encode a frame as an uncompressed BMP for display in a webview
+///
+/// BMP is used instead of PNG because encoding is a plain memory copy,
+/// which keeps the selector fast even for large multi-monitor setups.
+fn encode_frame_bmp(frame: &RgbaImage) -> AppResult<Vec<u8>> {
+    let mut buffer = Vec::new();
+    BmpEncoder::new(&mut buffer)
+        .write_image(
+            frame.as_raw(),
+            frame.width(),
+            frame.height(),
+            ExtendedColorType::Rgba8,
+        )
+        .map_err(|e| format!("Failed to encode frame: {}", e))?;
+    Ok(buffer)
+}
+
+/// Handle a `frame://localhost/<id>` request from a webview
+pub fn handle_frame_request(request: Request<Vec<u8>>) -> Response<Vec<u8>> {
+    let id = request.uri().path().trim_start_matches('/');
+
+    let result = get_frame(id).and_then(|frame| encode_frame_bmp(&frame.image));
+
+    let builder = Response::builder().header(header::ACCESS_CONTROL_ALLOW_ORIGIN, "*");
+    let response = match result {
+        Ok(bytes) => builder
+            .status(StatusCode::OK)
+            .header(header::CONTENT_TYPE, "image/bmp")
+            .body(bytes),
+        Err(e) => builder
+            .status(StatusCode::NOT_FOUND)
+            .header(header::CONTENT_TYPE, "text/plain")
+            .body(e.into_bytes()),
+    };
+
+    response.unwrap_or_else(|_| {
+        let mut fallback = Response::new(Vec::new());
+        *fallback.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+        fallback
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    #[test]
+    fn test_insert_and_get_frame() {
+        let id = insert_frame(StoredFrame {
+            monitor_id: 7,
+            scale_factor: 2.0,
+            image: RgbaImage::from_pixel(4, 3, Rgba([1, 2, 3, 255])),
+        })
+        .unwrap();
+
+        let frame = get_frame(&id).unwrap();
+        assert_eq!(frame.monitor_id, 7);
+        assert_eq!(frame.image.dimensions(), (4, 3));
+        assert_eq!(*frame.image.get_pixel(0, 0), Rgba([1, 2, 3, 255]));
+
+        remove_frames(std::slice::from_ref(&id));
+        assert!(get_frame(&id).is_err());
+    }
+
+    #[test]
+    fn test_encode_frame_bmp_header() {
+        let bytes = encode_frame_bmp(&RgbaImage::new(2, 2)).unwrap();
+        assert_eq!(&bytes[0..2], b"BM");
+    }
+}
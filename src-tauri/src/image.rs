@@ -1,7 +1,7 @@
 //! Image processing module
 
 use base64::{engine::general_purpose, Engine as _};
-use image::{DynamicImage, ImageFormat, Rgba, RgbaImage};
+use image::{imageops, DynamicImage, ImageFormat, Rgba, RgbaImage};
 use imageproc::filter::gaussian_blur_f32;
 use std::fs;
 use std::path::PathBuf;
@@ -46,12 +46,17 @@ impl CropRegion {
     }
 }
 
-/// Crop an image file and save to a new location
-pub fn crop_image(source_path: &str, region: CropRegion, save_dir: &str) -> AppResult<String> {
-    let img = image::open(source_path).map_err(|e| format!("Failed to open screenshot: {}", e))?;
-
-    let img_width = img.width();
-    let img_height = img.height();
+/// Crop an in-memory frame and save the result to a new location
+///
+/// Returns the saved path and the region actually cropped, after clamping
+/// it to the frame.
+pub fn crop_frame(
+    frame: &RgbaImage,
+    region: CropRegion,
+    save_dir: &str,
+) -> AppResult<(String, CropRegion)> {
+    let img_width = frame.width();
+    let img_height = frame.height();
 
     // Clamp region to image bounds
     let region = CropRegion::clamped(
@@ -70,9 +75,14 @@ pub fn crop_image(source_path: &str, region: CropRegion, save_dir: &str) -> AppR
         ));
     }
 
-    let cropped = img.crop_imm(region.x, region.y, region.width, region.height);
+    let cropped = imageops::crop_imm(frame, region.x, region.y, region.width, region.height);
 
-    save_image(&cropped, save_dir, "region")
+    let path = save_image(
+        &DynamicImage::ImageRgba8(cropped.to_image()),
+        save_dir,
+        "region",
+    )?;
+    Ok((path, region))
 }
 
 /// Save a DynamicImage to a directory with a generated filename
@@ -110,22 +120,45 @@ pub fn save_base64_image(image_data: &str, save_dir: &str, prefix: &str) -> AppR
     Ok(file_path.to_string_lossy().into_owned())
 }
 
-/// Copy a screenshot file to a destination directory
-pub fn copy_screenshot_to_dir(source_path: &str, save_dir: &str) -> AppResult<String> {
-    let src_path = PathBuf::from(source_path);
-    if !src_path.exists() {
-        return Err(format!("Screenshot file not found: {}", source_path));
-    }
-
-    let dest_path = PathBuf::from(save_dir);
-    ensure_dir(&dest_path)?;
-
-    let filename = generate_filename("shot", "png")?;
-    let file_path = dest_path.join(&filename);
-
-    fs::copy(&src_path, &file_path).map_err(|e| format!("Failed to copy screenshot: {}", e))?;
+/// Alpha-blend a circle or ring onto an image
+///
+/// Pixels between `inner_radius` and `outer_radius` from the center are
+/// blended with `color`; an inner radius of zero draws a filled disc.
+pub fn blend_circle(
+    img: &mut RgbaImage,
+    center_x: i32,
+    center_y: i32,
+    outer_radius: i32,
+    inner_radius: i32,
+    color: Rgba<u8>,
+) {
+    let outer_sq = (outer_radius * outer_radius) as i64;
+    let inner_sq = (inner_radius * inner_radius) as i64;
+    let alpha = color[3] as u32;
+
+    let min_x = (center_x - outer_radius).max(0);
+    let max_x = (center_x + outer_radius).min(img.width() as i32 - 1);
+    let min_y = (center_y - outer_radius).max(0);
+    let max_y = (center_y + outer_radius).min(img.height() as i32 - 1);
+
+    for y in min_y..=max_y {
+        for x in min_x..=max_x {
+            let dx = (x - center_x) as i64;
+            let dy = (y - center_y) as i64;
+            let dist_sq = dx * dx + dy * dy;
+            if dist_sq > outer_sq || dist_sq < inner_sq {
+                continue;
+            }
 
-    Ok(file_path.to_string_lossy().into_owned())
+            let pixel = img.get_pixel_mut(x as u32, y as u32);
+            for channel in 0..3 {
+                pixel[channel] = ((color[channel] as u32 * alpha
+                    + pixel[channel] as u32 * (255 - alpha))
+                    / 255) as u8;
+            }
+            pixel[3] = pixel[3].max(color[3]);
+        }
+    }
 }
 
 #[derive(Debug, Clone, serde::Deserialize)]
@@ -397,6 +430,66 @@ mod tests {
             assert_eq!(region.height, 1080);
             assert!(region.is_valid());
         }
+
+        #[test]
+        fn test_crop_frame_returns_clamped_region() {
+            let frame = RgbaImage::new(100, 50);
+            let save_dir = std::env::temp_dir().join("bettershot_crop_frame_test");
+            let requested = CropRegion {
+                x: 80,
+                y: 10,
+                width: 60,
+                height: 60,
+            };
+
+            let (path, region) = crop_frame(&frame, requested, save_dir.to_str().unwrap()).unwrap();
+
+            assert_eq!(
+                (region.x, region.y, region.width, region.height),
+                (80, 10, 20, 40)
+            );
+            assert_eq!(image::image_dimensions(&path).unwrap(), (20, 40));
+            let _ = std::fs::remove_dir_all(&save_dir);
+        }
+    }
+
+    mod blend_circle {
+        use super::*;
+
+        #[test]
+        fn test_blend_circle_filled() {
+            let mut img = RgbaImage::from_pixel(21, 21, Rgba([0, 0, 0, 255]));
+            blend_circle(&mut img, 10, 10, 5, 0, Rgba([255, 255, 255, 255]));
+
+            assert_eq!(*img.get_pixel(10, 10), Rgba([255, 255, 255, 255]));
+            assert_eq!(*img.get_pixel(0, 0), Rgba([0, 0, 0, 255]));
+        }
+
+        #[test]
+        fn test_blend_circle_ring_leaves_center() {
+            let mut img = RgbaImage::from_pixel(21, 21, Rgba([0, 0, 0, 255]));
+            blend_circle(&mut img, 10, 10, 8, 4, Rgba([255, 0, 0, 255]));
+
+            assert_eq!(*img.get_pixel(10, 10), Rgba([0, 0, 0, 255]));
+            assert_eq!(*img.get_pixel(16, 10), Rgba([255, 0, 0, 255]));
+        }
+
+        #[test]
+        fn test_blend_circle_half_alpha() {
+            let mut img = RgbaImage::from_pixel(5, 5, Rgba([0, 0, 0, 255]));
+            blend_circle(&mut img, 2, 2, 1, 0, Rgba([255, 255, 255, 128]));
+
+            assert_eq!(*img.get_pixel(2, 2), Rgba([128, 128, 128, 255]));
+        }
+
+        #[test]
+        fn test_blend_circle_clipped_at_edges() {
+            let mut img = RgbaImage::from_pixel(4, 4, Rgba([0, 0, 0, 255]));
+            blend_circle(&mut img, -2, -2, 4, 0, Rgba([255, 255, 255, 255]));
+
+            assert_eq!(*img.get_pixel(0, 0), Rgba([255, 255, 255, 255]));
+            assert_eq!(*img.get_pixel(3, 3), Rgba([0, 0, 0, 255]));
+        }
     }
 
     mod base64_validation {
@@ -0,0 +1,538 @@
+//! Interval and timelapse capture sessions
+//!
+//! A session captures the same monitor, region or window every N seconds
+//! into its own folder, skipping frames identical to the previous one.
+//! `manifest.json` is rewritten after every capture so an interrupted
+//! overnight session still leaves a usable record. When the session ends
+//! the kept frames can be assembled into a timelapse or a contact sheet.
+
+use image::{imageops, Rgba, RgbaImage};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+use crate::recording::{
+    encode_frames, ffmpeg_available, CaptureTarget, RecordingFormat, RecordingSource,
+};
+use crate::utils::{ensure_dir, generate_id, get_timestamp, AppResult};
+
+/// Event emitted after every capture attempt in a session
+pub const INTERVAL_SESSION_PROGRESS_EVENT: &str = "interval-session-progress";
+
+/// Event emitted once a session has stopped and its output is assembled
+pub const INTERVAL_SESSION_FINISHED_EVENT: &str = "interval-session-finished";
+
+const MANIFEST_FILE: &str = "manifest.json";
+
+/// How often a waiting session checks whether it was stopped
+const STOP_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+const CONTACT_SHEET_GAP: u32 = 8;
+const CONTACT_SHEET_BACKGROUND: Rgba<u8> = Rgba([30, 30, 30, 255]);
+
+static ACTIVE_SESSIONS: LazyLock<Mutex<HashMap<String, Arc<AtomicBool>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+fn default_timelapse_fps() -> u32 {
+    10
+}
+
+fn default_contact_sheet_columns() -> u32 {
+    5
+}
+
+fn default_thumbnail_width() -> u32 {
+    320
+}
+
+fn default_skip_duplicates() -> bool {
+    true
+}
+
+/// What to build from the kept frames when a session ends
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SessionAssembly {
+    Timelapse {
+        format: RecordingFormat,
+        #[serde(default = "default_timelapse_fps")]
+        fps: u32,
+    },
+    ContactSheet {
+        #[serde(default = "default_contact_sheet_columns")]
+        columns: u32,
+        #[serde(default = "default_thumbnail_width")]
+        thumbnail_width: u32,
+    },
+}
+
+/// Options for an interval capture session
+///
+/// The session runs until `duration_secs` elapses, `max_captures` attempts
+/// have been made, or it is stopped, whichever comes first.
+#[derive(Debug, Clone, Deserialize)]
+pub struct IntervalSessionOptions {
+    pub source: RecordingSource,
+    pub interval_secs: u32,
+    #[serde(default)]
+    pub duration_secs: Option<u32>,
+    #[serde(default)]
+    pub max_captures: Option<u32>,
+    /// Don't save a frame whose pixels match the previously saved one
+    #[serde(default = "default_skip_duplicates")]
+    pub skip_duplicates: bool,
+    #[serde(default)]
+    pub assemble: Option<SessionAssembly>,
+}
+
+/// A frame written to the session folder
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestFrame {
+    /// Capture attempt this frame came from, counting from zero
+    pub index: u32,
+    pub file: String,
+    pub captured_at: u64,
+    pub width: u32,
+    pub height: u32,
+    pub hash: String,
+}
+
+/// A capture attempt that failed, e.g. because the window was closed
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestFailure {
+    pub index: u32,
+    pub captured_at: u64,
+    pub message: String,
+}
+
+/// Contents of a session's `manifest.json`
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionManifest {
+    pub session_id: String,
+    pub source: RecordingSource,
+    pub interval_secs: u32,
+    pub started_at: u64,
+    pub finished_at: Option<u64>,
+    pub captures_attempted: u32,
+    pub skipped_duplicates: u32,
+    pub frames: Vec<ManifestFrame>,
+    pub failures: Vec<ManifestFailure>,
+    /// File name of the assembled timelapse or contact sheet
+    pub output: Option<String>,
+    pub assembly_error: Option<String>,
+}
+
+impl SessionManifest {
+    /// Write the manifest via a temporary file so a crash never leaves it truncated
+    fn write(&self, folder: &Path) -> AppResult<()> {
+        let json = serde_json::to_vec_pretty(self)
+            .map_err(|e| format!("Failed to serialize session manifest: {}", e))?;
+        let tmp_path = folder.join(format!("{}.tmp", MANIFEST_FILE));
+        fs::write(&tmp_path, json)
+            .map_err(|e| format!("Failed to write session manifest: {}", e))?;
+        fs::rename(&tmp_path, folder.join(MANIFEST_FILE))
+            .map_err(|e| format!("Failed to write session manifest: {}", e))
+    }
+}
+
+/// Returned when a session starts
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct IntervalSessionStarted {
+    pub session_id: String,
+    pub folder: String,
+}
+
+/// Payload of the `interval-session-progress` event
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct IntervalSessionProgress {
+    pub session_id: String,
+    pub captures_attempted: u32,
+    pub frames_saved: usize,
+    pub skipped_duplicates: u32,
+    /// Path of the frame saved by this attempt, if any
+    pub saved_path: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Payload of the `interval-session-finished` event
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct IntervalSessionFinished {
+    pub session_id: String,
+    pub folder: String,
+    pub manifest_path: String,
+    pub output_path: Option<String>,
+    pub error: Option<String>,
+}
+
+fn frame_hash(frame: &RgbaImage) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    frame.dimensions().hash(&mut hasher);
+    frame.as_raw().hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Sleep until `deadline`; returns `false` if the session was stopped first
+fn wait_until(deadline: Instant, stop: &AtomicBool) -> bool {
+    loop {
+        if stop.load(Ordering::SeqCst) {
+            return false;
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            return true;
+        }
+        thread::sleep((deadline - now).min(STOP_POLL_INTERVAL));
+    }
+}
+
+/// Lay frames out in a grid of thumbnails
+fn build_contact_sheet(
+    frames: impl Iterator<Item = AppResult<RgbaImage>>,
+    columns: u32,
+    thumbnail_width: u32,
+) -> AppResult<RgbaImage> {
+    let thumbnail_width = thumbnail_width.max(1);
+    let mut thumbnails = Vec::new();
+    for frame in frames {
+        let frame = frame?;
+        let height = ((frame.height() as u64 * thumbnail_width as u64)
+            / frame.width().max(1) as u64)
+            .max(1) as u32;
+        thumbnails.push(imageops::thumbnail(&frame, thumbnail_width, height));
+    }
+    if thumbnails.is_empty() {
+        return Err("No frames to build a contact sheet from".to_string());
+    }
+
+    let columns = columns.clamp(1, thumbnails.len() as u32);
+    let rows = (thumbnails.len() as u32).div_ceil(columns);
+    let cell_height = thumbnails.iter().map(|t| t.height()).max().unwrap_or(1);
+
+    let mut sheet = RgbaImage::from_pixel(
+        columns * thumbnail_width + (columns + 1) * CONTACT_SHEET_GAP,
+        rows * cell_height + (rows + 1) * CONTACT_SHEET_GAP,
+        CONTACT_SHEET_BACKGROUND,
+    );
+    for (i, thumbnail) in thumbnails.iter().enumerate() {
+        let (column, row) = (i as u32 % columns, i as u32 / columns);
+        let x = CONTACT_SHEET_GAP + column * (thumbnail_width + CONTACT_SHEET_GAP);
+        let y = CONTACT_SHEET_GAP + row * (cell_height + CONTACT_SHEET_GAP);
+        imageops::replace(&mut sheet, thumbnail, x as i64, y as i64);
+    }
+
+    Ok(sheet)
+}
+
+/// Build the requested timelapse or contact sheet from the saved frames
+fn assemble_output(
+    manifest: &SessionManifest,
+    assembly: &SessionAssembly,
+    folder: &Path,
+) -> AppResult<String> {
+    let frames = manifest.frames.iter().map(|frame| {
+        image::open(folder.join(&frame.file))
+            .map(|img| img.to_rgba8())
+            .map_err(|e| format!("Failed to load {}: {}", frame.file, e))
+    });
+
+    match assembly {
+        SessionAssembly::Timelapse { format, fps } => {
+            let file = format!("timelapse.{}", format.extension());
+            encode_frames(frames, *format, (*fps).max(1), &folder.join(&file))?;
+            Ok(file)
+        }
+        SessionAssembly::ContactSheet {
+            columns,
+            thumbnail_width,
+        } => {
+            let file = "contact_sheet.png".to_string();
+            build_contact_sheet(frames, *columns, *thumbnail_width)?
+                .save(folder.join(&file))
+                .map_err(|e| format!("Failed to save contact sheet: {}", e))?;
+            Ok(file)
+        }
+    }
+}
+
+/// Capture one frame and save it unless it duplicates the previous one
+///
+/// Returns the saved frame, or `None` if it was skipped as a duplicate.
+fn capture_into_session(
+    options: &IntervalSessionOptions,
+    folder: &Path,
+    index: u32,
+    captured_at: u64,
+    last_hash: &mut Option<u64>,
+) -> AppResult<Option<ManifestFrame>> {
+    // Re-resolve every time so a window that was closed and reopened is picked up again
+    let frame = CaptureTarget::resolve(&options.source)?.capture()?;
+    let hash = frame_hash(&frame);
+    if options.skip_duplicates && *last_hash == Some(hash) {
+        return Ok(None);
+    }
+
+    let file = format!("frame_{:05}.png", index);
+    frame
+        .save(folder.join(&file))
+        .map_err(|e| format!("Failed to save frame: {}", e))?;
+    *last_hash = Some(hash);
+
+    Ok(Some(ManifestFrame {
+        index,
+        file,
+        captured_at,
+        width: frame.width(),
+        height: frame.height(),
+        hash: format!("{:016x}", hash),
+    }))
+}
+
+fn run_session(
+    app_handle: &AppHandle,
+    session_id: &str,
+    options: &IntervalSessionOptions,
+    folder: &Path,
+    stop: &AtomicBool,
+) -> AppResult<SessionManifest> {
+    let mut manifest = SessionManifest {
+        session_id: session_id.to_string(),
+        source: options.source.clone(),
+        interval_secs: options.interval_secs,
+        started_at: get_timestamp()?,
+        finished_at: None,
+        captures_attempted: 0,
+        skipped_duplicates: 0,
+        frames: Vec::new(),
+        failures: Vec::new(),
+        output: None,
+        assembly_error: None,
+    };
+    manifest.write(folder)?;
+
+    let started = Instant::now();
+    let interval = Duration::from_secs(options.interval_secs as u64);
+    let end = options
+        .duration_secs
+        .map(|secs| started + Duration::from_secs(secs as u64));
+    let mut next_capture = started;
+    let mut last_hash = None;
+
+    loop {
+        if options
+            .max_captures
+            .is_some_and(|max| manifest.captures_attempted >= max)
+            || end.is_some_and(|end| next_capture >= end)
+            || !wait_until(next_capture, stop)
+        {
+            break;
+        }
+
+        let index = manifest.captures_attempted;
+        let captured_at = get_timestamp()?;
+        manifest.captures_attempted += 1;
+
+        let result = capture_into_session(options, folder, index, captured_at, &mut last_hash);
+        let (saved_path, error) = match result {
+            Ok(Some(frame)) => {
+                let path = folder.join(&frame.file).to_string_lossy().into_owned();
+                manifest.frames.push(frame);
+                (Some(path), None)
+            }
+            Ok(None) => {
+                manifest.skipped_duplicates += 1;
+                (None, None)
+            }
+            Err(message) => {
+                manifest.failures.push(ManifestFailure {
+                    index,
+                    captured_at,
+                    message: message.clone(),
+                });
+                (None, Some(message))
+            }
+        };
+        manifest.write(folder)?;
+
+        let _ = app_handle.emit(
+            INTERVAL_SESSION_PROGRESS_EVENT,
+            IntervalSessionProgress {
+                session_id: session_id.to_string(),
+                captures_attempted: manifest.captures_attempted,
+                frames_saved: manifest.frames.len(),
+                skipped_duplicates: manifest.skipped_duplicates,
+                saved_path,
+                error,
+            },
+        );
+
+        next_capture += interval;
+        // After a long stall (e.g. system sleep), resume from now instead of bursting
+        next_capture = next_capture.max(Instant::now());
+    }
+
+    manifest.finished_at = Some(get_timestamp()?);
+    if let Some(assembly) = &options.assemble {
+        if !manifest.frames.is_empty() {
+            match assemble_output(&manifest, assembly, folder) {
+                Ok(file) => manifest.output = Some(file),
+                Err(e) => manifest.assembly_error = Some(e),
+            }
+        }
+    }
+    manifest.write(folder)?;
+
+    Ok(manifest)
+}
+
+/// Start an interval capture session in the background
+///
+/// Frames go into a new `session_<timestamp>` folder under `save_dir`.
+pub fn start_interval_session(
+    app_handle: &AppHandle,
+    options: IntervalSessionOptions,
+    save_dir: &str,
+) -> AppResult<IntervalSessionStarted> {
+    if options.interval_secs == 0 {
+        return Err("Capture interval must be at least one second".to_string());
+    }
+    if let Some(SessionAssembly::Timelapse { format, .. }) = &options.assemble {
+        if *format != RecordingFormat::Gif && !ffmpeg_available() {
+            return Err(
+                "ffmpeg is required for MP4/WebM timelapses but was not found on PATH".to_string(),
+            );
+        }
+    }
+    // Fail fast if the source doesn't exist rather than logging failures all night
+    CaptureTarget::resolve(&options.source)?;
+
+    let session_id = generate_id()?;
+    let folder = PathBuf::from(save_dir).join(format!("session_{}", get_timestamp()?));
+    ensure_dir(&folder)?;
+
+    let stop = Arc::new(AtomicBool::new(false));
+    ACTIVE_SESSIONS
+        .lock()
+        .map_err(|e| format!("Failed to lock interval sessions: {}", e))?
+        .insert(session_id.clone(), stop.clone());
+
+    let started = IntervalSessionStarted {
+        session_id: session_id.clone(),
+        folder: folder.to_string_lossy().into_owned(),
+    };
+
+    let app_handle = app_handle.clone();
+    thread::spawn(move || {
+        let result = run_session(&app_handle, &session_id, &options, &folder, &stop);
+
+        if let Ok(mut sessions) = ACTIVE_SESSIONS.lock() {
+            sessions.remove(&session_id);
+        }
+
+        let (output_path, error) = match result {
+            Ok(manifest) => (
+                manifest
+                    .output
+                    .map(|file| folder.join(file).to_string_lossy().into_owned()),
+                manifest.assembly_error,
+            ),
+            Err(e) => (None, Some(e)),
+        };
+        let _ = app_handle.emit(
+            INTERVAL_SESSION_FINISHED_EVENT,
+            IntervalSessionFinished {
+                session_id,
+                folder: folder.to_string_lossy().into_owned(),
+                manifest_path: folder.join(MANIFEST_FILE).to_string_lossy().into_owned(),
+                output_path,
+                error,
+            },
+        );
+    });
+
+    Ok(started)
+}
+
+/// Ask a running session to stop after its current capture
+pub fn stop_interval_session(session_id: &str) -> AppResult<()> {
+    let sessions = ACTIVE_SESSIONS
+        .lock()
+        .map_err(|e| format!("Failed to lock interval sessions: {}", e))?;
+
+    let stop = sessions
+        .get(session_id)
+        .ok_or_else(|| format!("No interval session with id {}", session_id))?;
+    stop.store(true, Ordering::SeqCst);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_hash_detects_changes() {
+        let a = RgbaImage::from_pixel(4, 4, Rgba([10, 20, 30, 255]));
+        let mut b = a.clone();
+        assert_eq!(frame_hash(&a), frame_hash(&b));
+
+        b.put_pixel(3, 3, Rgba([10, 20, 31, 255]));
+        assert_ne!(frame_hash(&a), frame_hash(&b));
+    }
+
+    #[test]
+    fn test_wait_until_returns_early_when_stopped() {
+        let stop = AtomicBool::new(true);
+        let deadline = Instant::now() + Duration::from_secs(60);
+        assert!(!wait_until(deadline, &stop));
+
+        let stop = AtomicBool::new(false);
+        assert!(wait_until(Instant::now(), &stop));
+    }
+
+    #[test]
+    fn test_contact_sheet_layout() {
+        let colors = [[255, 0, 0], [0, 255, 0], [0, 0, 255]];
+        let frames = colors
+            .iter()
+            .map(|&[r, g, b]| Ok(RgbaImage::from_pixel(40, 20, Rgba([r, g, b, 255]))));
+
+        let sheet = build_contact_sheet(frames, 2, 20).unwrap();
+
+        let gap = CONTACT_SHEET_GAP;
+        assert_eq!(sheet.dimensions(), (2 * 20 + 3 * gap, 2 * 10 + 3 * gap));
+        assert_eq!(*sheet.get_pixel(gap, gap), Rgba([255, 0, 0, 255]));
+        assert_eq!(*sheet.get_pixel(2 * gap + 20, gap), Rgba([0, 255, 0, 255]));
+        assert_eq!(*sheet.get_pixel(gap, 2 * gap + 10), Rgba([0, 0, 255, 255]));
+        assert_eq!(*sheet.get_pixel(0, 0), CONTACT_SHEET_BACKGROUND);
+    }
+
+    #[test]
+    fn test_session_options_deserialize() {
+        let options: IntervalSessionOptions = serde_json::from_str(
+            r#"{"source":{"type":"monitor","monitor_id":1},"interval_secs":30,"duration_secs":3600,"assemble":{"type":"contact_sheet"}}"#,
+        )
+        .unwrap();
+
+        assert!(options.skip_duplicates);
+        assert_eq!(options.max_captures, None);
+        assert!(matches!(
+            options.assemble,
+            Some(SessionAssembly::ContactSheet { columns: 5, .. })
+        ));
+    }
+}
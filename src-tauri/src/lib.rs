@@ -4,20 +4,50 @@
 //! and saving screenshots with various features like region selection
 //! and background customization.
 
+mod backend;
+mod barcode;
 mod clipboard;
 mod commands;
+mod contrast;
+mod coordinator;
+mod countdown;
+mod cursor;
+mod elements;
+mod events;
+mod frames;
 mod image;
+mod interval;
+mod monitors;
 mod ocr;
+mod ocr_export;
+mod ocr_layout;
+mod ocr_table;
+#[cfg(target_os = "linux")]
+mod portal;
+mod privacy;
+mod recording;
+mod redaction;
+mod regions;
 mod screenshot;
+mod scroll;
+mod selector;
 mod utils;
+mod window_capture;
 
 use commands::{
-    capture_all_monitors, capture_region, capture_once, cleanup_temp_file,
-    copy_image_file_to_clipboard, emit_capture_complete, get_desktop_directory,
-    get_mouse_position, get_temp_directory, move_window_to_active_space,
-    native_capture_fullscreen, native_capture_interactive, native_capture_ocr_region,
-    native_capture_window, open_region_selector, play_screenshot_sound,
-    render_image_with_effects_rust, restore_main_window, save_edited_image,
+    audit_contrast, cancel_delayed_capture, cancel_native_capture, capture_all_monitors,
+    capture_once, capture_region, capture_saved_region, capture_window_image, cleanup_temp_file,
+    copy_image_file_to_clipboard, delete_saved_region, emit_capture_complete, export_ocr,
+    extract_table, get_capture_state, get_desktop_directory, get_diagnostics, get_mouse_position,
+    get_region_history, get_temp_directory, list_monitors, list_saved_regions, list_windows,
+    move_window_to_active_space, native_capture_fullscreen, native_capture_interactive,
+    native_capture_ocr_region, native_capture_ocr_table, native_capture_redacted,
+    native_capture_scan_qr_region, native_capture_window, open_frozen_region_selector,
+    open_region_selector, pause_recording, play_screenshot_sound, recapture_last_region,
+    recognize_layout, recognize_text, region_selector_ready, render_image_with_effects_rust,
+    restore_main_window, resume_recording, save_edited_image, save_region, scan_barcodes,
+    scroll_capture, start_interval_session, start_recording, stop_interval_session, stop_recording,
+    stop_scroll_capture, suggest_redactions,
 };
 
 use tauri::{Emitter, Manager, WebviewUrl, WebviewWindowBuilder};
@@ -76,6 +106,9 @@ pub fn run() {
             tauri_plugin_autostart::MacosLauncher::LaunchAgent,
             Some(vec!["--hidden"]),
         ))
+        .register_uri_scheme_protocol(frames::FRAME_PROTOCOL, |_ctx, request| {
+            frames::handle_frame_request(request)
+        })
         .setup(|app| {
             use tauri::menu::{ MenuBuilder, MenuItemBuilder, PredefinedMenuItem};
 
@@ -166,11 +199,21 @@ pub fn run() {
                     .ok();
             }
 
+            // Invalidate remembered regions and selectors when displays change
+            monitors::watch_monitors(app.handle().clone());
+
             let open_item = MenuItemBuilder::with_id("open", "Open Better Shot").build(app)?;
 
             let capture_region_item =
                 MenuItemBuilder::with_id("capture_region", "Capture Region").build(app)?;
 
+            let capture_frozen_item =
+                MenuItemBuilder::with_id("capture_frozen", "Freeze & Capture Region").build(app)?;
+
+            let capture_last_region_item =
+                MenuItemBuilder::with_id("capture_last_region", "Capture Previous Region")
+                    .build(app)?;
+
             let capture_screen_item =
                 MenuItemBuilder::with_id("capture_screen", "Capture Screen").build(app)?;
 
@@ -180,6 +223,18 @@ pub fn run() {
             let capture_ocr_item =
                 MenuItemBuilder::with_id("capture_ocr", "OCR Region").build(app)?;
 
+            let capture_ocr_table_item =
+                MenuItemBuilder::with_id("capture_ocr_table", "OCR Table").build(app)?;
+
+            let capture_scan_qr_item =
+                MenuItemBuilder::with_id("capture_scan_qr", "Scan QR Region").build(app)?;
+
+            let capture_redacted_item =
+                MenuItemBuilder::with_id("capture_redacted", "Capture & Redact Region").build(app)?;
+
+            let stop_recording_item =
+                MenuItemBuilder::with_id("stop_recording", "Stop Recording").build(app)?;
+
             let preferences_item =
                 MenuItemBuilder::with_id("preferences", "Preferences...")
                     .accelerator("CommandOrControl+,")
@@ -194,16 +249,23 @@ pub fn run() {
                     &open_item,
                     &PredefinedMenuItem::separator(app)?,
                     &capture_region_item,
+                    &capture_frozen_item,
+                    &capture_last_region_item,
                     &capture_screen_item,
                     &capture_window_item,
                     &capture_ocr_item,
+                    &capture_ocr_table_item,
+                    &capture_scan_qr_item,
+                    &capture_redacted_item,
+                    &PredefinedMenuItem::separator(app)?,
+                    &stop_recording_item,
                     &PredefinedMenuItem::separator(app)?,
                     &preferences_item,
                     &PredefinedMenuItem::separator(app)?,
                     &quit_item,
                 ])
                 .build()?;
-            let _tray = tauri::tray::TrayIconBuilder::new()
+            let _tray = tauri::tray::TrayIconBuilder::with_id(countdown::TRAY_ID)
                 .menu(&menu)
                 .icon(app.default_window_icon().unwrap().clone())
                 .tooltip("Better Shot")
@@ -217,6 +279,12 @@ pub fn run() {
                         "capture_region" => {
                             let _ = app.emit("capture-triggered", ());
                         }
+                        "capture_frozen" => {
+                            let _ = app.emit("capture-freeze", ());
+                        }
+                        "capture_last_region" => {
+                            let _ = app.emit("capture-last-region", ());
+                        }
                         "capture_screen" => {
                             let _ = app.emit("capture-fullscreen", ());
                         }
@@ -226,6 +294,24 @@ pub fn run() {
                         "capture_ocr" => {
                             let _ = app.emit("capture-ocr", ());
                         }
+                        "capture_ocr_table" => {
+                            let _ = app.emit("capture-ocr-table", ());
+                        }
+                        "capture_scan_qr" => {
+                            let _ = app.emit("capture-scan-qr", ());
+                        }
+                        "capture_redacted" => {
+                            let _ = app.emit("capture-redacted", ());
+                        }
+                        "stop_recording" => {
+                            // Encoding can take a while; the result is emitted as recording-finished
+                            let app = app.clone();
+                            std::thread::spawn(move || {
+                                if let Err(e) = recording::stop_recording(&app) {
+                                    eprintln!("Failed to stop recording: {}", e);
+                                }
+                            });
+                        }
                         "preferences" => {
                             if let Err(e) = show_main_window(app) {
                                 eprintln!("Failed to show window: {}", e);
@@ -255,11 +341,44 @@ pub fn run() {
             native_capture_fullscreen,
             native_capture_window,
             native_capture_ocr_region,
+            native_capture_ocr_table,
+            native_capture_scan_qr_region,
+            native_capture_redacted,
             play_screenshot_sound,
             get_mouse_position,
             move_window_to_active_space,
             copy_image_file_to_clipboard,
             open_region_selector,
+            open_frozen_region_selector,
+            region_selector_ready,
+            cancel_delayed_capture,
+            get_capture_state,
+            cancel_native_capture,
+            scroll_capture,
+            stop_scroll_capture,
+            start_recording,
+            pause_recording,
+            resume_recording,
+            stop_recording,
+            start_interval_session,
+            stop_interval_session,
+            recapture_last_region,
+            capture_saved_region,
+            get_region_history,
+            list_saved_regions,
+            save_region,
+            delete_saved_region,
+            list_windows,
+            list_monitors,
+            get_diagnostics,
+            recognize_text,
+            recognize_layout,
+            extract_table,
+            scan_barcodes,
+            suggest_redactions,
+            audit_contrast,
+            export_ocr,
+            capture_window_image,
             emit_capture_complete,
             cleanup_temp_file,
             restore_main_window
@@ -0,0 +1,186 @@
+//! Display configuration watcher
+//!
+//! Polls the monitor layout and emits `monitors-changed` when a monitor is
+//! added or removed or its resolution, position or scale changes. Regions
+//! remembered on a removed monitor are invalidated until it comes back, and
+//! an open region selector is closed if one of the monitors it shows is gone.
+
+use serde::Serialize;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::regions::invalidate_monitor_regions;
+use crate::screenshot::{monitor_layout, MonitorGeometry};
+
+/// Event emitted when the display configuration changes
+pub const MONITORS_CHANGED_EVENT: &str = "monitors-changed";
+
+/// How often the monitor layout is checked
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+const REGION_SELECTOR_LABEL: &str = "region-selector";
+
+/// Capture id of the open region selector and the monitors it shows
+static SELECTOR_MONITORS: Mutex<Option<(String, Vec<u32>)>> = Mutex::new(None);
+
+/// Remember which monitors a newly opened region selector shows
+pub fn track_selector_monitors(capture_id: &str, monitor_ids: Vec<u32>) {
+    if let Ok(mut shown) = SELECTOR_MONITORS.lock() {
+        *shown = Some((capture_id.to_string(), monitor_ids));
+    }
+}
+
+/// Forget a closed selector's monitors, unless a newer selector replaced it
+pub fn untrack_selector_monitors(capture_id: &str) {
+    if let Ok(mut shown) = SELECTOR_MONITORS.lock() {
+        if shown.as_ref().is_some_and(|(id, _)| id == capture_id) {
+            *shown = None;
+        }
+    }
+}
+
+/// Whether the open selector shows one of the removed monitors
+fn selector_lost_monitor(removed: &[u32]) -> bool {
+    SELECTOR_MONITORS.lock().is_ok_and(|shown| {
+        shown
+            .as_ref()
+            .is_some_and(|(_, monitor_ids)| monitor_ids.iter().any(|id| removed.contains(id)))
+    })
+}
+
+/// Payload of the `monitors-changed` event
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct MonitorsChanged {
+    /// The full layout after the change
+    pub monitors: Vec<MonitorGeometry>,
+    pub added: Vec<u32>,
+    pub removed: Vec<u32>,
+    /// Monitors whose geometry or scale changed
+    pub changed: Vec<u32>,
+}
+
+/// Compare two layouts, returning `None` if nothing changed
+fn diff_layouts(
+    previous: &[MonitorGeometry],
+    current: &[MonitorGeometry],
+) -> Option<MonitorsChanged> {
+    let find = |layout: &[MonitorGeometry], id: u32| layout.iter().find(|m| m.id == id).copied();
+
+    let added: Vec<u32> = current
+        .iter()
+        .filter(|m| find(previous, m.id).is_none())
+        .map(|m| m.id)
+        .collect();
+    let removed: Vec<u32> = previous
+        .iter()
+        .filter(|m| find(current, m.id).is_none())
+        .map(|m| m.id)
+        .collect();
+    let changed: Vec<u32> = current
+        .iter()
+        .filter(|m| find(previous, m.id).is_some_and(|old| old != **m))
+        .map(|m| m.id)
+        .collect();
+
+    if added.is_empty() && removed.is_empty() && changed.is_empty() {
+        return None;
+    }
+    Some(MonitorsChanged {
+        monitors: current.to_vec(),
+        added,
+        removed,
+        changed,
+    })
+}
+
+fn handle_change(app_handle: &AppHandle, change: MonitorsChanged) {
+    if !change.removed.is_empty() || !change.added.is_empty() {
+        if let Err(e) = invalidate_monitor_regions(app_handle, &change.removed, &change.added) {
+            eprintln!("Failed to invalidate regions: {}", e);
+        }
+    }
+
+    // Part of the selector would cover a screen that is gone
+    if selector_lost_monitor(&change.removed) {
+        if let Some(selector) = app_handle.get_webview_window(REGION_SELECTOR_LABEL) {
+            selector.close().ok();
+        }
+    }
+
+    if let Err(e) = app_handle.emit(MONITORS_CHANGED_EVENT, change) {
+        eprintln!("Failed to emit {}: {}", MONITORS_CHANGED_EVENT, e);
+    }
+}
+
+/// Start watching the display configuration in the background
+pub fn watch_monitors(app_handle: AppHandle) {
+    thread::spawn(move || {
+        let mut previous = monitor_layout().unwrap_or_default();
+
+        loop {
+            thread::sleep(POLL_INTERVAL);
+
+            // Enumeration can fail briefly while displays reconfigure; just
+            // compare against the next successful read
+            let current = match monitor_layout() {
+                Ok(current) => current,
+                Err(_) => continue,
+            };
+            if let Some(change) = diff_layouts(&previous, &current) {
+                handle_change(&app_handle, change);
+            }
+            previous = current;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn monitor(id: u32, x: i32, width: u32, scale_factor: f32) -> MonitorGeometry {
+        MonitorGeometry {
+            id,
+            x,
+            y: 0,
+            width,
+            height: 1080,
+            scale_factor,
+        }
+    }
+
+    #[test]
+    fn test_unchanged_layout_is_not_reported() {
+        let layout = vec![monitor(1, 0, 1920, 1.0), monitor(2, 1920, 2560, 2.0)];
+        assert_eq!(diff_layouts(&layout, &layout), None);
+    }
+
+    #[test]
+    fn test_diff_reports_added_removed_and_changed() {
+        let docked = vec![monitor(1, 0, 1920, 1.0), monitor(2, 1920, 2560, 2.0)];
+        let undocked = vec![monitor(1, 0, 1920, 2.0), monitor(3, -1280, 1280, 1.0)];
+
+        let change = diff_layouts(&docked, &undocked).unwrap();
+        assert_eq!(change.added, vec![3]);
+        assert_eq!(change.removed, vec![2]);
+        assert_eq!(change.changed, vec![1]);
+        assert_eq!(change.monitors, undocked);
+    }
+
+    #[test]
+    fn test_selector_closes_only_for_its_own_monitors() {
+        track_selector_monitors("first", vec![1, 2]);
+        assert!(!selector_lost_monitor(&[3]));
+        assert!(selector_lost_monitor(&[2]));
+
+        // A replaced selector closing late keeps the new one tracked
+        track_selector_monitors("second", vec![1]);
+        untrack_selector_monitors("first");
+        assert!(selector_lost_monitor(&[1]));
+
+        untrack_selector_monitors("second");
+        assert!(!selector_lost_monitor(&[1]));
+    }
+}
@@ -1,77 +1,1066 @@
-//! OCR module using macOS Vision framework
+//! Text recognition
+//!
+//! OCR runs through an `OcrEngine`. macOS uses the Vision framework; where
+//! Vision isn't available a local `tesseract` binary is used instead, found
+//! on `PATH` or through `BETTERSHOT_TESSERACT`.
+//!
+//! Engines return an `OcrResult` of blocks, lines and words with their
+//! bounding boxes, confidence and alternative readings, so features beyond
+//! copying text can work with where the text is.
+//!
+//! Languages, speed and the other recognition options come from settings
+//! as `OcrOptions` and are passed to whichever engine runs.
 
-use crate::utils::AppResult;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
 
+use crate::ocr_layout::{OcrLayout, OcrTextFormat};
+use crate::utils::{generate_id, value_or, AppResult, SETTINGS_STORE};
+
+/// Environment variable with the path of the `tesseract` binary to use
+const TESSERACT_VAR: &str = "BETTERSHOT_TESSERACT";
+
+const LANGUAGES_KEY: &str = "ocrLanguages";
+const AUTO_LANGUAGE_KEY: &str = "ocrAutomaticLanguageDetection";
+const LEVEL_KEY: &str = "ocrRecognitionLevel";
+const CORRECTION_KEY: &str = "ocrLanguageCorrection";
+const CUSTOM_WORDS_KEY: &str = "ocrCustomWords";
+const MIN_TEXT_HEIGHT_KEY: &str = "ocrMinimumTextHeight";
+
+/// Tesseract data names for BCP 47 language codes, covering the languages
+/// Vision recognizes
+const TESSERACT_LANGUAGES: &[(&str, &str)] = &[
+    ("ar", "ara"),
+    ("de", "deu"),
+    ("en", "eng"),
+    ("es", "spa"),
+    ("fr", "fra"),
+    ("it", "ita"),
+    ("ja", "jpn"),
+    ("ko", "kor"),
+    ("pt", "por"),
+    ("ru", "rus"),
+    ("th", "tha"),
+    ("uk", "ukr"),
+    ("vi", "vie"),
+    ("yue", "chi_tra"),
+    ("zh", "chi_sim"),
+    ("zh-hans", "chi_sim"),
+    ("zh-hant", "chi_tra"),
+];
+
+/// Speed against accuracy trade-off
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RecognitionLevel {
+    Fast,
+    #[default]
+    Accurate,
+}
+
+/// How text should be recognized
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct OcrOptions {
+    /// BCP 47 codes such as `en-US` or `ja`, most likely first; empty for
+    /// the engine's default
+    pub languages: Vec<String>,
+    pub automatic_language_detection: bool,
+    pub recognition_level: RecognitionLevel,
+    pub language_correction: bool,
+    /// Names and jargon to prefer during correction
+    pub custom_words: Vec<String>,
+    /// Smallest text to recognize, as a fraction of the image height; 0
+    /// for no limit
+    pub minimum_text_height: f32,
+}
+
+impl Default for OcrOptions {
+    fn default() -> Self {
+        Self {
+            languages: Vec::new(),
+            automatic_language_detection: false,
+            recognition_level: RecognitionLevel::default(),
+            language_correction: true,
+            custom_words: Vec::new(),
+            minimum_text_height: 0.0,
+        }
+    }
+}
+
+impl OcrOptions {
+    /// Load the options from `settings.json`, falling back to the defaults
+    pub fn load(app_handle: &AppHandle) -> AppResult<Self> {
+        let store = app_handle
+            .store(SETTINGS_STORE)
+            .map_err(|e| format!("Failed to open settings: {}", e))?;
+        let defaults = Self::default();
+
+        Ok(Self {
+            languages: value_or(store.get(LANGUAGES_KEY), defaults.languages),
+            automatic_language_detection: value_or(
+                store.get(AUTO_LANGUAGE_KEY),
+                defaults.automatic_language_detection,
+            ),
+            recognition_level: value_or(store.get(LEVEL_KEY), defaults.recognition_level),
+            language_correction: value_or(store.get(CORRECTION_KEY), defaults.language_correction),
+            custom_words: value_or(store.get(CUSTOM_WORDS_KEY), defaults.custom_words),
+            minimum_text_height: value_or(
+                store.get(MIN_TEXT_HEIGHT_KEY),
+                defaults.minimum_text_height,
+            ),
+        })
+    }
+
+    fn custom_words(&self) -> impl Iterator<Item = &str> {
+        self.custom_words
+            .iter()
+            .map(|word| word.trim())
+            .filter(|word| !word.is_empty())
+    }
+}
+
+/// A rectangle in image pixels, from the top-left corner
+#[derive(Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PixelRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl PixelRect {
+    pub fn right(&self) -> u32 {
+        self.x + self.width
+    }
+
+    pub fn bottom(&self) -> u32 {
+        self.y + self.height
+    }
+
+    /// The smallest rectangle containing both
+    pub fn union(&self, other: &PixelRect) -> PixelRect {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        PixelRect {
+            x,
+            y,
+            width: self.right().max(other.right()) - x,
+            height: self.bottom().max(other.bottom()) - y,
+        }
+    }
+
+    fn normalized(&self, image_width: u32, image_height: u32) -> NormalizedRect {
+        let width = image_width.max(1) as f64;
+        let height = image_height.max(1) as f64;
+        NormalizedRect {
+            x: self.x as f64 / width,
+            y: self.y as f64 / height,
+            width: self.width as f64 / width,
+            height: self.height as f64 / height,
+        }
+    }
+}
+
+/// A rectangle as fractions of the image size, from the top-left corner
+#[derive(Serialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct NormalizedRect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl NormalizedRect {
+    #[cfg(any(target_os = "macos", test))]
+    fn to_pixels(self, image_width: u32, image_height: u32) -> PixelRect {
+        let scale = |value: f64, size: u32| (value.clamp(0.0, 1.0) * size as f64).round() as u32;
+        let x = scale(self.x, image_width);
+        let y = scale(self.y, image_height);
+        PixelRect {
+            x,
+            y,
+            width: scale(self.x + self.width, image_width).saturating_sub(x),
+            height: scale(self.y + self.height, image_height).saturating_sub(y),
+        }
+    }
+}
+
+/// Another reading of the same text
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct OcrAlternative {
+    pub text: String,
+    pub confidence: f32,
+}
+
+/// A recognized word
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct OcrWord {
+    pub text: String,
+    pub bbox: PixelRect,
+    pub normalized_bbox: NormalizedRect,
+    /// From 0 to 1
+    pub confidence: f32,
+    pub alternatives: Vec<OcrAlternative>,
+}
+
+/// A line of text
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct OcrLine {
+    pub text: String,
+    pub bbox: PixelRect,
+    pub normalized_bbox: NormalizedRect,
+    pub confidence: f32,
+    pub alternatives: Vec<OcrAlternative>,
+    pub words: Vec<OcrWord>,
+}
+
+/// A block of lines that belong together, like a paragraph
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct OcrBlock {
+    pub bbox: PixelRect,
+    pub normalized_bbox: NormalizedRect,
+    pub confidence: f32,
+    pub lines: Vec<OcrLine>,
+}
+
+/// Everything recognized in one image
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct OcrResult {
+    pub engine: &'static str,
+    /// Image size in pixels
+    pub width: u32,
+    pub height: u32,
+    pub blocks: Vec<OcrBlock>,
+}
+
+fn mean_confidence(confidences: impl Iterator<Item = f32>) -> f32 {
+    let (sum, count) = confidences.fold((0.0, 0), |(sum, count), c| (sum + c, count + 1));
+    if count == 0 {
+        0.0
+    } else {
+        sum / count as f32
+    }
+}
+
+fn union_of(rects: impl Iterator<Item = PixelRect>) -> PixelRect {
+    rects
+        .reduce(|union, rect| union.union(&rect))
+        .unwrap_or_default()
+}
+
+impl OcrWord {
+    pub(crate) fn new(
+        text: String,
+        bbox: PixelRect,
+        confidence: f32,
+        image_size: (u32, u32),
+    ) -> Self {
+        Self {
+            text,
+            bbox,
+            normalized_bbox: bbox.normalized(image_size.0, image_size.1),
+            confidence,
+            alternatives: Vec::new(),
+        }
+    }
+}
+
+impl OcrLine {
+    /// A line made of words, with its text, box and confidence derived from them
+    pub(crate) fn from_words(words: Vec<OcrWord>, image_size: (u32, u32)) -> Self {
+        let bbox = union_of(words.iter().map(|word| word.bbox));
+        Self {
+            text: words
+                .iter()
+                .map(|word| word.text.as_str())
+                .collect::<Vec<_>>()
+                .join(" "),
+            bbox,
+            normalized_bbox: bbox.normalized(image_size.0, image_size.1),
+            confidence: mean_confidence(words.iter().map(|word| word.confidence)),
+            alternatives: Vec::new(),
+            words,
+        }
+    }
+}
+
+impl OcrBlock {
+    pub(crate) fn from_lines(lines: Vec<OcrLine>, image_size: (u32, u32)) -> Self {
+        let bbox = union_of(lines.iter().map(|line| line.bbox));
+        Self {
+            bbox,
+            normalized_bbox: bbox.normalized(image_size.0, image_size.1),
+            confidence: mean_confidence(lines.iter().map(|line| line.confidence)),
+            lines,
+        }
+    }
+}
+
+impl OcrResult {
+    pub fn lines(&self) -> impl Iterator<Item = &OcrLine> {
+        self.blocks.iter().flat_map(|block| block.lines.iter())
+    }
+
+    pub fn words(&self) -> impl Iterator<Item = &OcrWord> {
+        self.lines().flat_map(|line| line.words.iter())
+    }
+
+    /// The recognized text, one line of text per line
+    pub fn text(&self) -> String {
+        self.lines()
+            .map(|line| line.text.as_str())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Group lines in reading order into blocks
+///
+/// A line joins the previous block if it starts within one line height
+/// below it and overlaps it horizontally.
+#[cfg(any(target_os = "macos", test))]
+fn group_into_blocks(lines: Vec<OcrLine>, image_size: (u32, u32)) -> Vec<OcrBlock> {
+    let mut groups: Vec<Vec<OcrLine>> = Vec::new();
+    for line in lines {
+        let joins_previous = groups.last().is_some_and(|group| {
+            let bbox = union_of(group.iter().map(|line| line.bbox));
+            let last = &group[group.len() - 1].bbox;
+            let gap = line.bbox.y as i64 - last.bottom() as i64;
+            let max_gap = last.height.max(line.bbox.height) as i64;
+            let overlaps = line.bbox.x < bbox.right() && bbox.x < line.bbox.right();
+            gap >= -(max_gap / 2) && gap <= max_gap && overlaps
+        });
+        match groups.last_mut() {
+            Some(group) if joins_previous => group.push(line),
+            _ => groups.push(vec![line]),
+        }
+    }
+
+    groups
+        .into_iter()
+        .map(|group| OcrBlock::from_lines(group, image_size))
+        .collect()
+}
+
+/// A text recognition backend
+pub trait OcrEngine: Send + Sync {
+    /// Short name, as reported in diagnostics
+    fn name(&self) -> &'static str;
+
+    /// Recognize the text in an image file
+    fn recognize(&self, image_path: &Path, options: &OcrOptions) -> AppResult<OcrResult>;
+}
+
+fn ensure_exists(image_path: &Path) -> AppResult<()> {
+    if image_path.exists() {
+        Ok(())
+    } else {
+        Err(format!(
+            "Image file does not exist: {}",
+            image_path.display()
+        ))
+    }
+}
+
+/// Words of a line with their ranges in UTF-16 code units, as `NSString`
+/// ranges are counted
+#[cfg(any(target_os = "macos", test))]
+fn utf16_word_ranges(text: &str) -> Vec<(&str, std::ops::Range<usize>)> {
+    let mut ranges = Vec::new();
+    let mut offset = 0;
+    let mut start: Option<(usize, usize)> = None;
+
+    for (index, ch) in text.char_indices() {
+        match (ch.is_whitespace(), start) {
+            (true, Some((byte_start, utf16_start))) => {
+                ranges.push((&text[byte_start..index], utf16_start..offset));
+                start = None;
+            }
+            (false, None) => start = Some((index, offset)),
+            _ => {}
+        }
+        offset += ch.len_utf16();
+    }
+    if let Some((byte_start, utf16_start)) = start {
+        ranges.push((&text[byte_start..], utf16_start..offset));
+    }
+    ranges
+}
+
+/// Give each word of a line's top reading the other readings of it
+///
+/// Vision only scores whole lines, so words start with their line's
+/// confidence. Another reading of a word is the word in that reading that
+/// overlaps it most horizontally. A word's confidence is scaled by the
+/// share of the readings' confidence that agrees with it, so words the
+/// readings disagree on score lower than the line.
+#[cfg(any(target_os = "macos", test))]
+fn attach_word_alternatives(words: &mut [OcrWord], readings: &[(f32, Vec<OcrWord>)]) {
+    let overlap =
+        |a: &PixelRect, b: &PixelRect| a.right().min(b.right()).saturating_sub(a.x.max(b.x));
+
+    for word in words.iter_mut() {
+        let mut agreeing = word.confidence;
+        let mut total = word.confidence;
+        for (confidence, reading) in readings {
+            let Some(other) = reading
+                .iter()
+                .filter(|other| overlap(&word.bbox, &other.bbox) > 0)
+                .max_by_key(|other| overlap(&word.bbox, &other.bbox))
+            else {
+                continue;
+            };
+
+            total += confidence;
+            if other.text == word.text {
+                agreeing += confidence;
+            } else if !word.alternatives.iter().any(|alt| alt.text == other.text) {
+                word.alternatives.push(OcrAlternative {
+                    text: other.text.clone(),
+                    confidence: *confidence,
+                });
+            }
+        }
+        if total > 0.0 {
+            word.confidence *= agreeing / total;
+        }
+    }
+}
+
+/// Candidate readings requested per Vision observation
 #[cfg(target_os = "macos")]
-pub fn recognize_text_from_image(image_path: &str) -> AppResult<String> {
-    use objc2::rc::autoreleasepool;
-    use objc2::runtime::AnyObject;
-    use objc2::AnyThread;
-    use objc2_foundation::{NSArray, NSDictionary, NSString, NSURL};
-    use objc2_vision::{
-        VNImageRequestHandler, VNRecognizeTextRequest, VNRecognizedTextObservation,
-        VNRecognizedText, VNRequest, VNRequestTextRecognitionLevel,
-    };
-    use std::path::Path;
+const VISION_CANDIDATES: usize = 3;
 
-    let path = Path::new(image_path);
-    if !path.exists() {
-        return Err(format!("Image file does not exist: {}", image_path));
+/// OCR with the macOS Vision framework
+#[cfg(target_os = "macos")]
+pub struct VisionEngine;
+
+#[cfg(target_os = "macos")]
+impl OcrEngine for VisionEngine {
+    fn name(&self) -> &'static str {
+        "vision"
     }
 
-    autoreleasepool(|_| {
-        unsafe {
-            let ns_string = NSString::from_str(image_path);
+    fn recognize(&self, image_path: &Path, options: &OcrOptions) -> AppResult<OcrResult> {
+        use objc2::rc::{autoreleasepool, Retained};
+        use objc2::runtime::{AnyObject, NSObjectProtocol};
+        use objc2::{sel, AnyThread};
+        use objc2_core_foundation::CGRect;
+        use objc2_foundation::{NSArray, NSDictionary, NSRange, NSString, NSURL};
+        use objc2_vision::{
+            VNImageRequestHandler, VNRecognizeTextRequest, VNRecognizedText,
+            VNRecognizedTextObservation, VNRequest, VNRequestTextRecognitionLevel,
+        };
+
+        ensure_exists(image_path)?;
+        let image_size = image::image_dimensions(image_path)
+            .map_err(|e| format!("Failed to read image size: {}", e))?;
+
+        // Vision boxes are normalized with the origin at the bottom left
+        let to_pixels = |rect: CGRect| {
+            NormalizedRect {
+                x: rect.origin.x,
+                y: 1.0 - rect.origin.y - rect.size.height,
+                width: rect.size.width,
+                height: rect.size.height,
+            }
+            .to_pixels(image_size.0, image_size.1)
+        };
+
+        let lines = autoreleasepool(|_| unsafe {
+            let ns_string = NSString::from_str(&image_path.to_string_lossy());
             let ns_url = NSURL::fileURLWithPath_isDirectory(&ns_string, false);
-            let options = NSDictionary::<NSString, AnyObject>::new();
+            let handler_options = NSDictionary::<NSString, AnyObject>::new();
 
             let handler = VNImageRequestHandler::initWithURL_options(
                 VNImageRequestHandler::alloc(),
                 &ns_url,
-                &*options,
+                &*handler_options,
             );
 
-            let text_request = VNRecognizeTextRequest::init(
-                VNRecognizeTextRequest::alloc(),
-            );
+            let text_request = VNRecognizeTextRequest::init(VNRecognizeTextRequest::alloc());
 
-            text_request.setRecognitionLevel(VNRequestTextRecognitionLevel::Accurate);
-            text_request.setUsesLanguageCorrection(true);
+            text_request.setRecognitionLevel(match options.recognition_level {
+                RecognitionLevel::Fast => VNRequestTextRecognitionLevel::Fast,
+                RecognitionLevel::Accurate => VNRequestTextRecognitionLevel::Accurate,
+            });
+            text_request.setUsesLanguageCorrection(options.language_correction);
+
+            let ns_strings = |values: Vec<&str>| {
+                let strings: Vec<Retained<NSString>> =
+                    values.into_iter().map(NSString::from_str).collect();
+                NSArray::from_retained_slice(&strings)
+            };
+            let languages: Vec<&str> = options
+                .languages
+                .iter()
+                .map(|language| language.trim())
+                .filter(|language| !language.is_empty())
+                .collect();
+            if !languages.is_empty() {
+                text_request.setRecognitionLanguages(&ns_strings(languages));
+            }
+            let custom_words: Vec<&str> = options.custom_words().collect();
+            if !custom_words.is_empty() {
+                text_request.setCustomWords(&ns_strings(custom_words));
+            }
+            if options.minimum_text_height > 0.0 {
+                text_request.setMinimumTextHeight(options.minimum_text_height.min(1.0));
+            }
+            // Language detection needs macOS 13
+            if text_request.respondsToSelector(sel!(setAutomaticallyDetectsLanguage:)) {
+                text_request.setAutomaticallyDetectsLanguage(options.automatic_language_detection);
+            }
 
             let request_ref: &VNRequest = text_request.as_ref();
             let requests = NSArray::from_slice(&[request_ref]);
-            
+
             handler
                 .performRequests_error(&requests)
                 .map_err(|e| format!("Vision request failed: {:?}", e))?;
 
-            let observations = text_request.results();
-            let mut recognized_texts = Vec::new();
-
-            if let Some(obs_array) = observations {
-                for obs in obs_array.iter() {
-                    if let Some(text_obs) = obs.downcast_ref::<VNRecognizedTextObservation>() {
-                        let candidates = text_obs.topCandidates(1);
-                        for cand in candidates.iter() {
-                            if let Some(text_cand) = cand.downcast_ref::<VNRecognizedText>() {
-                                let str_ref = text_cand.string();
-                                recognized_texts.push(str_ref.to_string());
-                            }
-                        }
+            let mut lines = Vec::new();
+            let Some(obs_array) = text_request.results() else {
+                return Ok(lines);
+            };
+
+            for obs in obs_array.iter() {
+                let Some(text_obs) = obs.downcast_ref::<VNRecognizedTextObservation>() else {
+                    continue;
+                };
+
+                let mut best: Option<(String, f32, Vec<OcrWord>)> = None;
+                let mut alternatives = Vec::new();
+                let mut readings = Vec::new();
+                for cand in text_obs.topCandidates(VISION_CANDIDATES).iter() {
+                    let Some(text_cand) = cand.downcast_ref::<VNRecognizedText>() else {
+                        continue;
+                    };
+                    let text = text_cand.string().to_string();
+                    let confidence = text_cand.confidence();
+
+                    let words: Vec<OcrWord> = utf16_word_ranges(&text)
+                        .into_iter()
+                        .filter_map(|(word, range)| {
+                            let word_box = text_cand
+                                .boundingBoxForRange_error(NSRange::new(range.start, range.len()))
+                                .ok()?;
+                            Some(OcrWord::new(
+                                word.to_string(),
+                                to_pixels(word_box.boundingBox()),
+                                confidence,
+                                image_size,
+                            ))
+                        })
+                        .collect();
+                    if best.is_some() {
+                        alternatives.push(OcrAlternative { text, confidence });
+                        readings.push((confidence, words));
+                    } else {
+                        best = Some((text, confidence, words));
                     }
                 }
+
+                if let Some((text, confidence, mut words)) = best {
+                    attach_word_alternatives(&mut words, &readings);
+                    let bbox = to_pixels(text_obs.boundingBox());
+                    lines.push(OcrLine {
+                        text,
+                        bbox,
+                        normalized_bbox: bbox.normalized(image_size.0, image_size.1),
+                        confidence,
+                        alternatives,
+                        words,
+                    });
+                }
+            }
+            Ok::<_, String>(lines)
+        })?;
+
+        Ok(OcrResult {
+            engine: self.name(),
+            width: image_size.0,
+            height: image_size.1,
+            blocks: group_into_blocks(lines, image_size),
+        })
+    }
+}
+
+/// OCR with a local `tesseract` binary
+pub struct TesseractEngine {
+    program: PathBuf,
+}
+
+impl TesseractEngine {
+    /// Find a `tesseract` binary that runs, or `None` if there is none
+    pub fn locate() -> Option<Self> {
+        let program = std::env::var_os(TESSERACT_VAR)
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from("tesseract"));
+
+        let runs = Command::new(&program)
+            .arg("--version")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok_and(|status| status.success());
+        runs.then_some(Self { program })
+    }
+
+    /// Language data installed for this binary
+    fn installed_languages(&self) -> AppResult<Vec<String>> {
+        let output = Command::new(&self.program)
+            .arg("--list-langs")
+            .stdin(Stdio::null())
+            .output()
+            .map_err(|e| format!("Failed to run tesseract: {}", e))?;
+        // The first line is a "List of available languages" header
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .skip(1)
+            .map(|line| line.trim().to_string())
+            .filter(|line| !line.is_empty())
+            .collect())
+    }
+}
+
+/// The Tesseract data name for a BCP 47 code
+///
+/// Codes are matched in full first (`zh-Hant`), then by their primary
+/// language (`de` for `de-AT`). Anything that already looks like a data name
+/// (`eng`, `chi_sim`) is passed through.
+fn tesseract_language(code: &str) -> Option<String> {
+    let code = code.trim();
+    let lower = code.to_ascii_lowercase();
+    let primary = lower.split(['-', '_']).next().unwrap_or_default();
+    let known = |key: &str| {
+        TESSERACT_LANGUAGES
+            .iter()
+            .find(|(bcp47, _)| *bcp47 == key)
+            .map(|(_, data)| data.to_string())
+    };
+
+    known(&lower).or_else(|| known(primary)).or_else(|| {
+        let data_name = primary.len() == 3 && primary.chars().all(|c| c.is_ascii_lowercase());
+        data_name.then(|| code.to_string())
+    })
+}
+
+/// The `-l` argument for Tesseract, or `None` for its default
+///
+/// Without automatic detection a language with no installed data is an
+/// error. With it, such languages are skipped, and an empty list loads
+/// every installed language so Tesseract can pick per word.
+fn tesseract_language_arg(options: &OcrOptions, installed: &[String]) -> AppResult<Option<String>> {
+    let mut languages: Vec<String> = Vec::new();
+    for code in &options.languages {
+        let Some(language) = tesseract_language(code) else {
+            if options.automatic_language_detection {
+                continue;
             }
+            return Err(format!("Tesseract does not support language '{}'", code));
+        };
+        if !languages.contains(&language) {
+            languages.push(language);
+        }
+    }
+
+    let (available, missing): (Vec<String>, Vec<String>) = languages
+        .into_iter()
+        .partition(|language| installed.contains(language));
+    if !options.automatic_language_detection {
+        if !missing.is_empty() {
+            return Err(format!(
+                "Tesseract language data is not installed for: {}",
+                missing.join(", ")
+            ));
+        }
+        return Ok((!available.is_empty()).then(|| available.join("+")));
+    }
+
+    if !available.is_empty() {
+        return Ok(Some(available.join("+")));
+    }
+    let every: Vec<&str> = installed
+        .iter()
+        .map(String::as_str)
+        .filter(|language| *language != "osd")
+        .collect();
+    Ok((!every.is_empty()).then(|| every.join("+")))
+}
 
-            if recognized_texts.is_empty() {
-                return Err("No text recognized in image".to_string());
+/// Words of one Tesseract line, keyed by (paragraph, line) number
+type TsvLine = ((u32, u32), Vec<OcrWord>);
+
+/// Build a result from Tesseract's TSV output
+///
+/// Words are grouped into lines by paragraph and line number and into
+/// blocks by block number. Tesseract reports no alternatives. Words shorter
+/// than `minimum_text_height` (a fraction of the image height) are dropped.
+fn parse_tesseract_tsv(tsv: &str, minimum_text_height: f32) -> AppResult<OcrResult> {
+    let mut image_size = None;
+    let mut words = Vec::new();
+
+    for row in tsv.lines().filter(|row| !row.starts_with("level")) {
+        let fields: Vec<&str> = row.splitn(12, '\t').collect();
+        if fields.len() < 11 {
+            continue;
+        }
+        let number = |index: usize| fields[index].trim().parse::<u32>().ok();
+        let (Some(level), Some(left), Some(top), Some(width), Some(height)) =
+            (number(0), number(6), number(7), number(8), number(9))
+        else {
+            continue;
+        };
+
+        match level {
+            1 => image_size = Some((width, height)),
+            5 => {
+                let text = fields.get(11).map_or("", |text| text.trim());
+                let confidence = fields[10].trim().parse::<f32>().unwrap_or(-1.0);
+                let (Some(block), Some(paragraph), Some(line)) = (number(2), number(3), number(4))
+                else {
+                    continue;
+                };
+                if text.is_empty() || confidence < 0.0 {
+                    continue;
+                }
+                let bbox = PixelRect {
+                    x: left,
+                    y: top,
+                    width,
+                    height,
+                };
+                words.push((block, (paragraph, line), text.to_string(), bbox, confidence));
             }
+            _ => {}
+        }
+    }
 
-            Ok(recognized_texts.join("\n"))
+    let image_size = image_size.ok_or("Tesseract output has no page")?;
+    let min_height = minimum_text_height.clamp(0.0, 1.0) * image_size.1 as f32;
+    let mut blocks: Vec<(u32, Vec<TsvLine>)> = Vec::new();
+    for (block, line, text, bbox, confidence) in words {
+        if (bbox.height as f32) < min_height {
+            continue;
         }
+        let word = OcrWord::new(text, bbox, (confidence / 100.0).min(1.0), image_size);
+        if blocks.last().map(|(number, _)| *number) != Some(block) {
+            blocks.push((block, Vec::new()));
+        }
+        let lines = &mut blocks.last_mut().expect("block pushed above").1;
+        match lines.last_mut() {
+            Some((number, words)) if *number == line => words.push(word),
+            _ => lines.push((line, vec![word])),
+        }
+    }
+
+    Ok(OcrResult {
+        engine: "tesseract",
+        width: image_size.0,
+        height: image_size.1,
+        blocks: blocks
+            .into_iter()
+            .map(|(_, lines)| {
+                let lines = lines
+                    .into_iter()
+                    .map(|(_, words)| OcrLine::from_words(words, image_size))
+                    .collect();
+                OcrBlock::from_lines(lines, image_size)
+            })
+            .collect(),
     })
 }
 
-#[cfg(not(target_os = "macos"))]
-pub fn recognize_text_from_image(_image_path: &str) -> AppResult<String> {
-    Err("OCR is only supported on macOS".to_string())
+impl OcrEngine for TesseractEngine {
+    fn name(&self) -> &'static str {
+        "tesseract"
+    }
+
+    fn recognize(&self, image_path: &Path, options: &OcrOptions) -> AppResult<OcrResult> {
+        ensure_exists(image_path)?;
+
+        let mut command = Command::new(&self.program);
+        command.arg(image_path).arg("stdout");
+
+        if !options.languages.is_empty() || options.automatic_language_detection {
+            let installed = self.installed_languages()?;
+            if let Some(languages) = tesseract_language_arg(options, &installed)? {
+                command.args(["-l", &languages]);
+            }
+        }
+        if options.recognition_level == RecognitionLevel::Fast {
+            // Skip the second pass over inverted text
+            command.args(["-c", "tessedit_do_invert=0"]);
+        }
+        if !options.language_correction {
+            command.args(["-c", "load_system_dawg=0", "-c", "load_freq_dawg=0"]);
+        }
+
+        let custom_words: Vec<&str> = options.custom_words().collect();
+        let words_file = if custom_words.is_empty() {
+            None
+        } else {
+            let path =
+                std::env::temp_dir().join(format!("bettershot-ocr-words-{}.txt", generate_id()?));
+            std::fs::write(&path, custom_words.join("\n") + "\n")
+                .map_err(|e| format!("Failed to write OCR word list: {}", e))?;
+            command.arg("--user-words").arg(&path);
+            Some(path)
+        };
+
+        let output = command
+            .arg("tsv")
+            .stdin(Stdio::null())
+            .output()
+            .map_err(|e| format!("Failed to run tesseract: {}", e));
+        if let Some(path) = words_file {
+            let _ = std::fs::remove_file(path);
+        }
+        let output = output?;
+        if !output.status.success() {
+            return Err(format!(
+                "tesseract failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+
+        parse_tesseract_tsv(
+            &String::from_utf8_lossy(&output.stdout),
+            options.minimum_text_height,
+        )
+    }
+}
+
+/// OCR engines usable on this system, preferred first
+pub fn available_engines() -> Vec<Box<dyn OcrEngine>> {
+    let mut engines: Vec<Box<dyn OcrEngine>> = Vec::new();
+    #[cfg(target_os = "macos")]
+    engines.push(Box::new(VisionEngine));
+    if let Some(tesseract) = TesseractEngine::locate() {
+        engines.push(Box::new(tesseract));
+    }
+    engines
+}
+
+/// The preferred OCR engine, Vision if available and Tesseract otherwise
+pub fn default_engine() -> AppResult<Box<dyn OcrEngine>> {
+    available_engines().into_iter().next().ok_or_else(|| {
+        "OCR needs Tesseract; install the tesseract package or set BETTERSHOT_TESSERACT".to_string()
+    })
+}
+
+/// Recognize an image with the preferred engine
+pub fn recognize_image(image_path: &Path, options: &OcrOptions) -> AppResult<OcrResult> {
+    default_engine()?.recognize(image_path, options)
+}
+
+/// Recognize an image and lay the text out in reading order
+pub fn recognize_text_from_image(
+    image_path: &str,
+    options: &OcrOptions,
+    format: OcrTextFormat,
+) -> AppResult<String> {
+    let result = recognize_image(Path::new(image_path), options)?;
+    let text = OcrLayout::from_result(&result).to_text(format);
+    if text.is_empty() {
+        return Err("No text recognized in image".to_string());
+    }
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_TSV: &str = "level\tpage_num\tblock_num\tpar_num\tline_num\tword_num\tleft\ttop\twidth\theight\tconf\ttext
+1\t1\t0\t0\t0\t0\t0\t0\t520\t130\t-1\t
+2\t1\t1\t0\t0\t0\t20\t22\t343\t86\t-1\t
+3\t1\t1\t1\t0\t0\t20\t22\t343\t86\t-1\t
+4\t1\t1\t1\t1\t0\t20\t22\t343\t36\t-1\t
+5\t1\t1\t1\t1\t1\t20\t23\t96\t26\t96.5\tBetter
+5\t1\t1\t1\t1\t2\t128\t22\t72\t27\t93.1\tShot
+4\t1\t1\t1\t2\t0\t20\t72\t317\t36\t-1\t
+5\t1\t1\t1\t2\t1\t20\t72\t69\t27\t91.0\tfrom
+5\t1\t1\t1\t2\t2\t100\t80\t10\t5\t12.0\t
+5\t1\t1\t1\t2\t3\t103\t73\t37\t26\t88.0\t42
+";
+
+    #[test]
+    fn test_parse_tesseract_tsv() {
+        let result = parse_tesseract_tsv(SAMPLE_TSV, 0.0).unwrap();
+        assert_eq!((result.width, result.height), (520, 130));
+        assert_eq!(result.blocks.len(), 1);
+        assert_eq!(result.text(), "Better Shot\nfrom 42");
+
+        let line = &result.blocks[0].lines[0];
+        assert_eq!(
+            line.bbox,
+            PixelRect {
+                x: 20,
+                y: 22,
+                width: 180,
+                height: 27,
+            }
+        );
+        assert!((line.confidence - 0.948).abs() < 0.001);
+        assert_eq!(line.words[1].text, "Shot");
+        assert!((line.words[1].normalized_bbox.x - 128.0 / 520.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_parse_tesseract_tsv_drops_small_text() {
+        let result = parse_tesseract_tsv(SAMPLE_TSV, 0.205).unwrap();
+        assert_eq!(result.text(), "Shot\nfrom");
+    }
+
+    #[test]
+    fn test_tesseract_language_codes() {
+        assert_eq!(tesseract_language("en-US").as_deref(), Some("eng"));
+        assert_eq!(tesseract_language("de").as_deref(), Some("deu"));
+        assert_eq!(tesseract_language("ja-JP").as_deref(), Some("jpn"));
+        assert_eq!(tesseract_language("zh-Hant").as_deref(), Some("chi_tra"));
+        assert_eq!(tesseract_language("chi_sim").as_deref(), Some("chi_sim"));
+        assert_eq!(tesseract_language("klingon"), None);
+    }
+
+    #[test]
+    fn test_tesseract_language_arg() {
+        let installed: Vec<String> = ["deu", "eng", "osd"].map(String::from).to_vec();
+        let options = |languages: &[&str], automatic: bool| OcrOptions {
+            languages: languages.iter().map(|code| code.to_string()).collect(),
+            automatic_language_detection: automatic,
+            ..OcrOptions::default()
+        };
+
+        assert_eq!(
+            tesseract_language_arg(&options(&["en-US", "de-DE", "en-GB"], false), &installed),
+            Ok(Some("eng+deu".to_string()))
+        );
+        assert!(
+            tesseract_language_arg(&options(&["en", "ja"], false), &installed)
+                .unwrap_err()
+                .contains("jpn")
+        );
+        assert_eq!(
+            tesseract_language_arg(&options(&["en", "ja"], true), &installed),
+            Ok(Some("eng".to_string()))
+        );
+        assert_eq!(
+            tesseract_language_arg(&options(&[], true), &installed),
+            Ok(Some("deu+eng".to_string()))
+        );
+        assert_eq!(
+            tesseract_language_arg(&options(&[], false), &installed),
+            Ok(None)
+        );
+    }
+
+    #[test]
+    fn test_normalized_rect_round_trip() {
+        let rect = PixelRect {
+            x: 52,
+            y: 13,
+            width: 104,
+            height: 26,
+        };
+        assert_eq!(rect.normalized(520, 130).to_pixels(520, 130), rect);
+    }
+
+    #[test]
+    fn test_group_into_blocks_splits_on_gaps() {
+        let line = |y: u32, x: u32| {
+            OcrLine::from_words(
+                vec![OcrWord::new(
+                    "word".to_string(),
+                    PixelRect {
+                        x,
+                        y,
+                        width: 100,
+                        height: 20,
+                    },
+                    0.9,
+                    (800, 600),
+                )],
+                (800, 600),
+            )
+        };
+
+        let blocks = group_into_blocks(
+            vec![line(10, 10), line(34, 10), line(120, 10), line(144, 400)],
+            (800, 600),
+        );
+        let sizes: Vec<usize> = blocks.iter().map(|block| block.lines.len()).collect();
+        assert_eq!(sizes, vec![2, 1, 1]);
+    }
+
+    #[test]
+    fn test_word_alternatives_reach_the_alto_export() {
+        let word = |text: &str, x: u32, confidence: f32| {
+            OcrWord::new(
+                text.to_string(),
+                PixelRect {
+                    x,
+                    y: 10,
+                    width: 60,
+                    height: 20,
+                },
+                confidence,
+                (400, 100),
+            )
+        };
+        let mut words = vec![word("Invoice", 10, 0.8), word("2024", 80, 0.8)];
+        let readings = vec![(0.2, vec![word("Invoice", 12, 0.2), word("2O24", 78, 0.2)])];
+        attach_word_alternatives(&mut words, &readings);
+
+        assert!(words[0].alternatives.is_empty());
+        assert!((words[0].confidence - 0.8).abs() < 0.001);
+        assert_eq!(
+            words[1].alternatives,
+            vec![OcrAlternative {
+                text: "2O24".to_string(),
+                confidence: 0.2,
+            }]
+        );
+        assert!((words[1].confidence - 0.64).abs() < 0.001);
+
+        let line = OcrLine::from_words(words, (400, 100));
+        let result = OcrResult {
+            engine: "vision",
+            width: 400,
+            height: 100,
+            blocks: vec![OcrBlock::from_lines(vec![line], (400, 100))],
+        };
+        assert!(crate::ocr_export::to_alto(&result, "shot.png")
+            .contains("<ALTERNATIVE>2O24</ALTERNATIVE>"));
+    }
+
+    #[test]
+    fn test_utf16_word_ranges() {
+        assert_eq!(
+            utf16_word_ranges("Größe 𝒳 ok"),
+            vec![("Größe", 0..5), ("𝒳", 6..8), ("ok", 9..11)]
+        );
+    }
+
+    #[test]
+    #[ignore = "needs tesseract"]
+    fn test_tesseract_reads_fixture() {
+        let engine = TesseractEngine::locate()
+            .expect("tesseract must be installed to run the OCR fixture test");
+        let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/ocr-sample.png");
+
+        let result = engine.recognize(&fixture, &OcrOptions::default()).unwrap();
+        assert_eq!(result.text(), "Better Shot reads text\nfrom 42 screenshots");
+        assert_eq!(result.words().count(), 7);
+        assert!(result.words().all(|word| word.bbox.right() <= 520));
+    }
 }
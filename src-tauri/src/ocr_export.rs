@@ -0,0 +1,320 @@
+//! OCR result export
+//!
+//! Writes an `OcrResult` as hOCR (XHTML with `ocr_*` classes) or ALTO 4
+//! XML, so recognized text can be opened in other OCR and archiving tools.
+//! Both use pixel coordinates; confidence is given as `x_wconf` (0-100) in
+//! hOCR and `WC` (0-1) in ALTO.
+
+use serde::Deserialize;
+use std::fmt::Write;
+
+use crate::ocr::{OcrResult, PixelRect};
+
+/// Supported export formats
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum OcrExportFormat {
+    Hocr,
+    Alto,
+}
+
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+/// hOCR `bbox x0 y0 x1 y1` property
+fn hocr_bbox(rect: &PixelRect) -> String {
+    format!(
+        "bbox {} {} {} {}",
+        rect.x,
+        rect.y,
+        rect.right(),
+        rect.bottom()
+    )
+}
+
+fn hocr_confidence(confidence: f32) -> u32 {
+    (confidence.clamp(0.0, 1.0) * 100.0).round() as u32
+}
+
+/// Export as hOCR; `image_name` is recorded as the page's source image
+pub fn to_hocr(result: &OcrResult, image_name: &str) -> String {
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str("<!DOCTYPE html PUBLIC \"-//W3C//DTD XHTML 1.0 Transitional//EN\" \"http://www.w3.org/TR/xhtml1/DTD/xhtml1-transitional.dtd\">\n");
+    out.push_str("<html xmlns=\"http://www.w3.org/1999/xhtml\">\n <head>\n  <title></title>\n");
+    out.push_str("  <meta http-equiv=\"Content-Type\" content=\"text/html;charset=utf-8\"/>\n");
+    let _ = writeln!(
+        out,
+        "  <meta name=\"ocr-system\" content=\"Better Shot ({})\"/>",
+        result.engine
+    );
+    out.push_str("  <meta name=\"ocr-capabilities\" content=\"ocr_page ocr_carea ocr_par ocr_line ocrx_word\"/>\n");
+    out.push_str(" </head>\n <body>\n");
+
+    let page = PixelRect {
+        x: 0,
+        y: 0,
+        width: result.width,
+        height: result.height,
+    };
+    let _ = writeln!(
+        out,
+        "  <div class=\"ocr_page\" id=\"page_1\" title=\"image &quot;{}&quot;; {}\">",
+        escape_xml(image_name),
+        hocr_bbox(&page)
+    );
+
+    let (mut line_id, mut word_id) = (0, 0);
+    for (block_index, block) in result.blocks.iter().enumerate() {
+        let bbox = hocr_bbox(&block.bbox);
+        let id = block_index + 1;
+        let _ = writeln!(
+            out,
+            "   <div class=\"ocr_carea\" id=\"block_{}\" title=\"{}\">",
+            id, bbox
+        );
+        let _ = writeln!(
+            out,
+            "    <p class=\"ocr_par\" id=\"par_{}\" title=\"{}\">",
+            id, bbox
+        );
+        for line in &block.lines {
+            line_id += 1;
+            let _ = writeln!(
+                out,
+                "     <span class=\"ocr_line\" id=\"line_{}\" title=\"{}; x_wconf {}\">",
+                line_id,
+                hocr_bbox(&line.bbox),
+                hocr_confidence(line.confidence)
+            );
+            for word in &line.words {
+                word_id += 1;
+                let _ = writeln!(
+                    out,
+                    "      <span class=\"ocrx_word\" id=\"word_{}\" title=\"{}; x_wconf {}\">{}</span>",
+                    word_id,
+                    hocr_bbox(&word.bbox),
+                    hocr_confidence(word.confidence),
+                    escape_xml(&word.text)
+                );
+            }
+            out.push_str("     </span>\n");
+        }
+        out.push_str("    </p>\n   </div>\n");
+    }
+
+    out.push_str("  </div>\n </body>\n</html>\n");
+    out
+}
+
+/// ALTO position and size attributes
+fn alto_position(rect: &PixelRect) -> String {
+    format!(
+        "HPOS=\"{}\" VPOS=\"{}\" WIDTH=\"{}\" HEIGHT=\"{}\"",
+        rect.x, rect.y, rect.width, rect.height
+    )
+}
+
+/// Export as ALTO 4 XML; `image_name` is recorded as the source image
+pub fn to_alto(result: &OcrResult, image_name: &str) -> String {
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str("<alto xmlns=\"http://www.loc.gov/standards/alto/ns-v4#\" xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\" xsi:schemaLocation=\"http://www.loc.gov/standards/alto/ns-v4# http://www.loc.gov/alto/v4/alto-4-2.xsd\">\n");
+    out.push_str("  <Description>\n    <MeasurementUnit>pixel</MeasurementUnit>\n");
+    let _ = writeln!(
+        out,
+        "    <sourceImageInformation>\n      <fileName>{}</fileName>\n    </sourceImageInformation>",
+        escape_xml(image_name)
+    );
+    let _ = writeln!(
+        out,
+        "    <OCRProcessing ID=\"OCR_0\">\n      <ocrProcessingStep>\n        <processingSoftware>\n          <softwareName>Better Shot ({})</softwareName>\n        </processingSoftware>\n      </ocrProcessingStep>\n    </OCRProcessing>",
+        result.engine
+    );
+    out.push_str("  </Description>\n  <Layout>\n");
+
+    let page = PixelRect {
+        x: 0,
+        y: 0,
+        width: result.width,
+        height: result.height,
+    };
+    let _ = writeln!(
+        out,
+        "    <Page ID=\"page_1\" PHYSICAL_IMG_NR=\"1\" WIDTH=\"{}\" HEIGHT=\"{}\">",
+        result.width, result.height
+    );
+    let _ = writeln!(out, "      <PrintSpace {}>", alto_position(&page));
+
+    let (mut line_id, mut word_id) = (0, 0);
+    for (block_index, block) in result.blocks.iter().enumerate() {
+        let _ = writeln!(
+            out,
+            "        <TextBlock ID=\"block_{}\" {}>",
+            block_index + 1,
+            alto_position(&block.bbox)
+        );
+        for line in &block.lines {
+            line_id += 1;
+            let _ = writeln!(
+                out,
+                "          <TextLine ID=\"line_{}\" {}>",
+                line_id,
+                alto_position(&line.bbox)
+            );
+            for (index, word) in line.words.iter().enumerate() {
+                if let Some(previous) = index.checked_sub(1).map(|i| &line.words[i]) {
+                    let gap = word.bbox.x.saturating_sub(previous.bbox.right());
+                    let _ = writeln!(
+                        out,
+                        "            <SP WIDTH=\"{}\" HPOS=\"{}\" VPOS=\"{}\"/>",
+                        gap,
+                        previous.bbox.right(),
+                        line.bbox.y
+                    );
+                }
+                word_id += 1;
+                let _ = write!(
+                    out,
+                    "            <String ID=\"string_{}\" {} CONTENT=\"{}\" WC=\"{:.2}\"",
+                    word_id,
+                    alto_position(&word.bbox),
+                    escape_xml(&word.text),
+                    word.confidence.clamp(0.0, 1.0)
+                );
+                if word.alternatives.is_empty() {
+                    out.push_str("/>\n");
+                    continue;
+                }
+                out.push_str(">\n");
+                for alternative in &word.alternatives {
+                    let _ = writeln!(
+                        out,
+                        "              <ALTERNATIVE>{}</ALTERNATIVE>",
+                        escape_xml(&alternative.text)
+                    );
+                }
+                out.push_str("            </String>\n");
+            }
+            out.push_str("          </TextLine>\n");
+        }
+        out.push_str("        </TextBlock>\n");
+    }
+
+    out.push_str("      </PrintSpace>\n    </Page>\n  </Layout>\n</alto>\n");
+    out
+}
+
+/// Export in the given format
+pub fn export_ocr_result(result: &OcrResult, image_name: &str, format: OcrExportFormat) -> String {
+    match format {
+        OcrExportFormat::Hocr => to_hocr(result, image_name),
+        OcrExportFormat::Alto => to_alto(result, image_name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ocr::{NormalizedRect, OcrAlternative, OcrBlock, OcrLine, OcrWord};
+
+    fn rect(x: u32, y: u32, width: u32, height: u32) -> PixelRect {
+        PixelRect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    fn word(text: &str, bbox: PixelRect, alternatives: Vec<OcrAlternative>) -> OcrWord {
+        OcrWord {
+            text: text.to_string(),
+            bbox,
+            normalized_bbox: NormalizedRect::default(),
+            confidence: 0.915,
+            alternatives,
+        }
+    }
+
+    fn sample() -> OcrResult {
+        let words = vec![
+            word("R&D", rect(10, 20, 40, 12), Vec::new()),
+            word(
+                "<ok>",
+                rect(60, 21, 30, 11),
+                vec![OcrAlternative {
+                    text: "cok".to_string(),
+                    confidence: 0.2,
+                }],
+            ),
+        ];
+        let line = OcrLine {
+            text: "R&D <ok>".to_string(),
+            bbox: rect(10, 20, 80, 13),
+            normalized_bbox: NormalizedRect::default(),
+            confidence: 0.9,
+            alternatives: Vec::new(),
+            words,
+        };
+        OcrResult {
+            engine: "tesseract",
+            width: 200,
+            height: 100,
+            blocks: vec![OcrBlock {
+                bbox: line.bbox,
+                normalized_bbox: NormalizedRect::default(),
+                confidence: 0.9,
+                lines: vec![line],
+            }],
+        }
+    }
+
+    #[test]
+    fn test_hocr_has_boxes_and_escaped_words() {
+        let hocr = to_hocr(&sample(), "shot \"1\".png");
+
+        assert!(
+            hocr.contains("title=\"image &quot;shot &quot;1&quot;.png&quot;; bbox 0 0 200 100\"")
+        );
+        assert!(hocr
+            .contains("class=\"ocr_line\" id=\"line_1\" title=\"bbox 10 20 90 33; x_wconf 90\""));
+        assert!(
+            hocr.contains("id=\"word_1\" title=\"bbox 10 20 50 32; x_wconf 92\">R&amp;D</span>")
+        );
+        assert!(hocr.contains(">&lt;ok&gt;</span>"));
+        assert!(hocr.trim_end().ends_with("</html>"));
+    }
+
+    #[test]
+    fn test_alto_has_strings_spaces_and_alternatives() {
+        let alto = to_alto(&sample(), "shot.png");
+
+        assert!(alto.contains("<fileName>shot.png</fileName>"));
+        assert!(alto
+            .contains("<Page ID=\"page_1\" PHYSICAL_IMG_NR=\"1\" WIDTH=\"200\" HEIGHT=\"100\">"));
+        assert!(alto.contains(
+            "<String ID=\"string_1\" HPOS=\"10\" VPOS=\"20\" WIDTH=\"40\" HEIGHT=\"12\" CONTENT=\"R&amp;D\" WC=\"0.92\"/>"
+        ));
+        assert!(alto.contains("<SP WIDTH=\"10\" HPOS=\"50\" VPOS=\"20\"/>"));
+        assert!(alto.contains(
+            "CONTENT=\"&lt;ok&gt;\" WC=\"0.92\">\n              <ALTERNATIVE>cok</ALTERNATIVE>"
+        ));
+        assert_eq!(
+            alto.matches("<TextLine ").count(),
+            alto.matches("</TextLine>").count()
+        );
+    }
+}
@@ -0,0 +1,544 @@
+//! Layout reconstruction for OCR results
+//!
+//! Engines report lines in whatever order they found them, so two-column
+//! pages interleave and code loses its indentation. This rebuilds reading
+//! order from the line and word boxes:
+//!
+//! - Lines are grouped into bands that share a vertical range. Consecutive
+//!   bands with a common gap wider than a gutter are split into columns,
+//!   read left to right, and each column is laid out the same way again.
+//! - Within a column, rows separated by a larger gap than usual start a new
+//!   paragraph.
+//! - Paragraphs in a monospace font are treated as code: leading and inner
+//!   whitespace is rebuilt from the x-offsets, and they can be wrapped in a
+//!   Markdown fence.
+
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+
+use crate::ocr::{OcrLine, OcrResult, PixelRect};
+use crate::utils::{value_or, AppResult, SETTINGS_STORE};
+
+const TEXT_FORMAT_KEY: &str = "ocrTextFormat";
+
+/// Narrowest gap between columns, in line heights
+const GUTTER_LINE_HEIGHTS: f64 = 1.5;
+
+/// Vertical gap between rows that starts a new paragraph, in line heights
+const PARAGRAPH_GAP_LINE_HEIGHTS: f64 = 0.8;
+
+/// Words needed to decide whether a paragraph is monospace
+const MIN_WIDTH_SAMPLES: usize = 3;
+
+/// How far a word's character width may be from the median in monospace
+const MONOSPACE_TOLERANCE: f64 = 0.15;
+
+/// Share of words that must be within the tolerance in monospace
+const MONOSPACE_SHARE: f64 = 0.85;
+
+/// How recognized text is written out
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum OcrTextFormat {
+    #[default]
+    Plain,
+    /// Code blocks are wrapped in a Markdown fence
+    Markdown,
+}
+
+impl OcrTextFormat {
+    /// Load the format from `settings.json`, plain text if unset
+    pub fn load(app_handle: &AppHandle) -> AppResult<Self> {
+        let store = app_handle
+            .store(SETTINGS_STORE)
+            .map_err(|e| format!("Failed to open settings: {}", e))?;
+        Ok(value_or(store.get(TEXT_FORMAT_KEY), Self::default()))
+    }
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LayoutBlockKind {
+    Paragraph,
+    Code,
+}
+
+/// A paragraph or code block; code lines keep their indentation, and blank
+/// lines inside code are kept as empty strings
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LayoutBlock {
+    pub kind: LayoutBlockKind,
+    pub bbox: PixelRect,
+    pub lines: Vec<String>,
+}
+
+/// Text read top to bottom, such as one column of a page
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LayoutColumn {
+    pub bbox: PixelRect,
+    pub blocks: Vec<LayoutBlock>,
+}
+
+/// An OCR result in reading order
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct OcrLayout {
+    pub columns: Vec<LayoutColumn>,
+}
+
+/// A word with its box, or a whole line when the engine gave no word boxes
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Fragment<'a> {
+    pub text: &'a str,
+    pub bbox: PixelRect,
+}
+
+pub(crate) fn fragments(line: &OcrLine) -> Vec<Fragment<'_>> {
+    if line.words.is_empty() {
+        return vec![Fragment {
+            text: &line.text,
+            bbox: line.bbox,
+        }];
+    }
+    line.words
+        .iter()
+        .map(|word| Fragment {
+            text: &word.text,
+            bbox: word.bbox,
+        })
+        .collect()
+}
+
+/// Fragments side by side on one visual line, left to right
+struct Row<'a> {
+    fragments: Vec<Fragment<'a>>,
+    bbox: PixelRect,
+}
+
+fn median(mut values: Vec<f64>) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(f64::total_cmp);
+    Some(values[values.len() / 2])
+}
+
+/// Split boxes into bands that share a vertical range, top to bottom
+///
+/// A box joins the band above when it overlaps it by at least half its
+/// height, so neighbouring lines whose boxes just touch stay apart.
+pub(crate) fn bands<T>(mut items: Vec<T>, bbox: impl Fn(&T) -> PixelRect) -> Vec<Vec<T>> {
+    items.sort_by_key(|item| {
+        let rect = bbox(item);
+        (rect.y, rect.x)
+    });
+
+    let mut bands: Vec<(u32, Vec<T>)> = Vec::new();
+    for item in items {
+        let rect = bbox(&item);
+        match bands.last_mut() {
+            Some((bottom, band))
+                if bottom.saturating_sub(rect.y).min(rect.height) * 2 >= rect.height.max(1) =>
+            {
+                *bottom = (*bottom).max(rect.bottom());
+                band.push(item);
+            }
+            _ => bands.push((rect.bottom(), vec![item])),
+        }
+    }
+    bands.into_iter().map(|(_, band)| band).collect()
+}
+
+/// Gaps of at least `gutter` pixels between the lines of a band
+fn band_gutters(band: &[&OcrLine], gutter: u32) -> Vec<(u32, u32)> {
+    let mut spans: Vec<(u32, u32)> = band
+        .iter()
+        .map(|line| (line.bbox.x, line.bbox.right()))
+        .collect();
+    spans.sort();
+
+    let mut gaps = Vec::new();
+    let mut covered_to = spans.first().map_or(0, |span| span.1);
+    for (start, end) in spans.into_iter().skip(1) {
+        if start >= covered_to + gutter {
+            gaps.push((covered_to, start));
+        }
+        covered_to = covered_to.max(end);
+    }
+    gaps
+}
+
+/// The widest part of `gap` that no line in the band covers
+fn narrow_gutter(gap: (u32, u32), band: &[&OcrLine]) -> (u32, u32) {
+    let mut free = vec![gap];
+    for line in band {
+        let (left, right) = (line.bbox.x, line.bbox.right());
+        free = free
+            .into_iter()
+            .flat_map(|(start, end)| {
+                if right <= start || left >= end {
+                    vec![(start, end)]
+                } else {
+                    vec![(start, left.max(start)), (right.min(end), end)]
+                }
+            })
+            .filter(|(start, end)| end > start)
+            .collect();
+    }
+    free.into_iter()
+        .max_by_key(|(start, end)| end - start)
+        .unwrap_or((gap.0, gap.0))
+}
+
+/// Consecutive bands and the gutters still free in all of them
+type BandGroup<'a> = (Vec<(u32, u32)>, Vec<&'a OcrLine>);
+
+/// Lines in reading order, as runs that are each read top to bottom
+///
+/// Bands are gathered into groups while they keep a gutter in common; a
+/// group with gutters is split into columns, which are laid out again.
+fn reading_order(lines: Vec<&OcrLine>, gutter: u32) -> Vec<Vec<&OcrLine>> {
+    let mut groups: Vec<BandGroup> = Vec::new();
+    for band in bands(lines, |line| line.bbox) {
+        let own = band_gutters(&band, gutter);
+        if let Some((gutters, members)) = groups.last_mut() {
+            let kept: Vec<(u32, u32)> = gutters
+                .iter()
+                .map(|gap| narrow_gutter(*gap, &band))
+                .filter(|(start, end)| end - start >= gutter)
+                .collect();
+            let joins = if gutters.is_empty() {
+                own.is_empty()
+            } else {
+                !kept.is_empty()
+            };
+            if joins {
+                *gutters = kept;
+                members.extend(band);
+                continue;
+            }
+        }
+        groups.push((own, band));
+    }
+
+    let mut runs = Vec::new();
+    for (gutters, members) in groups {
+        if gutters.is_empty() {
+            runs.push(members);
+            continue;
+        }
+        let mut columns: Vec<Vec<&OcrLine>> = vec![Vec::new(); gutters.len() + 1];
+        for line in members {
+            let center = line.bbox.x + line.bbox.width / 2;
+            let index = gutters.iter().filter(|(start, _)| center >= *start).count();
+            columns[index].push(line);
+        }
+        for column in columns.into_iter().filter(|column| !column.is_empty()) {
+            runs.extend(reading_order(column, gutter));
+        }
+    }
+    runs
+}
+
+fn rows<'a>(lines: Vec<&'a OcrLine>) -> Vec<Row<'a>> {
+    bands(lines, |line| line.bbox)
+        .into_iter()
+        .map(|band| {
+            let mut fragments: Vec<Fragment> =
+                band.iter().flat_map(|line| fragments(line)).collect();
+            fragments.sort_by_key(|fragment| fragment.bbox.x);
+            let bbox = fragments
+                .iter()
+                .map(|fragment| fragment.bbox)
+                .reduce(|union, rect| union.union(&rect))
+                .unwrap_or_default();
+            Row { fragments, bbox }
+        })
+        .collect()
+}
+
+/// Split a column's rows where the gap between them is wider than usual
+fn paragraphs(rows: Vec<Row<'_>>) -> Vec<Vec<Row<'_>>> {
+    let line_height =
+        median(rows.iter().map(|row| row.bbox.height as f64).collect()).unwrap_or_default();
+    let break_gap = line_height * PARAGRAPH_GAP_LINE_HEIGHTS;
+
+    let mut paragraphs: Vec<Vec<Row>> = Vec::new();
+    for row in rows {
+        match paragraphs.last_mut() {
+            Some(paragraph)
+                if (row.bbox.y as f64)
+                    - (paragraph.last().map_or(0, |last| last.bbox.bottom()) as f64)
+                    <= break_gap =>
+            {
+                paragraph.push(row)
+            }
+            _ => paragraphs.push(vec![row]),
+        }
+    }
+    paragraphs
+}
+
+/// The character width of a monospace paragraph, or `None` if its words
+/// have proportional widths
+fn monospace_char_width(rows: &[Row<'_>]) -> Option<f64> {
+    let widths: Vec<f64> = rows
+        .iter()
+        .flat_map(|row| &row.fragments)
+        .filter_map(|fragment| {
+            let chars = fragment.text.chars().count();
+            (chars >= 3).then(|| fragment.bbox.width as f64 / chars as f64)
+        })
+        .collect();
+    if widths.len() < MIN_WIDTH_SAMPLES {
+        return None;
+    }
+
+    let char_width = median(widths.clone())?;
+    let close = widths
+        .iter()
+        .filter(|width| ((*width / char_width) - 1.0).abs() <= MONOSPACE_TOLERANCE)
+        .count();
+    (char_width > 0.0 && close as f64 >= widths.len() as f64 * MONOSPACE_SHARE)
+        .then_some(char_width)
+}
+
+/// Rows with their whitespace rebuilt on a grid of `char_width` columns
+/// starting at `left`
+///
+/// Gaps between ink boxes include the glyphs' side bearings, so inner gaps
+/// round down unless they are nearly a whole extra character.
+fn code_lines(rows: &[Row<'_>], left: u32, char_width: f64) -> Vec<String> {
+    rows.iter()
+        .map(|row| {
+            let mut line = String::new();
+            let mut end = left;
+            for (index, fragment) in row.fragments.iter().enumerate() {
+                let gap = fragment.bbox.x.saturating_sub(end) as f64 / char_width;
+                let spaces = if index == 0 {
+                    gap.round() as usize
+                } else {
+                    ((gap + 0.2).floor() as usize).max(1)
+                };
+                line.push_str(&" ".repeat(spaces));
+                line.push_str(fragment.text);
+                end = fragment.bbox.right();
+            }
+            line
+        })
+        .collect()
+}
+
+fn rows_bbox(rows: &[Row<'_>]) -> PixelRect {
+    rows.iter()
+        .map(|row| row.bbox)
+        .reduce(|union, rect| union.union(&rect))
+        .unwrap_or_default()
+}
+
+/// Lay out one column, merging neighbouring code paragraphs into one block
+fn column_blocks(rows: Vec<Row<'_>>) -> Vec<LayoutBlock> {
+    let left = rows.iter().map(|row| row.bbox.x).min().unwrap_or_default();
+    let mut blocks: Vec<LayoutBlock> = Vec::new();
+
+    for paragraph in paragraphs(rows) {
+        let bbox = rows_bbox(&paragraph);
+        let Some(char_width) = monospace_char_width(&paragraph) else {
+            blocks.push(LayoutBlock {
+                kind: LayoutBlockKind::Paragraph,
+                bbox,
+                lines: paragraph
+                    .iter()
+                    .map(|row| {
+                        row.fragments
+                            .iter()
+                            .map(|fragment| fragment.text)
+                            .collect::<Vec<_>>()
+                            .join(" ")
+                    })
+                    .collect(),
+            });
+            continue;
+        };
+
+        let lines = code_lines(&paragraph, left, char_width);
+        match blocks.last_mut() {
+            Some(block) if block.kind == LayoutBlockKind::Code => {
+                block.bbox = block.bbox.union(&bbox);
+                block.lines.push(String::new());
+                block.lines.extend(lines);
+            }
+            _ => blocks.push(LayoutBlock {
+                kind: LayoutBlockKind::Code,
+                bbox,
+                lines,
+            }),
+        }
+    }
+    blocks
+}
+
+impl OcrLayout {
+    /// Rebuild the reading order and structure of a result
+    pub fn from_result(result: &OcrResult) -> Self {
+        let lines: Vec<&OcrLine> = result.lines().collect();
+        let line_height =
+            median(lines.iter().map(|line| line.bbox.height as f64).collect()).unwrap_or_default();
+        let gutter = ((line_height * GUTTER_LINE_HEIGHTS).round() as u32).max(1);
+
+        let columns = reading_order(lines, gutter)
+            .into_iter()
+            .map(|run| {
+                let rows = rows(run);
+                LayoutColumn {
+                    bbox: rows_bbox(&rows),
+                    blocks: column_blocks(rows),
+                }
+            })
+            .collect();
+        Self { columns }
+    }
+
+    /// The text, with blocks and columns separated by blank lines
+    pub fn to_text(&self, format: OcrTextFormat) -> String {
+        self.columns
+            .iter()
+            .flat_map(|column| &column.blocks)
+            .map(|block| {
+                let text = block.lines.join("\n");
+                match (block.kind, format) {
+                    (LayoutBlockKind::Code, OcrTextFormat::Markdown) => {
+                        format!("```\n{}\n```", text)
+                    }
+                    _ => text,
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ocr::{OcrBlock, OcrWord};
+
+    const IMAGE: (u32, u32) = (1000, 1000);
+
+    /// A line of words starting at `x`, each as wide as `char_width` says
+    /// and 10px apart
+    fn line(x: u32, y: u32, text: &str, char_width: impl Fn(&str) -> u32) -> OcrLine {
+        let mut left = x;
+        let words = text
+            .split(' ')
+            .filter(|word| !word.is_empty())
+            .map(|word| {
+                let width = char_width(word);
+                let bbox = PixelRect {
+                    x: left,
+                    y,
+                    width,
+                    height: 20,
+                };
+                left += width + 10;
+                OcrWord::new(word.to_string(), bbox, 0.9, IMAGE)
+            })
+            .collect();
+        OcrLine::from_words(words, IMAGE)
+    }
+
+    /// Proportional widths, so words never look monospace
+    fn prose(x: u32, y: u32, text: &str) -> OcrLine {
+        line(x, y, text, |word| {
+            word.chars()
+                .map(|c| match c {
+                    'i' | 'l' | 't' | 'f' | 'r' | 'j' => 4,
+                    'm' | 'w' => 18,
+                    _ => 12,
+                })
+                .sum()
+        })
+    }
+
+    fn result(lines: Vec<OcrLine>) -> OcrResult {
+        OcrResult {
+            engine: "test",
+            width: IMAGE.0,
+            height: IMAGE.1,
+            blocks: vec![OcrBlock::from_lines(lines, IMAGE)],
+        }
+    }
+
+    #[test]
+    fn test_columns_read_in_order() {
+        // Rows of both columns line up, and engines report them interleaved
+        let layout = OcrLayout::from_result(&result(vec![
+            prose(20, 10, "A heading across the whole page that is long"),
+            prose(20, 50, "left one"),
+            prose(500, 51, "right one"),
+            prose(20, 75, "left two"),
+            prose(500, 74, "right two"),
+            prose(20, 100, "left three"),
+        ]));
+
+        assert_eq!(
+            layout.to_text(OcrTextFormat::Plain),
+            "A heading across the whole page that is long\n\n\
+             left one\nleft two\nleft three\n\n\
+             right one\nright two"
+        );
+        assert_eq!(layout.columns.len(), 3);
+        assert_eq!(layout.columns[2].bbox.x, 500);
+    }
+
+    #[test]
+    fn test_gaps_split_paragraphs() {
+        let layout = OcrLayout::from_result(&result(vec![
+            prose(20, 10, "first paragraph"),
+            prose(20, 34, "still first"),
+            prose(20, 90, "second paragraph"),
+        ]));
+
+        let blocks = &layout.columns[0].blocks;
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0].lines, vec!["first paragraph", "still first"]);
+        assert_eq!(blocks[1].kind, LayoutBlockKind::Paragraph);
+    }
+
+    #[test]
+    fn test_code_keeps_indentation() {
+        let mono = |word: &str| word.chars().count() as u32 * 10;
+        let layout = OcrLayout::from_result(&result(vec![
+            prose(20, 10, "Example:"),
+            line(20, 50, "fn main() {", mono),
+            line(60, 74, "let answer = 42;", mono),
+            line(60, 98, "println!(\"{}\", answer);", mono),
+            line(20, 122, "}", mono),
+        ]));
+
+        assert_eq!(
+            layout.to_text(OcrTextFormat::Markdown),
+            "Example:\n\n```\nfn main() {\n    let answer = 42;\n    println!(\"{}\", answer);\n}\n```"
+        );
+        assert_eq!(layout.columns[0].blocks[1].kind, LayoutBlockKind::Code);
+    }
+
+    #[test]
+    fn test_code_paragraphs_merge_with_blank_line() {
+        let mono = |word: &str| word.chars().count() as u32 * 10;
+        let layout = OcrLayout::from_result(&result(vec![
+            line(20, 10, "let value = read(path);", mono),
+            line(20, 80, "let other = parse(value);", mono),
+        ]));
+
+        let blocks = &layout.columns[0].blocks;
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(
+            blocks[0].lines,
+            vec!["let value = read(path);", "", "let other = parse(value);"]
+        );
+    }
+}
@@ -0,0 +1,555 @@
+//! Table extraction from OCR results
+//!
+//! Rows and columns come from ruling lines in the image when the table is
+//! fully ruled, and otherwise from the text itself: words that share a
+//! vertical range form a row, and gaps that run down the whole table
+//! separate columns. Recognized words are then assigned to cells, and the
+//! table can be written as TSV (which pastes into spreadsheets), CSV or a
+//! Markdown table.
+
+use image::GrayImage;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+
+use crate::ocr::{recognize_image, OcrOptions, OcrResult, PixelRect};
+use crate::ocr_layout::{bands, fragments, Fragment};
+use crate::utils::{value_or, AppResult, SETTINGS_STORE};
+
+const TABLE_FORMAT_KEY: &str = "ocrTableFormat";
+
+/// Luma difference from the background that counts as a line or text
+const INK_CONTRAST: i16 = 24;
+
+/// Share of the image a run of ink must span to be a ruling line
+const RULE_COVERAGE: f64 = 0.6;
+
+/// Thicker runs are filled areas, such as a header or striped row
+const MAX_RULE_THICKNESS: u32 = 5;
+
+/// Narrowest gap between columns, in text heights
+const COLUMN_GAP_TEXT_HEIGHTS: f64 = 1.0;
+
+/// How tables are written out
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TableFormat {
+    #[default]
+    Tsv,
+    Csv,
+    Markdown,
+}
+
+impl TableFormat {
+    /// Load the format from `settings.json`, TSV if unset
+    pub fn load(app_handle: &AppHandle) -> AppResult<Self> {
+        let store = app_handle
+            .store(SETTINGS_STORE)
+            .map_err(|e| format!("Failed to open settings: {}", e))?;
+        Ok(value_or(store.get(TABLE_FORMAT_KEY), Self::default()))
+    }
+}
+
+/// Cell text by row; every row has the same number of cells
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct OcrTable {
+    pub rows: Vec<Vec<String>>,
+    /// Whether ruling lines in the image decided the rows or columns
+    pub ruled: bool,
+}
+
+/// Positions of the horizontal and vertical ruling lines in an image
+#[derive(Clone, Debug, Default, PartialEq)]
+struct Rules {
+    rows: Vec<u32>,
+    columns: Vec<u32>,
+}
+
+/// The most common luma, taken to be the background
+fn background_luma(image: &GrayImage) -> u8 {
+    let mut histogram = [0u32; 256];
+    for pixel in image.pixels() {
+        histogram[pixel.0[0] as usize] += 1;
+    }
+    (0..=255u8)
+        .max_by_key(|luma| histogram[*luma as usize])
+        .unwrap_or(255)
+}
+
+/// Lines among `count` rows (or columns) of `length` pixels, as the center
+/// of each thin run of lines with a long unbroken stretch of ink
+fn rule_positions(count: u32, length: u32, ink: impl Fn(u32, u32) -> bool) -> Vec<u32> {
+    let min_run = ((length as f64 * RULE_COVERAGE).ceil() as u32).max(1);
+    let is_rule = |index: u32| {
+        let mut run = 0;
+        for offset in 0..length {
+            if ink(index, offset) {
+                run += 1;
+                if run >= min_run {
+                    return true;
+                }
+            } else {
+                run = 0;
+            }
+        }
+        false
+    };
+
+    let mut positions = Vec::new();
+    let mut start = None;
+    for index in 0..=count {
+        match (index < count && is_rule(index), start) {
+            (true, None) => start = Some(index),
+            (false, Some(first)) => {
+                if index - first <= MAX_RULE_THICKNESS {
+                    positions.push((first + index - 1) / 2);
+                }
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    positions
+}
+
+fn find_rules(image: &GrayImage) -> Rules {
+    let background = background_luma(image) as i16;
+    let ink =
+        |x: u32, y: u32| (image.get_pixel(x, y).0[0] as i16 - background).abs() > INK_CONTRAST;
+    let (width, height) = image.dimensions();
+
+    Rules {
+        rows: rule_positions(height, width, |y, x| ink(x, y)),
+        columns: rule_positions(width, height, ink),
+    }
+}
+
+/// Whether text bands are separated by rules, rather than the table just
+/// having a border or a line under the header
+///
+/// `spans` are the extents of consecutive bands along the axis the rules
+/// cross. Only gaps between bands inside the outermost rules count, and at
+/// least half of them must hold a rule.
+fn fully_ruled(spans: &[(u32, u32)], rules: &[u32]) -> bool {
+    let (Some(first), Some(last)) = (rules.first(), rules.last()) else {
+        return false;
+    };
+    if rules.len() < 2 {
+        return false;
+    }
+
+    let gaps: Vec<(u32, u32)> = spans
+        .windows(2)
+        .map(|pair| (pair[0].1, pair[1].0))
+        .filter(|(above, below)| above >= first && below <= last)
+        .collect();
+    let separated = gaps
+        .iter()
+        .filter(|(above, below)| rules.iter().any(|rule| rule >= above && rule <= below))
+        .count();
+    !gaps.is_empty() && separated * 2 >= gaps.len()
+}
+
+/// Column boundaries from gaps that run down the table
+///
+/// A gap may be crossed by one row in ten, so a title or a long cell that
+/// spills over doesn't merge two columns.
+fn aligned_columns(rows: &[Vec<Fragment<'_>>], min_gap: u32) -> Vec<u32> {
+    let Some(right) = rows
+        .iter()
+        .flatten()
+        .map(|fragment| fragment.bbox.right())
+        .max()
+    else {
+        return Vec::new();
+    };
+    let left = rows
+        .iter()
+        .flatten()
+        .map(|fragment| fragment.bbox.x)
+        .min()
+        .unwrap_or_default();
+
+    let mut coverage = vec![0usize; (right - left) as usize];
+    for row in rows {
+        let mut covered = vec![false; coverage.len()];
+        for fragment in row {
+            let start = (fragment.bbox.x - left) as usize;
+            let end = (fragment.bbox.right() - left) as usize;
+            covered[start..end].fill(true);
+        }
+        for (count, covered) in coverage.iter_mut().zip(covered) {
+            *count += covered as usize;
+        }
+    }
+
+    let allowed = rows.len() / 10;
+    let mut boundaries = Vec::new();
+    let mut gap_start = None;
+    for (offset, count) in coverage.iter().enumerate() {
+        match (*count <= allowed, gap_start) {
+            (true, None) => gap_start = Some(offset),
+            (false, Some(start)) => {
+                if offset - start >= min_gap as usize {
+                    boundaries.push(left + ((start + offset) / 2) as u32);
+                }
+                gap_start = None;
+            }
+            _ => {}
+        }
+    }
+    boundaries
+}
+
+fn span(fragments: &[Fragment<'_>], axis: impl Fn(&PixelRect) -> (u32, u32)) -> (u32, u32) {
+    fragments
+        .iter()
+        .map(|fragment| axis(&fragment.bbox))
+        .reduce(|(start, end), (from, to)| (start.min(from), end.max(to)))
+        .unwrap_or_default()
+}
+
+/// Index of the cell a position falls in, given sorted boundaries
+fn cell_index(boundaries: &[u32], position: u32) -> usize {
+    boundaries
+        .iter()
+        .filter(|boundary| position >= **boundary)
+        .count()
+}
+
+impl OcrTable {
+    /// Build a table from a result and the image it was recognized in
+    pub fn from_result(result: &OcrResult, image: &GrayImage) -> Self {
+        let words: Vec<Fragment> = result.lines().flat_map(fragments).collect();
+        let text_height = {
+            let mut heights: Vec<u32> = words.iter().map(|word| word.bbox.height).collect();
+            heights.sort_unstable();
+            heights.get(heights.len() / 2).copied().unwrap_or(1)
+        };
+        let rules = find_rules(image);
+
+        let mut text_rows = bands(words, |word| word.bbox);
+        // Bands come sorted by top, so words a pixel apart would swap
+        for row in &mut text_rows {
+            row.sort_by_key(|word| word.bbox.x);
+        }
+        let row_spans: Vec<(u32, u32)> = text_rows
+            .iter()
+            .map(|row| span(row, |rect| (rect.y, rect.bottom())))
+            .collect();
+        let ruled_rows = fully_ruled(&row_spans, &rules.rows);
+
+        // Vertical rules only at the edges are a border, not columns
+        let (left, right) = span(&text_rows.concat(), |rect| (rect.x, rect.right()));
+        let inner_rules: Vec<u32> = rules
+            .columns
+            .iter()
+            .copied()
+            .filter(|x| *x > left && *x < right)
+            .collect();
+        let min_gap = ((text_height as f64 * COLUMN_GAP_TEXT_HEIGHTS).round() as u32).max(1);
+        let column_boundaries = if inner_rules.is_empty() {
+            aligned_columns(&text_rows, min_gap)
+        } else {
+            inner_rules.clone()
+        };
+
+        // Text rows between the same pair of rules are one wrapped row
+        let mut cells: Vec<(usize, Vec<Vec<&str>>)> = Vec::new();
+        for (row, (top, bottom)) in text_rows.iter().zip(row_spans) {
+            let key = if ruled_rows {
+                cell_index(&rules.rows, (top + bottom) / 2)
+            } else {
+                cells.len()
+            };
+            if cells.last().map(|(last, _)| *last) != Some(key) {
+                cells.push((key, vec![Vec::new(); column_boundaries.len() + 1]));
+            }
+            let (_, columns) = cells.last_mut().expect("row pushed above");
+            for word in row {
+                let center = word.bbox.x + word.bbox.width / 2;
+                columns[cell_index(&column_boundaries, center)].push(word.text);
+            }
+        }
+
+        let used: Vec<bool> = (0..=column_boundaries.len())
+            .map(|column| cells.iter().any(|(_, row)| !row[column].is_empty()))
+            .collect();
+        let rows = cells
+            .into_iter()
+            .map(|(_, row)| {
+                row.into_iter()
+                    .zip(&used)
+                    .filter(|(_, used)| **used)
+                    .map(|(words, _)| words.join(" "))
+                    .collect()
+            })
+            .collect();
+
+        Self {
+            rows,
+            ruled: ruled_rows || !inner_rules.is_empty(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// Tab-separated values; tabs and line breaks in cells become spaces
+    pub fn to_tsv(&self) -> String {
+        self.rows
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| cell.replace(['\t', '\n', '\r'], " "))
+                    .collect::<Vec<_>>()
+                    .join("\t")
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Comma-separated values, with cells quoted as in RFC 4180
+    pub fn to_csv(&self) -> String {
+        self.rows
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| {
+                        if cell.contains([',', '"', '\n', '\r']) {
+                            format!("\"{}\"", cell.replace('"', "\"\""))
+                        } else {
+                            cell.clone()
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .join(",")
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// A Markdown table with the first row as its header
+    pub fn to_markdown(&self) -> String {
+        let Some(columns) = self.rows.first().map(Vec::len) else {
+            return String::new();
+        };
+        let line = |cells: Vec<String>| format!("| {} |", cells.join(" | "));
+        let escape = |row: &Vec<String>| {
+            row.iter()
+                .map(|cell| cell.replace('|', "\\|").replace('\n', " "))
+                .collect()
+        };
+
+        let mut lines = vec![line(escape(&self.rows[0]))];
+        lines.push(line(vec!["---".to_string(); columns]));
+        lines.extend(self.rows[1..].iter().map(|row| line(escape(row))));
+        lines.join("\n")
+    }
+
+    pub fn to_format(&self, format: TableFormat) -> String {
+        match format {
+            TableFormat::Tsv => self.to_tsv(),
+            TableFormat::Csv => self.to_csv(),
+            TableFormat::Markdown => self.to_markdown(),
+        }
+    }
+}
+
+/// Recognize an image with the preferred engine and extract its table
+pub fn extract_table_from_image(image_path: &Path, options: &OcrOptions) -> AppResult<OcrTable> {
+    let result = recognize_image(image_path, options)?;
+    let image = image::open(image_path)
+        .map_err(|e| format!("Failed to open image: {}", e))?
+        .to_luma8();
+
+    let table = OcrTable::from_result(&result, &image);
+    if table.is_empty() {
+        return Err("No text recognized in image".to_string());
+    }
+    Ok(table)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ocr::{OcrBlock, OcrLine, OcrWord};
+    use image::Luma;
+
+    const IMAGE: (u32, u32) = (400, 200);
+
+    fn word(text: &str, x: u32, y: u32) -> OcrWord {
+        let bbox = PixelRect {
+            x,
+            y,
+            width: text.chars().count() as u32 * 8,
+            height: 12,
+        };
+        OcrWord::new(text.to_string(), bbox, 0.9, IMAGE)
+    }
+
+    /// One engine line per cell, as engines report widely spaced columns
+    fn result(cells: &[(&str, u32, u32)]) -> OcrResult {
+        let lines = cells
+            .iter()
+            .map(|(text, x, y)| {
+                let mut left = *x;
+                let words = text
+                    .split(' ')
+                    .map(|text| {
+                        let word = word(text, left, *y);
+                        left = word.bbox.right() + 4;
+                        word
+                    })
+                    .collect();
+                OcrLine::from_words(words, IMAGE)
+            })
+            .collect();
+        OcrResult {
+            engine: "test",
+            width: IMAGE.0,
+            height: IMAGE.1,
+            blocks: vec![OcrBlock::from_lines(lines, IMAGE)],
+        }
+    }
+
+    fn blank() -> GrayImage {
+        GrayImage::from_pixel(IMAGE.0, IMAGE.1, Luma([255]))
+    }
+
+    fn rows(table: &OcrTable) -> Vec<Vec<&str>> {
+        table
+            .rows
+            .iter()
+            .map(|row| row.iter().map(String::as_str).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_columns_from_alignment() {
+        let table = OcrTable::from_result(
+            &result(&[
+                ("Name", 10, 10),
+                ("Status", 150, 10),
+                ("Last seen", 260, 10),
+                ("web server", 10, 30),
+                ("up", 150, 30),
+                ("2 min ago", 260, 30),
+                ("db", 10, 50),
+                ("down", 150, 50),
+            ]),
+            &blank(),
+        );
+
+        assert!(!table.ruled);
+        assert_eq!(
+            rows(&table),
+            vec![
+                vec!["Name", "Status", "Last seen"],
+                vec!["web server", "up", "2 min ago"],
+                vec!["db", "down", ""],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_words_in_a_cell_stay_in_order_when_tops_differ() {
+        let line = |words: &[(&str, u32, u32)]| {
+            OcrLine::from_words(
+                words
+                    .iter()
+                    .map(|(text, x, y)| word(text, *x, *y))
+                    .collect(),
+                IMAGE,
+            )
+        };
+        let result = OcrResult {
+            engine: "test",
+            width: IMAGE.0,
+            height: IMAGE.1,
+            blocks: vec![OcrBlock::from_lines(
+                vec![
+                    line(&[("Product", 10, 11), ("name", 70, 10)]),
+                    line(&[("Price", 200, 10)]),
+                    line(&[("Better", 10, 33), ("Shot", 62, 32)]),
+                    line(&[("$0", 200, 31)]),
+                ],
+                IMAGE,
+            )],
+        };
+
+        let table = OcrTable::from_result(&result, &blank());
+
+        assert_eq!(
+            rows(&table),
+            vec![vec!["Product name", "Price"], vec!["Better Shot", "$0"]]
+        );
+    }
+
+    #[test]
+    fn test_ruled_rows_join_wrapped_cells() {
+        let mut image = blank();
+        for y in [5, 28, 70] {
+            for x in 0..IMAGE.0 {
+                image.put_pixel(x, y, Luma([120]));
+            }
+        }
+        let table = OcrTable::from_result(
+            &result(&[
+                ("Name", 10, 10),
+                ("Notes", 150, 10),
+                ("api", 10, 33),
+                ("needs a", 150, 33),
+                ("restart", 150, 50),
+            ]),
+            &image,
+        );
+
+        assert!(table.ruled);
+        assert_eq!(
+            rows(&table),
+            vec![vec!["Name", "Notes"], vec!["api", "needs a restart"]]
+        );
+    }
+
+    #[test]
+    fn test_thick_bands_are_not_rules() {
+        let mut image = blank();
+        for y in 40..60 {
+            for x in 0..IMAGE.0 {
+                image.put_pixel(x, y, Luma([200]));
+            }
+        }
+        for x in 0..IMAGE.0 {
+            image.put_pixel(x, 100, Luma([0]));
+        }
+        assert_eq!(
+            find_rules(&image),
+            Rules {
+                rows: vec![100],
+                columns: Vec::new()
+            }
+        );
+    }
+
+    #[test]
+    fn test_formats() {
+        let table = OcrTable {
+            rows: vec![
+                vec!["Item".to_string(), "Price".to_string()],
+                vec!["Tea, green".to_string(), "a|b".to_string()],
+            ],
+            ruled: false,
+        };
+
+        assert_eq!(table.to_tsv(), "Item\tPrice\nTea, green\ta|b");
+        assert_eq!(table.to_csv(), "Item,Price\n\"Tea, green\",a|b");
+        assert_eq!(
+            table.to_markdown(),
+            "| Item | Price |\n| --- | --- |\n| Tea, green | a\\|b |"
+        );
+    }
+}
@@ -0,0 +1,912 @@
+//! Screen capture through xdg-desktop-portal
+//!
+//! Wayland compositors don't let clients read the screen, so on Wayland
+//! captures go through the desktop portal over D-Bus: `Screenshot` for still
+//! images and `ScreenCast` for recordings. Portal calls return a request
+//! object whose `Response` signal carries the result, possibly after the
+//! user answered a dialog.
+//!
+//! Screen cast frames arrive over PipeWire; they are decoded by a
+//! `gst-launch-1.0` child process reading the PipeWire remote on its stdin.
+
+use image::RgbaImage;
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::fmt;
+use std::io::Read;
+use std::os::fd::OwnedFd;
+use std::os::unix::ffi::OsStringExt;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, TryRecvError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use zbus::blocking::{Connection, Proxy};
+use zbus::zvariant::{
+    DeserializeDict, DynamicType, ObjectPath, OwnedObjectPath, OwnedValue, Type, Value,
+};
+
+use crate::utils::{generate_id, move_file, AppResult};
+
+const PORTAL_DESTINATION: &str = "org.freedesktop.portal.Desktop";
+const PORTAL_PATH: &str = "/org/freedesktop/portal/desktop";
+const REQUEST_INTERFACE: &str = "org.freedesktop.portal.Request";
+const SESSION_INTERFACE: &str = "org.freedesktop.portal.Session";
+pub const SCREENSHOT_INTERFACE: &str = "org.freedesktop.portal.Screenshot";
+pub const SCREENCAST_INTERFACE: &str = "org.freedesktop.portal.ScreenCast";
+
+/// How long a request without a dialog may take to respond
+pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// How long the user has to answer a portal dialog
+pub const DIALOG_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// How long a new screen cast may take to deliver its first frame
+const FIRST_FRAME_TIMEOUT: Duration = Duration::from_secs(5);
+
+const FRAME_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// `cursor_mode` bit for drawing the cursor into the stream
+const CURSOR_MODE_EMBEDDED: u32 = 2;
+
+type Options<'a> = HashMap<&'static str, Value<'a>>;
+
+#[derive(DeserializeDict, Type, Debug)]
+#[zvariant(signature = "a{sv}")]
+struct ScreenshotResults {
+    uri: Option<String>,
+}
+
+#[derive(DeserializeDict, Type, Debug)]
+#[zvariant(signature = "a{sv}")]
+struct CreateSessionResults {
+    session_handle: Option<String>,
+}
+
+#[derive(DeserializeDict, Type, Debug)]
+#[zvariant(signature = "a{sv}")]
+struct StartResults {
+    streams: Option<Vec<(u32, StreamProperties)>>,
+}
+
+#[derive(DeserializeDict, Type, Debug)]
+#[zvariant(signature = "a{sv}")]
+struct StreamProperties {
+    position: Option<(i32, i32)>,
+    size: Option<(i32, i32)>,
+}
+
+/// What a screen cast shares
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CastSource {
+    Monitor,
+    Window,
+}
+
+impl CastSource {
+    /// The portal's `types` bitmask
+    fn bits(self) -> u32 {
+        match self {
+            CastSource::Monitor => 1,
+            CastSource::Window => 2,
+        }
+    }
+}
+
+/// One PipeWire stream of a started screen cast
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CastStream {
+    pub node_id: u32,
+    /// Position in the compositor's logical coordinates, for monitors
+    pub position: Option<(i32, i32)>,
+    pub size: Option<(u32, u32)>,
+}
+
+/// Object path element from a sender's unique name, as the portal derives it
+fn sender_element(unique_name: &str) -> String {
+    unique_name.trim_start_matches(':').replace('.', "_")
+}
+
+/// Path of the request object the portal creates for `token`
+fn request_path(unique_name: &str, token: &str) -> String {
+    format!(
+        "{}/request/{}/{}",
+        PORTAL_PATH,
+        sender_element(unique_name),
+        token
+    )
+}
+
+/// A fresh handle token; only `[A-Za-z0-9_]` is allowed in object paths
+fn handle_token() -> AppResult<String> {
+    Ok(format!("bettershot_{}", generate_id()?.replace('-', "_")))
+}
+
+/// Turn the `file://` URI of a portal screenshot into a path
+fn uri_to_path(uri: &str) -> AppResult<PathBuf> {
+    let encoded = uri
+        .strip_prefix("file://")
+        .ok_or_else(|| format!("The portal returned an unsupported URI: {}", uri))?;
+    let encoded = encoded
+        .strip_prefix("localhost")
+        .unwrap_or(encoded)
+        .as_bytes();
+
+    let mut decoded = Vec::with_capacity(encoded.len());
+    let mut i = 0;
+    while i < encoded.len() {
+        let escape = encoded
+            .get(i + 1..i + 3)
+            .filter(|hex| encoded[i] == b'%' && hex.iter().all(u8::is_ascii_hexdigit));
+        match escape {
+            Some(hex) => {
+                let hex = std::str::from_utf8(hex).expect("hex digits are ASCII");
+                decoded.push(u8::from_str_radix(hex, 16).expect("checked hex digits"));
+                i += 3;
+            }
+            None => {
+                decoded.push(encoded[i]);
+                i += 1;
+            }
+        }
+    }
+    Ok(PathBuf::from(OsString::from_vec(decoded)))
+}
+
+/// How a portal request ended without a result
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RequestError {
+    /// The user dismissed the portal's dialog
+    Cancelled,
+    Failed(String),
+}
+
+impl fmt::Display for RequestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RequestError::Cancelled => f.write_str("The request was cancelled"),
+            RequestError::Failed(message) => f.write_str(message),
+        }
+    }
+}
+
+impl From<RequestError> for String {
+    fn from(error: RequestError) -> Self {
+        error.to_string()
+    }
+}
+
+fn response_error(code: u32) -> RequestError {
+    match code {
+        1 => RequestError::Cancelled,
+        _ => RequestError::Failed(format!("The portal request failed with response {}", code)),
+    }
+}
+
+/// A portal request waiting for its `Response` signal
+///
+/// Dropping it before the portal responded closes the request, which
+/// dismisses any dialog it opened.
+pub struct PendingRequest<T> {
+    connection: Connection,
+    path: OwnedObjectPath,
+    response: Receiver<Result<T, RequestError>>,
+    finished: bool,
+}
+
+impl<T> PendingRequest<T> {
+    fn closed() -> RequestError {
+        RequestError::Failed("The portal closed the request without responding".to_string())
+    }
+
+    /// The result if the portal has responded
+    pub fn try_response(&mut self) -> Result<Option<T>, RequestError> {
+        let response = match self.response.try_recv() {
+            Ok(result) => result.map(Some),
+            Err(TryRecvError::Empty) => return Ok(None),
+            Err(TryRecvError::Disconnected) => Err(Self::closed()),
+        };
+        self.finished = true;
+        response
+    }
+
+    /// Block until the portal responds
+    pub fn wait(mut self, timeout: Duration) -> Result<T, RequestError> {
+        let response = match self.response.recv_timeout(timeout) {
+            Ok(result) => result,
+            Err(RecvTimeoutError::Timeout) => {
+                return Err(RequestError::Failed(format!(
+                    "Timed out after {} seconds waiting for the desktop portal",
+                    timeout.as_secs()
+                )))
+            }
+            Err(RecvTimeoutError::Disconnected) => Err(Self::closed()),
+        };
+        self.finished = true;
+        response
+    }
+}
+
+impl<T> Drop for PendingRequest<T> {
+    fn drop(&mut self) {
+        if self.finished {
+            return;
+        }
+        let _ = Proxy::new(
+            &self.connection,
+            PORTAL_DESTINATION,
+            self.path.clone(),
+            REQUEST_INTERFACE,
+        )
+        .and_then(|proxy| proxy.call::<_, _, ()>("Close", &()));
+    }
+}
+
+/// A connection to the desktop portal
+pub struct Portal {
+    connection: Connection,
+}
+
+impl Portal {
+    /// Connect to the portal on the session bus
+    pub fn session() -> AppResult<Self> {
+        let connection = Connection::session()
+            .map_err(|e| format!("Failed to connect to the session bus: {}", e))?;
+        Ok(Self { connection })
+    }
+
+    fn proxy(&self, interface: &'static str) -> AppResult<Proxy<'static>> {
+        Proxy::new(&self.connection, PORTAL_DESTINATION, PORTAL_PATH, interface)
+            .map_err(|e| format!("Failed to reach the desktop portal: {}", e))
+    }
+
+    /// Version of a portal interface, or `None` if it isn't available
+    pub fn version(&self, interface: &'static str) -> Option<u32> {
+        self.proxy(interface)
+            .ok()?
+            .get_property::<u32>("version")
+            .ok()
+    }
+
+    /// Call a portal method that answers through a request object
+    ///
+    /// The `Response` signal is subscribed to before the call so a quick
+    /// response can't be missed. `map` turns the response's results into
+    /// the request's value.
+    fn request<B, R, T>(
+        &self,
+        interface: &'static str,
+        method: &'static str,
+        token: &str,
+        body: &B,
+        map: fn(R) -> AppResult<T>,
+    ) -> AppResult<PendingRequest<T>>
+    where
+        B: serde::Serialize + DynamicType,
+        R: DeserializeOwned + Type + Send + 'static,
+        T: Send + 'static,
+    {
+        let unique_name = self
+            .connection
+            .unique_name()
+            .ok_or("The session bus connection has no unique name")?;
+        let path = ObjectPath::try_from(request_path(unique_name.as_str(), token))
+            .map_err(|e| format!("Invalid portal request path: {}", e))?;
+
+        let mut signals = Proxy::new(
+            &self.connection,
+            PORTAL_DESTINATION,
+            path.clone(),
+            REQUEST_INTERFACE,
+        )
+        .and_then(|proxy| proxy.receive_signal("Response"))
+        .map_err(|e| format!("Failed to subscribe to the portal response: {}", e))?;
+
+        let (sender, response) = mpsc::channel();
+        thread::spawn(move || {
+            let result = match signals.next() {
+                Some(message) => match message.body().deserialize::<(u32, R)>() {
+                    Ok((0, results)) => map(results).map_err(RequestError::Failed),
+                    Ok((code, _)) => Err(response_error(code)),
+                    Err(e) => Err(RequestError::Failed(format!(
+                        "Failed to read the portal response: {}",
+                        e
+                    ))),
+                },
+                None => Err(RequestError::Failed(
+                    "The session bus connection was closed".to_string(),
+                )),
+            };
+            let _ = sender.send(result);
+        });
+
+        let returned: OwnedObjectPath = self
+            .proxy(interface)?
+            .call(method, body)
+            .map_err(|e| format!("Portal call {}.{} failed: {}", interface, method, e))?;
+        if returned.as_str() != path.as_str() {
+            // Portals older than version 0.9 pick their own request path
+            return Err(format!(
+                "The desktop portal answered on an unexpected request path {}",
+                returned.as_str()
+            ));
+        }
+
+        Ok(PendingRequest {
+            connection: self.connection.clone(),
+            path: returned,
+            response,
+            finished: false,
+        })
+    }
+
+    /// Request a screenshot of the whole desktop
+    ///
+    /// An interactive request lets the user pick what to capture first. The
+    /// portal saves the image itself; the request resolves to its path.
+    pub fn screenshot(&self, interactive: bool) -> AppResult<PendingRequest<PathBuf>> {
+        let token = handle_token()?;
+        let options: Options = HashMap::from([
+            ("handle_token", Value::from(token.as_str())),
+            ("interactive", Value::from(interactive)),
+            ("modal", Value::from(true)),
+        ]);
+        self.request(
+            SCREENSHOT_INTERFACE,
+            "Screenshot",
+            &token,
+            &("", options),
+            |results: ScreenshotResults| {
+                let uri = results
+                    .uri
+                    .ok_or("The portal did not return a screenshot")?;
+                uri_to_path(&uri)
+            },
+        )
+    }
+
+    /// Start a screen cast, letting the user choose what to share
+    pub fn screen_cast(&self, source: CastSource) -> AppResult<ScreenCast> {
+        let token = handle_token()?;
+        let session_token = handle_token()?;
+        let options: Options = HashMap::from([
+            ("handle_token", Value::from(token.as_str())),
+            ("session_handle_token", Value::from(session_token.as_str())),
+        ]);
+        let session = self
+            .request(
+                SCREENCAST_INTERFACE,
+                "CreateSession",
+                &token,
+                &(options,),
+                |results: CreateSessionResults| {
+                    let handle = results
+                        .session_handle
+                        .ok_or("The portal did not create a screen cast session")?;
+                    OwnedObjectPath::try_from(handle)
+                        .map_err(|e| format!("Invalid screen cast session handle: {}", e))
+                },
+            )?
+            .wait(REQUEST_TIMEOUT)?;
+
+        // From here on dropping the cast closes the session
+        let mut cast = ScreenCast {
+            connection: self.connection.clone(),
+            session,
+            streams: Vec::new(),
+        };
+
+        let token = handle_token()?;
+        let mut options: Options = HashMap::from([
+            ("handle_token", Value::from(token.as_str())),
+            ("types", Value::from(source.bits())),
+            ("multiple", Value::from(false)),
+        ]);
+        let cursor_modes = self
+            .proxy(SCREENCAST_INTERFACE)?
+            .get_property::<u32>("AvailableCursorModes")
+            .unwrap_or(0);
+        if cursor_modes & CURSOR_MODE_EMBEDDED != 0 {
+            options.insert("cursor_mode", Value::from(CURSOR_MODE_EMBEDDED));
+        }
+        self.request(
+            SCREENCAST_INTERFACE,
+            "SelectSources",
+            &token,
+            &(&cast.session, options),
+            |_: HashMap<String, OwnedValue>| Ok(()),
+        )?
+        .wait(REQUEST_TIMEOUT)?;
+
+        let token = handle_token()?;
+        let options: Options = HashMap::from([("handle_token", Value::from(token.as_str()))]);
+        cast.streams = self
+            .request(
+                SCREENCAST_INTERFACE,
+                "Start",
+                &token,
+                &(&cast.session, "", options),
+                |results: StartResults| {
+                    Ok(results
+                        .streams
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(|(node_id, properties)| CastStream {
+                            node_id,
+                            position: properties.position,
+                            size: properties
+                                .size
+                                .map(|(width, height)| (width.max(0) as u32, height.max(0) as u32)),
+                        })
+                        .collect())
+                },
+            )?
+            .wait(DIALOG_TIMEOUT)?;
+
+        if cast.streams.is_empty() {
+            return Err("Nothing was selected to share".to_string());
+        }
+        Ok(cast)
+    }
+}
+
+/// A started portal screen cast session
+///
+/// Dropping it closes the session, which ends its streams.
+pub struct ScreenCast {
+    connection: Connection,
+    session: OwnedObjectPath,
+    streams: Vec<CastStream>,
+}
+
+impl ScreenCast {
+    pub fn streams(&self) -> &[CastStream] {
+        &self.streams
+    }
+
+    /// A PipeWire remote restricted to this session's streams
+    fn open_pipewire_remote(&self) -> AppResult<OwnedFd> {
+        let options: Options = HashMap::new();
+        let fd: zbus::zvariant::OwnedFd = Proxy::new(
+            &self.connection,
+            PORTAL_DESTINATION,
+            PORTAL_PATH,
+            SCREENCAST_INTERFACE,
+        )
+        .and_then(|proxy| proxy.call("OpenPipeWireRemote", &(&self.session, options)))
+        .map_err(|e| format!("Failed to open the PipeWire remote: {}", e))?;
+        Ok(fd.into())
+    }
+}
+
+impl Drop for ScreenCast {
+    fn drop(&mut self) {
+        let _ = Proxy::new(
+            &self.connection,
+            PORTAL_DESTINATION,
+            self.session.clone(),
+            SESSION_INTERFACE,
+        )
+        .and_then(|proxy| proxy.call::<_, _, ()>("Close", &()));
+    }
+}
+
+/// Decoded frames of a screen cast's first stream
+///
+/// A background reader keeps only the latest frame, so capturing never
+/// falls behind the stream.
+pub struct ScreenCastFrames {
+    decoder: Child,
+    latest: Arc<Mutex<Option<RgbaImage>>>,
+    _cast: ScreenCast,
+}
+
+impl ScreenCastFrames {
+    pub fn start(cast: ScreenCast) -> AppResult<Self> {
+        let stream = cast.streams[0];
+        let (width, height) = stream
+            .size
+            .filter(|&(width, height)| width > 0 && height > 0)
+            .ok_or("The portal did not report the screen cast size")?;
+        let remote = cast.open_pipewire_remote()?;
+
+        let mut decoder = Command::new("gst-launch-1.0")
+            .args(["-q", "pipewiresrc", "fd=0"])
+            .arg(format!("path={}", stream.node_id))
+            .args(["!", "videoconvert", "!", "videoscale", "!"])
+            .arg(format!(
+                "video/x-raw,format=RGBA,width={},height={}",
+                width, height
+            ))
+            .args(["!", "fdsink", "fd=1", "sync=false"])
+            .stdin(Stdio::from(remote))
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| {
+                format!(
+                    "Failed to start gst-launch-1.0 for the screen cast; recording on Wayland needs GStreamer with the PipeWire plugin: {}",
+                    e
+                )
+            })?;
+        let stdout = decoder
+            .stdout
+            .take()
+            .ok_or("Failed to read the screen cast decoder output")?;
+
+        let latest = Arc::new(Mutex::new(None));
+        let reader_latest = latest.clone();
+        thread::spawn(move || read_frames(stdout, width, height, &reader_latest));
+
+        Ok(Self {
+            decoder,
+            latest,
+            _cast: cast,
+        })
+    }
+
+    /// The most recent frame, waiting briefly for the first one
+    pub fn latest_frame(&mut self) -> AppResult<RgbaImage> {
+        let deadline = Instant::now() + FIRST_FRAME_TIMEOUT;
+        loop {
+            if let Some(frame) = self.latest.lock().map_err(|e| e.to_string())?.as_ref() {
+                return Ok(frame.clone());
+            }
+            if let Ok(Some(status)) = self.decoder.try_wait() {
+                return Err(format!("The screen cast decoder exited with {}", status));
+            }
+            if Instant::now() >= deadline {
+                return Err("The screen cast did not deliver a frame".to_string());
+            }
+            thread::sleep(FRAME_POLL_INTERVAL);
+        }
+    }
+}
+
+impl Drop for ScreenCastFrames {
+    fn drop(&mut self) {
+        let _ = self.decoder.kill();
+        let _ = self.decoder.wait();
+    }
+}
+
+/// Whether `gst-launch-1.0`, which decodes screen cast frames, can be run
+/// from `PATH`
+pub fn gstreamer_available() -> bool {
+    Command::new("gst-launch-1.0")
+        .arg("--version")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map(|status| status.success())
+        .unwrap_or(false)
+}
+
+/// Read raw RGBA frames until the decoder's output ends
+fn read_frames(mut output: impl Read, width: u32, height: u32, latest: &Mutex<Option<RgbaImage>>) {
+    let mut buffer = vec![0; width as usize * height as usize * 4];
+    while output.read_exact(&mut buffer).is_ok() {
+        let Some(frame) = RgbaImage::from_raw(width, height, buffer.clone()) else {
+            return;
+        };
+        match latest.lock() {
+            Ok(mut latest) => *latest = Some(frame),
+            Err(_) => return,
+        }
+    }
+}
+
+/// Load a portal screenshot and remove the portal's copy
+fn take_screenshot_file(path: &Path) -> AppResult<RgbaImage> {
+    let image = image::open(path)
+        .map_err(|e| format!("Failed to read the portal screenshot: {}", e))?
+        .to_rgba8();
+    let _ = std::fs::remove_file(path);
+    Ok(image)
+}
+
+/// Capture the whole desktop without asking the user
+pub fn grab_desktop() -> AppResult<RgbaImage> {
+    let path = Portal::session()?
+        .screenshot(false)?
+        .wait(REQUEST_TIMEOUT)?;
+    take_screenshot_file(&path)
+}
+
+/// Move a portal screenshot to `destination`
+///
+/// The portal saves into the user's pictures folder, which may be on
+/// another file system, so the file is copied when it can't be renamed.
+pub fn move_screenshot(path: &Path, destination: &Path) -> AppResult<()> {
+    move_file(path, destination).map_err(|e| format!("Failed to save the portal screenshot: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader};
+
+    #[test]
+    fn test_request_path_from_unique_name() {
+        assert_eq!(
+            request_path(":1.42", "bettershot_7_1"),
+            "/org/freedesktop/portal/desktop/request/1_42/bettershot_7_1"
+        );
+    }
+
+    #[test]
+    fn test_uri_to_path_decodes_escapes() {
+        assert_eq!(
+            uri_to_path("file:///home/ada/Pictures/Screenshot%20from%20today.png").unwrap(),
+            PathBuf::from("/home/ada/Pictures/Screenshot from today.png")
+        );
+        assert_eq!(
+            uri_to_path("file://localhost/tmp/100%25%zz.png").unwrap(),
+            PathBuf::from("/tmp/100%%zz.png")
+        );
+        assert!(uri_to_path("https://example.com/shot.png").is_err());
+    }
+
+    const BUS_CONFIG: &str = r#"<!DOCTYPE busconfig PUBLIC "-//freedesktop//DTD D-Bus Bus Configuration 1.0//EN"
+ "http://www.freedesktop.org/standards/dbus/1.0/busconfig.dtd">
+<busconfig>
+  <type>session</type>
+  <listen>unix:path=SOCKET</listen>
+  <auth>EXTERNAL</auth>
+  <policy context="default">
+    <allow send_destination="*" eavesdrop="true"/>
+    <allow eavesdrop="true"/>
+    <allow own="*"/>
+  </policy>
+</busconfig>
+"#;
+
+    /// A private session bus run by `dbus-daemon`
+    struct TestBus {
+        daemon: Child,
+        address: String,
+        dir: PathBuf,
+    }
+
+    impl TestBus {
+        fn start() -> Self {
+            let dir = std::env::temp_dir().join(format!(
+                "bettershot-portal-{}-{}",
+                std::process::id(),
+                generate_id().expect("generate bus id")
+            ));
+            std::fs::create_dir_all(&dir).expect("create bus directory");
+            let config = dir.join("bus.conf");
+            let socket = dir.join("bus");
+            std::fs::write(
+                &config,
+                BUS_CONFIG.replace("SOCKET", &socket.to_string_lossy()),
+            )
+            .expect("write bus config");
+
+            let mut daemon = Command::new("dbus-daemon")
+                .arg(format!("--config-file={}", config.display()))
+                .args(["--nofork", "--print-address"])
+                .stdout(Stdio::piped())
+                .stderr(Stdio::null())
+                .spawn()
+                .expect("dbus-daemon must be installed to run the portal tests");
+            let mut address = String::new();
+            BufReader::new(daemon.stdout.take().expect("dbus-daemon stdout"))
+                .read_line(&mut address)
+                .expect("read bus address");
+
+            Self {
+                daemon,
+                address: address.trim().to_string(),
+                dir,
+            }
+        }
+
+        fn portal(&self) -> Portal {
+            let connection = zbus::blocking::connection::Builder::address(self.address.as_str())
+                .and_then(|builder| builder.build())
+                .expect("connect to test bus");
+            Portal { connection }
+        }
+    }
+
+    impl Drop for TestBus {
+        fn drop(&mut self) {
+            let _ = self.daemon.kill();
+            let _ = self.daemon.wait();
+            let _ = std::fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    type Results = HashMap<&'static str, Value<'static>>;
+
+    fn option_string(options: &HashMap<String, OwnedValue>, key: &str) -> String {
+        match options.get(key).map(|value| &**value) {
+            Some(Value::Str(value)) => value.to_string(),
+            _ => panic!("missing option {}", key),
+        }
+    }
+
+    /// Answer a request the way the portal does: emit `Response` on the
+    /// request object and return its path
+    async fn respond(
+        connection: &zbus::Connection,
+        header: &zbus::message::Header<'_>,
+        options: &HashMap<String, OwnedValue>,
+        code: u32,
+        results: Results,
+    ) -> zbus::fdo::Result<OwnedObjectPath> {
+        let sender = header.sender().expect("method call has a sender");
+        let path = request_path(sender.as_str(), &option_string(options, "handle_token"));
+        connection
+            .emit_signal(
+                None::<zbus::names::BusName<'_>>,
+                path.as_str(),
+                REQUEST_INTERFACE,
+                "Response",
+                &(code, results),
+            )
+            .await?;
+        Ok(OwnedObjectPath::try_from(path).expect("valid request path"))
+    }
+
+    struct MockScreenshot {
+        code: u32,
+        uri: String,
+    }
+
+    #[zbus::interface(name = "org.freedesktop.portal.Screenshot")]
+    impl MockScreenshot {
+        async fn screenshot(
+            &self,
+            _parent_window: String,
+            options: HashMap<String, OwnedValue>,
+            #[zbus(header)] header: zbus::message::Header<'_>,
+            #[zbus(connection)] connection: &zbus::Connection,
+        ) -> zbus::fdo::Result<OwnedObjectPath> {
+            let results = HashMap::from([("uri", Value::from(self.uri.clone()))]);
+            respond(connection, &header, &options, self.code, results).await
+        }
+
+        #[zbus(property, name = "version")]
+        fn version(&self) -> u32 {
+            2
+        }
+    }
+
+    struct MockScreenCast;
+
+    #[zbus::interface(name = "org.freedesktop.portal.ScreenCast")]
+    impl MockScreenCast {
+        async fn create_session(
+            &self,
+            options: HashMap<String, OwnedValue>,
+            #[zbus(header)] header: zbus::message::Header<'_>,
+            #[zbus(connection)] connection: &zbus::Connection,
+        ) -> zbus::fdo::Result<OwnedObjectPath> {
+            let sender = header.sender().expect("method call has a sender");
+            let session = format!(
+                "{}/session/{}/{}",
+                PORTAL_PATH,
+                sender_element(sender.as_str()),
+                option_string(&options, "session_handle_token")
+            );
+            let results = HashMap::from([("session_handle", Value::from(session))]);
+            respond(connection, &header, &options, 0, results).await
+        }
+
+        async fn select_sources(
+            &self,
+            _session_handle: OwnedObjectPath,
+            options: HashMap<String, OwnedValue>,
+            #[zbus(header)] header: zbus::message::Header<'_>,
+            #[zbus(connection)] connection: &zbus::Connection,
+        ) -> zbus::fdo::Result<OwnedObjectPath> {
+            respond(connection, &header, &options, 0, HashMap::new()).await
+        }
+
+        async fn start(
+            &self,
+            _session_handle: OwnedObjectPath,
+            _parent_window: String,
+            options: HashMap<String, OwnedValue>,
+            #[zbus(header)] header: zbus::message::Header<'_>,
+            #[zbus(connection)] connection: &zbus::Connection,
+        ) -> zbus::fdo::Result<OwnedObjectPath> {
+            let properties: Results = HashMap::from([
+                ("position", Value::from((0i32, 0i32))),
+                ("size", Value::from((1280i32, 720i32))),
+            ]);
+            let results = HashMap::from([("streams", Value::from(vec![(42u32, properties)]))]);
+            respond(connection, &header, &options, 0, results).await
+        }
+
+        #[zbus(property, name = "AvailableCursorModes")]
+        fn available_cursor_modes(&self) -> u32 {
+            CURSOR_MODE_EMBEDDED
+        }
+
+        #[zbus(property, name = "version")]
+        fn version(&self) -> u32 {
+            5
+        }
+    }
+
+    /// Serve a mock portal on the test bus for as long as the result lives
+    fn serve_mock_portal(bus: &TestBus, screenshot: MockScreenshot) -> Connection {
+        zbus::blocking::connection::Builder::address(bus.address.as_str())
+            .and_then(|builder| builder.name(PORTAL_DESTINATION))
+            .and_then(|builder| builder.serve_at(PORTAL_PATH, screenshot))
+            .and_then(|builder| builder.serve_at(PORTAL_PATH, MockScreenCast))
+            .and_then(|builder| builder.build())
+            .expect("serve mock portal")
+    }
+
+    #[test]
+    #[ignore = "needs dbus-daemon"]
+    fn test_screenshot_through_mock_portal() {
+        let bus = TestBus::start();
+        let _mock = serve_mock_portal(
+            &bus,
+            MockScreenshot {
+                code: 0,
+                uri: "file:///tmp/Screenshot%201.png".to_string(),
+            },
+        );
+
+        let portal = bus.portal();
+        assert_eq!(portal.version(SCREENSHOT_INTERFACE), Some(2));
+        let path = portal
+            .screenshot(false)
+            .unwrap()
+            .wait(Duration::from_secs(5))
+            .unwrap();
+        assert_eq!(path, PathBuf::from("/tmp/Screenshot 1.png"));
+    }
+
+    #[test]
+    #[ignore = "needs dbus-daemon"]
+    fn test_cancelled_screenshot_is_an_error() {
+        let bus = TestBus::start();
+        let _mock = serve_mock_portal(
+            &bus,
+            MockScreenshot {
+                code: 1,
+                uri: String::new(),
+            },
+        );
+
+        let result = bus
+            .portal()
+            .screenshot(true)
+            .unwrap()
+            .wait(Duration::from_secs(5));
+        assert_eq!(result, Err(RequestError::Cancelled));
+    }
+
+    #[test]
+    #[ignore = "needs dbus-daemon"]
+    fn test_screen_cast_through_mock_portal() {
+        let bus = TestBus::start();
+        let _mock = serve_mock_portal(
+            &bus,
+            MockScreenshot {
+                code: 0,
+                uri: String::new(),
+            },
+        );
+
+        let cast = bus.portal().screen_cast(CastSource::Monitor).unwrap();
+        assert_eq!(
+            cast.streams(),
+            &[CastStream {
+                node_id: 42,
+                position: Some((0, 0)),
+                size: Some((1280, 720)),
+            }]
+        );
+    }
+}
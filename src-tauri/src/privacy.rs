@@ -0,0 +1,413 @@
+//! Sensitive-window masking
+//!
+//! Before a capture is saved, windows whose app name or title matches the
+//! user's deny-list are covered with a solid fill or pixelation. The
+//! deny-list lives in `settings.json` so it is shared with the preferences
+//! page. If masking is enabled but the window list can't be read, the
+//! capture fails rather than risk leaking something.
+//!
+//! Captures where the user picks the area in the macOS screenshot picker
+//! (window, interactive and redacted) aren't masked: the picker doesn't
+//! report where on screen the selection was.
+
+use image::{Rgba, RgbaImage};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+use xcap::Monitor;
+
+use crate::backend::{capture_backend, CaptureBackend};
+use crate::cursor::pixel_ratio;
+use crate::utils::{value_or, AppResult, SETTINGS_STORE};
+use crate::window_capture::{list_windows, WindowInfo};
+
+const ENABLED_KEY: &str = "sensitiveWindowMasking";
+const APP_NAMES_KEY: &str = "sensitiveAppNames";
+const TITLES_KEY: &str = "sensitiveWindowTitles";
+const STYLE_KEY: &str = "sensitiveMaskStyle";
+
+/// App names masked when the user hasn't configured their own list
+const DEFAULT_APP_NAMES: &[&str] = &[
+    "1Password",
+    "Bitwarden",
+    "Dashlane",
+    "KeePassXC",
+    "Keychain Access",
+    "LastPass",
+    "Passwords",
+];
+
+const MASKING_UNAVAILABLE_ON_WAYLAND: &str =
+    "Sensitive windows can't be detected on Wayland, so this capture was not masked";
+
+const MASK_COLOR: Rgba<u8> = Rgba([40, 40, 40, 255]);
+
+/// Pixelation block size in screen coordinate units
+const PIXELATE_BLOCK: f64 = 16.0;
+
+/// How masked windows are drawn
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum MaskStyle {
+    #[default]
+    Solid,
+    Pixelate,
+}
+
+/// The deny-list from settings
+#[derive(Clone, Debug, PartialEq)]
+pub struct SensitiveWindowRules {
+    pub enabled: bool,
+    pub app_names: Vec<String>,
+    pub window_titles: Vec<String>,
+    pub style: MaskStyle,
+}
+
+impl Default for SensitiveWindowRules {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            app_names: DEFAULT_APP_NAMES
+                .iter()
+                .map(|name| name.to_string())
+                .collect(),
+            window_titles: Vec::new(),
+            style: MaskStyle::default(),
+        }
+    }
+}
+
+impl SensitiveWindowRules {
+    /// Load the rules from `settings.json`, falling back to the defaults
+    pub fn load(app_handle: &AppHandle) -> AppResult<Self> {
+        let store = app_handle
+            .store(SETTINGS_STORE)
+            .map_err(|e| format!("Failed to open settings: {}", e))?;
+        let defaults = Self::default();
+
+        Ok(Self {
+            enabled: value_or(store.get(ENABLED_KEY), defaults.enabled),
+            app_names: value_or(store.get(APP_NAMES_KEY), defaults.app_names),
+            window_titles: value_or(store.get(TITLES_KEY), defaults.window_titles),
+            style: value_or(store.get(STYLE_KEY), defaults.style),
+        })
+    }
+
+    fn is_active(&self) -> bool {
+        self.enabled && (!self.app_names.is_empty() || !self.window_titles.is_empty())
+    }
+
+    /// Case-insensitive substring match against app name or title
+    fn matches(&self, window: &WindowInfo) -> bool {
+        let contains = |haystack: &str, needles: &[String]| {
+            let haystack = haystack.to_lowercase();
+            needles.iter().any(|needle| {
+                let needle = needle.trim().to_lowercase();
+                !needle.is_empty() && haystack.contains(&needle)
+            })
+        };
+
+        contains(&window.app_name, &self.app_names) || contains(&window.title, &self.window_titles)
+    }
+}
+
+/// Screen rectangle of a window to mask, in global screen coordinates
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct MaskedRect {
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+}
+
+/// Sensitive windows found on screen at capture time
+///
+/// Built once per capture and then applied to every captured image.
+#[derive(Clone, Debug, Default)]
+pub struct SensitiveWindows {
+    rects: Vec<MaskedRect>,
+    style: MaskStyle,
+    /// Why masking is enabled but can't be done
+    unavailable: Option<&'static str>,
+}
+
+impl SensitiveWindows {
+    /// Find on-screen windows matching the deny-list in settings
+    ///
+    /// Wayland doesn't let apps list other windows, so nothing can be
+    /// masked when capturing through the desktop portal; `unavailable` then
+    /// says so, for the capture to report.
+    pub fn detect(app_handle: &AppHandle) -> AppResult<Self> {
+        let rules = SensitiveWindowRules::load(app_handle)?;
+        if capture_backend() == CaptureBackend::WaylandPortal {
+            return Ok(Self::unavailable_with(&rules));
+        }
+        Self::detect_with(&rules)
+    }
+
+    fn unavailable_with(rules: &SensitiveWindowRules) -> Self {
+        Self {
+            unavailable: rules.is_active().then_some(MASKING_UNAVAILABLE_ON_WAYLAND),
+            ..Self::default()
+        }
+    }
+
+    /// Find on-screen windows matching the given rules
+    pub fn detect_with(rules: &SensitiveWindowRules) -> AppResult<Self> {
+        if !rules.is_active() {
+            return Ok(Self::default());
+        }
+
+        let windows = list_windows()
+            .map_err(|e| format!("Cannot check for sensitive windows, capture aborted: {}", e))?;
+        Ok(Self::from_windows(rules, &windows))
+    }
+
+    fn from_windows(rules: &SensitiveWindowRules, windows: &[WindowInfo]) -> Self {
+        // Covered parts of a sensitive window are masked too; over-masking is
+        // preferable to working out exact visibility and getting it wrong.
+        let rects = windows
+            .iter()
+            .filter(|window| !window.is_minimized && rules.matches(window))
+            .map(|window| MaskedRect {
+                x: window.x,
+                y: window.y,
+                width: window.width,
+                height: window.height,
+            })
+            .collect();
+
+        Self {
+            rects,
+            style: rules.style,
+            unavailable: None,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rects.is_empty()
+    }
+
+    /// Why masking was enabled but couldn't be applied, if it was
+    pub fn unavailable(&self) -> Option<&'static str> {
+        self.unavailable
+    }
+
+    /// Mask sensitive windows in an image
+    ///
+    /// `origin_x`/`origin_y` is the image's top-left corner in global screen
+    /// coordinates and `pixel_ratio` the number of image pixels per unit.
+    pub fn apply(&self, image: &mut RgbaImage, origin_x: i32, origin_y: i32, pixel_ratio: f64) {
+        let (image_width, image_height) = image.dimensions();
+        let block = ((PIXELATE_BLOCK * pixel_ratio).round() as u32).max(1);
+
+        for rect in &self.rects {
+            let to_pixel = |value: i64, origin: i32, limit: u32| {
+                (((value - origin as i64) as f64 * pixel_ratio).round() as i64)
+                    .clamp(0, limit as i64) as u32
+            };
+            let x0 = to_pixel(rect.x as i64, origin_x, image_width);
+            let y0 = to_pixel(rect.y as i64, origin_y, image_height);
+            let x1 = to_pixel(rect.x as i64 + rect.width as i64, origin_x, image_width);
+            let y1 = to_pixel(rect.y as i64 + rect.height as i64, origin_y, image_height);
+            if x0 >= x1 || y0 >= y1 {
+                continue;
+            }
+
+            match self.style {
+                MaskStyle::Solid => fill_rect(image, x0, y0, x1, y1),
+                MaskStyle::Pixelate => pixelate_rect(image, x0, y0, x1, y1, block),
+            }
+        }
+    }
+}
+
+/// Mask sensitive windows in a saved capture of the primary monitor
+///
+/// Used for captures taken by external tools, which are written straight
+/// to disk. The file is rewritten in place, or deleted if masking fails so
+/// an unmasked capture is never left behind.
+pub fn mask_primary_monitor_file(path: &str, sensitive: &SensitiveWindows) -> AppResult<()> {
+    if sensitive.is_empty() {
+        return Ok(());
+    }
+
+    let result = mask_monitor_file(path, sensitive);
+    if result.is_err() {
+        let _ = std::fs::remove_file(path);
+    }
+    result
+}
+
+fn mask_monitor_file(path: &str, sensitive: &SensitiveWindows) -> AppResult<()> {
+    let mut image = image::open(path)
+        .map_err(|e| format!("Failed to open capture for masking: {}", e))?
+        .to_rgba8();
+
+    let monitors = Monitor::all().map_err(|e| format!("Failed to get monitors: {}", e))?;
+    let primary = monitors
+        .iter()
+        .find(|monitor| monitor.is_primary().unwrap_or(false))
+        .ok_or_else(|| "No primary monitor found".to_string())?;
+    let origin_x = primary
+        .x()
+        .map_err(|e| format!("Failed to get monitor x: {}", e))?;
+    let origin_y = primary
+        .y()
+        .map_err(|e| format!("Failed to get monitor y: {}", e))?;
+    let scale_factor = primary
+        .scale_factor()
+        .map_err(|e| format!("Failed to get monitor scale factor: {}", e))?;
+
+    sensitive.apply(&mut image, origin_x, origin_y, pixel_ratio(scale_factor));
+    image
+        .save(path)
+        .map_err(|e| format!("Failed to save masked capture: {}", e))
+}
+
+pub(crate) fn fill_rect(image: &mut RgbaImage, x0: u32, y0: u32, x1: u32, y1: u32) {
+    for y in y0..y1 {
+        for x in x0..x1 {
+            image.put_pixel(x, y, MASK_COLOR);
+        }
+    }
+}
+
+/// Replace each block in the rect with its average colour
+pub(crate) fn pixelate_rect(image: &mut RgbaImage, x0: u32, y0: u32, x1: u32, y1: u32, block: u32) {
+    for by in (y0..y1).step_by(block as usize) {
+        for bx in (x0..x1).step_by(block as usize) {
+            let bx1 = (bx + block).min(x1);
+            let by1 = (by + block).min(y1);
+
+            let mut sum = [0u64; 4];
+            for y in by..by1 {
+                for x in bx..bx1 {
+                    for (total, channel) in sum.iter_mut().zip(image.get_pixel(x, y).0) {
+                        *total += channel as u64;
+                    }
+                }
+            }
+            let count = ((bx1 - bx) * (by1 - by)) as u64;
+            let average = Rgba(sum.map(|total| (total / count) as u8));
+
+            for y in by..by1 {
+                for x in bx..bx1 {
+                    image.put_pixel(x, y, average);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(app_name: &str, title: &str, x: i32, y: i32) -> WindowInfo {
+        WindowInfo {
+            id: 1,
+            pid: 1,
+            app_name: app_name.to_string(),
+            title: title.to_string(),
+            x,
+            y,
+            width: 20,
+            height: 10,
+            is_minimized: false,
+            is_focused: false,
+        }
+    }
+
+    #[test]
+    fn test_rules_match_app_name_and_title_case_insensitively() {
+        let rules = SensitiveWindowRules {
+            window_titles: vec!["online banking".to_string()],
+            ..SensitiveWindowRules::default()
+        };
+
+        assert!(rules.matches(&window("1password 8", "Vault", 0, 0)));
+        assert!(rules.matches(&window("Firefox", "My Online Banking – Firefox", 0, 0)));
+        assert!(!rules.matches(&window("Firefox", "Docs", 0, 0)));
+    }
+
+    #[test]
+    fn test_disabled_rules_detect_nothing() {
+        let rules = SensitiveWindowRules {
+            enabled: false,
+            ..SensitiveWindowRules::default()
+        };
+        assert!(SensitiveWindows::detect_with(&rules).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_unavailable_masking_is_reported_only_when_enabled() {
+        let enabled = SensitiveWindowRules::default();
+        let disabled = SensitiveWindowRules {
+            enabled: false,
+            ..SensitiveWindowRules::default()
+        };
+
+        assert!(SensitiveWindows::unavailable_with(&enabled)
+            .unavailable()
+            .is_some());
+        assert!(SensitiveWindows::unavailable_with(&disabled)
+            .unavailable()
+            .is_none());
+    }
+
+    #[test]
+    fn test_apply_solid_mask_in_image_coordinates() {
+        let rules = SensitiveWindowRules {
+            style: MaskStyle::Solid,
+            ..SensitiveWindowRules::default()
+        };
+        let windows = [
+            window("Bitwarden", "Vault", 110, 55),
+            window("Terminal", "zsh", 0, 0),
+        ];
+        let sensitive = SensitiveWindows::from_windows(&rules, &windows);
+
+        // Image origin (100, 50) at 2x: the window covers pixels (20..60, 10..30)
+        let white = Rgba([255, 255, 255, 255]);
+        let mut image = RgbaImage::from_pixel(80, 80, white);
+        sensitive.apply(&mut image, 100, 50, 2.0);
+
+        assert_eq!(*image.get_pixel(20, 10), MASK_COLOR);
+        assert_eq!(*image.get_pixel(59, 29), MASK_COLOR);
+        assert_eq!(*image.get_pixel(60, 29), white);
+        assert_eq!(*image.get_pixel(19, 10), white);
+        assert_eq!(*image.get_pixel(5, 5), white);
+    }
+
+    #[test]
+    fn test_failed_masking_removes_the_unmasked_capture() {
+        let path = std::env::temp_dir().join("bettershot_unmaskable.png");
+        std::fs::write(&path, b"not a png").unwrap();
+        let rules = SensitiveWindowRules::default();
+        let sensitive =
+            SensitiveWindows::from_windows(&rules, &[window("Bitwarden", "Vault", 0, 0)]);
+
+        assert!(mask_primary_monitor_file(&path.to_string_lossy(), &sensitive).is_err());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_default_mask_style_is_solid() {
+        assert_eq!(SensitiveWindowRules::default().style, MaskStyle::Solid);
+    }
+
+    #[test]
+    fn test_pixelate_averages_blocks() {
+        let mut image = RgbaImage::from_fn(4, 2, |x, _| {
+            if x % 2 == 0 {
+                Rgba([0, 0, 0, 255])
+            } else {
+                Rgba([200, 100, 50, 255])
+            }
+        });
+        pixelate_rect(&mut image, 0, 0, 4, 2, 2);
+
+        assert!(image.pixels().all(|p| *p == Rgba([100, 50, 25, 255])));
+    }
+}
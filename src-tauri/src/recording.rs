@@ -0,0 +1,1009 @@
+//! Screen recording module
+//!
+//! A grabber thread captures a monitor, region or window at a fixed frame
+//! rate into a bounded ring buffer, and an encoder thread drains the buffer
+//! into a GIF (natively) or an MP4/WebM (by piping raw frames to ffmpeg).
+
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{imageops, Delay, Frame, Rgba, RgbaImage};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufWriter, Read, Write};
+use std::path::PathBuf;
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+use xcap::{Monitor, Window};
+
+#[cfg(target_os = "linux")]
+use crate::backend::{capture_backend, CaptureBackend};
+use crate::countdown::TRAY_ID;
+use crate::cursor::{pixel_ratio, query_cursor, CURSOR_HALO_COLOR, CURSOR_HALO_RADIUS};
+use crate::image::blend_circle;
+#[cfg(target_os = "linux")]
+use crate::image::CropRegion;
+#[cfg(target_os = "linux")]
+use crate::portal::{CastSource, Portal, ScreenCastFrames};
+use crate::screenshot::find_monitor;
+use crate::utils::{ensure_dir, generate_filename, AppResult};
+use crate::window_capture::find_window;
+
+/// Event emitted whenever the recording state changes
+pub const RECORDING_STATE_EVENT: &str = "recording-state-changed";
+
+/// Event emitted with the output path once a recording has been encoded
+pub const RECORDING_FINISHED_EVENT: &str = "recording-finished";
+
+const CLICK_HIGHLIGHT_RADIUS: i32 = 26;
+const CLICK_HIGHLIGHT_COLOR: Rgba<u8> = Rgba([255, 69, 58, 200]);
+
+static ACTIVE_RECORDING: Mutex<Option<ActiveRecording>> = Mutex::new(None);
+
+/// What to record
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum RecordingSource {
+    Monitor {
+        monitor_id: u32,
+    },
+    /// A rectangle in physical pixels relative to the monitor
+    Region {
+        monitor_id: u32,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    },
+    Window {
+        window_id: u32,
+    },
+}
+
+/// Output container for a recording
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RecordingFormat {
+    Gif,
+    Mp4,
+    Webm,
+}
+
+impl RecordingFormat {
+    pub(crate) fn extension(self) -> &'static str {
+        match self {
+            RecordingFormat::Gif => "gif",
+            RecordingFormat::Mp4 => "mp4",
+            RecordingFormat::Webm => "webm",
+        }
+    }
+}
+
+fn default_fps() -> u32 {
+    15
+}
+
+/// Most memory the buffered frames may take, whatever `buffer_frames` allows
+///
+/// An uncompressed 4K frame is about 33 MB, so this holds a handful of them
+/// or a couple of seconds of 1080p.
+const BUFFER_BYTE_BUDGET: usize = 256 * 1024 * 1024;
+
+fn default_buffer_frames() -> usize {
+    30
+}
+
+/// Options for a recording
+#[derive(Debug, Clone, Deserialize)]
+pub struct RecordingOptions {
+    pub source: RecordingSource,
+    pub format: RecordingFormat,
+    #[serde(default = "default_fps")]
+    pub fps: u32,
+    /// Draw a soft halo around the cursor
+    #[serde(default)]
+    pub highlight_cursor: bool,
+    /// Draw a ring around the cursor while the primary button is held
+    #[serde(default)]
+    pub highlight_clicks: bool,
+    /// Frames buffered between grabber and encoder before the oldest is dropped
+    ///
+    /// Fewer are kept when large frames would exceed [`BUFFER_BYTE_BUDGET`].
+    #[serde(default = "default_buffer_frames")]
+    pub buffer_frames: usize,
+}
+
+/// Current state of the recorder
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RecordingState {
+    Idle,
+    Recording,
+    Paused,
+}
+
+/// Payload of the `recording-state-changed` event
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingStateChanged {
+    pub state: RecordingState,
+    pub error: Option<String>,
+}
+
+/// Payload of the `recording-finished` event
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingFinished {
+    pub path: String,
+    pub frame_count: usize,
+    pub dropped_frames: usize,
+}
+
+/// Bounded FIFO between the grabber and encoder threads
+///
+/// When the encoder falls behind, the oldest buffered frame is dropped so
+/// the grabber never blocks and memory stays bounded by both a frame count
+/// and a byte budget.
+struct FrameRing {
+    inner: Mutex<RingInner>,
+    available: Condvar,
+    capacity: usize,
+    max_bytes: usize,
+}
+
+struct RingInner {
+    frames: VecDeque<RgbaImage>,
+    bytes: usize,
+    closed: bool,
+    pushed: usize,
+    dropped: usize,
+}
+
+impl FrameRing {
+    fn new(capacity: usize, max_bytes: usize) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            inner: Mutex::new(RingInner {
+                frames: VecDeque::with_capacity(capacity),
+                bytes: 0,
+                closed: false,
+                pushed: 0,
+                dropped: 0,
+            }),
+            available: Condvar::new(),
+            capacity,
+            max_bytes,
+        }
+    }
+
+    /// Buffer a frame, dropping the oldest ones to make room
+    ///
+    /// The newest frame is always kept, even if it alone exceeds the budget.
+    fn push(&self, frame: RgbaImage) {
+        if let Ok(mut inner) = self.inner.lock() {
+            let frame_bytes = frame.as_raw().len();
+            while inner.frames.len() >= self.capacity
+                || (!inner.frames.is_empty() && inner.bytes + frame_bytes > self.max_bytes)
+            {
+                let Some(oldest) = inner.frames.pop_front() else {
+                    break;
+                };
+                inner.bytes -= oldest.as_raw().len();
+                inner.dropped += 1;
+            }
+            inner.bytes += frame_bytes;
+            inner.frames.push_back(frame);
+            inner.pushed += 1;
+            self.available.notify_one();
+        }
+    }
+
+    /// Wait for the next frame; returns `None` once closed and drained
+    fn pop(&self) -> Option<RgbaImage> {
+        let mut inner = self.inner.lock().ok()?;
+        loop {
+            if let Some(frame) = inner.frames.pop_front() {
+                inner.bytes -= frame.as_raw().len();
+                return Some(frame);
+            }
+            if inner.closed {
+                return None;
+            }
+            inner = self.available.wait(inner).ok()?;
+        }
+    }
+
+    fn close(&self) {
+        if let Ok(mut inner) = self.inner.lock() {
+            inner.closed = true;
+            self.available.notify_all();
+        }
+    }
+
+    /// Frames pushed and frames dropped so far
+    fn counts(&self) -> (usize, usize) {
+        self.inner
+            .lock()
+            .map(|inner| (inner.pushed, inner.dropped))
+            .unwrap_or((0, 0))
+    }
+}
+
+/// Resolved capture target
+///
+/// xcap handles are not `Send` on every platform, so targets are resolved
+/// on the thread that captures from them.
+pub(crate) struct CaptureTarget {
+    kind: TargetKind,
+    pixel_ratio: f64,
+}
+
+enum TargetKind {
+    Monitor(Monitor),
+    Region {
+        monitor: Monitor,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    },
+    Window(Window),
+    /// Portal screenshots of the whole desktop, on Wayland
+    #[cfg(target_os = "linux")]
+    Portal { region: Option<CropRegion> },
+    /// A portal screen cast the user chose to share, on Wayland
+    #[cfg(target_os = "linux")]
+    ScreenCast {
+        frames: Mutex<ScreenCastFrames>,
+        region: Option<CropRegion>,
+    },
+}
+
+/// The part of a frame a region source covers
+#[cfg(target_os = "linux")]
+fn region_of(source: &RecordingSource) -> Option<CropRegion> {
+    match *source {
+        RecordingSource::Region {
+            x,
+            y,
+            width,
+            height,
+            ..
+        } => Some(CropRegion {
+            x,
+            y,
+            width,
+            height,
+        }),
+        _ => None,
+    }
+}
+
+/// Crop a frame to a region, clamped to the frame
+#[cfg(target_os = "linux")]
+fn crop_to(frame: RgbaImage, region: Option<CropRegion>) -> RgbaImage {
+    match region {
+        Some(region) => {
+            let region = CropRegion::clamped(
+                region.x,
+                region.y,
+                region.width,
+                region.height,
+                frame.width(),
+                frame.height(),
+            );
+            imageops::crop_imm(&frame, region.x, region.y, region.width, region.height).to_image()
+        }
+        None => frame,
+    }
+}
+
+impl CaptureTarget {
+    /// Resolve a target for continuous capture
+    ///
+    /// On Wayland this starts a portal screen cast, which asks the user what
+    /// to share once, rather than taking a portal screenshot per frame.
+    pub(crate) fn resolve_stream(source: &RecordingSource) -> AppResult<Self> {
+        #[cfg(target_os = "linux")]
+        if capture_backend() == CaptureBackend::WaylandPortal {
+            let cast_source = match source {
+                RecordingSource::Window { .. } => CastSource::Window,
+                _ => CastSource::Monitor,
+            };
+            let cast = Portal::session()?.screen_cast(cast_source)?;
+            // Regions are selected on the whole desktop; make them relative
+            // to the shared monitor
+            let (origin_x, origin_y) = cast.streams()[0].position.unwrap_or((0, 0));
+            let region = region_of(source).map(|region| CropRegion {
+                x: (region.x as i32 - origin_x).max(0) as u32,
+                y: (region.y as i32 - origin_y).max(0) as u32,
+                ..region
+            });
+            return Ok(Self {
+                kind: TargetKind::ScreenCast {
+                    frames: Mutex::new(ScreenCastFrames::start(cast)?),
+                    region,
+                },
+                pixel_ratio: 1.0,
+            });
+        }
+        Self::resolve(source)
+    }
+
+    pub(crate) fn resolve(source: &RecordingSource) -> AppResult<Self> {
+        #[cfg(target_os = "linux")]
+        if capture_backend() == CaptureBackend::WaylandPortal {
+            if let RecordingSource::Window { .. } = source {
+                return Err(
+                    "Window sources need a screen cast through the desktop portal".to_string(),
+                );
+            }
+            return Ok(Self {
+                kind: TargetKind::Portal {
+                    region: region_of(source),
+                },
+                pixel_ratio: 1.0,
+            });
+        }
+
+        let kind = match *source {
+            RecordingSource::Monitor { monitor_id } => {
+                TargetKind::Monitor(find_monitor(monitor_id)?)
+            }
+            RecordingSource::Region {
+                monitor_id,
+                x,
+                y,
+                width,
+                height,
+            } => TargetKind::Region {
+                monitor: find_monitor(monitor_id)?,
+                x,
+                y,
+                width,
+                height,
+            },
+            RecordingSource::Window { window_id } => TargetKind::Window(find_window(window_id)?),
+        };
+
+        let scale_factor = match &kind {
+            TargetKind::Monitor(monitor) | TargetKind::Region { monitor, .. } => {
+                monitor.scale_factor()
+            }
+            TargetKind::Window(window) => window
+                .current_monitor()
+                .and_then(|monitor| monitor.scale_factor()),
+            #[cfg(target_os = "linux")]
+            TargetKind::Portal { .. } | TargetKind::ScreenCast { .. } => Ok(1.0),
+        }
+        .unwrap_or(1.0);
+
+        Ok(Self {
+            kind,
+            pixel_ratio: pixel_ratio(scale_factor),
+        })
+    }
+
+    pub(crate) fn capture(&self) -> AppResult<RgbaImage> {
+        let result = match &self.kind {
+            TargetKind::Monitor(monitor) => monitor.capture_image(),
+            TargetKind::Region {
+                monitor,
+                x,
+                y,
+                width,
+                height,
+            } => monitor.capture_region(*x, *y, *width, *height),
+            TargetKind::Window(window) => window.capture_image(),
+            #[cfg(target_os = "linux")]
+            TargetKind::Portal { region } => {
+                return Ok(crop_to(crate::portal::grab_desktop()?, *region));
+            }
+            #[cfg(target_os = "linux")]
+            TargetKind::ScreenCast { frames, region } => {
+                let frame = frames
+                    .lock()
+                    .map_err(|e| format!("Failed to lock screen cast: {}", e))?
+                    .latest_frame()?;
+                return Ok(crop_to(frame, *region));
+            }
+        };
+        result.map_err(|e| format!("Failed to capture frame: {}", e))
+    }
+
+    /// Map a global cursor position to pixel coordinates within a captured frame
+    fn frame_position(&self, cursor_x: f64, cursor_y: f64) -> Option<(i32, i32)> {
+        let (origin_x, origin_y, offset_x, offset_y) = match &self.kind {
+            TargetKind::Monitor(monitor) => (monitor.x().ok()?, monitor.y().ok()?, 0, 0),
+            TargetKind::Region { monitor, x, y, .. } => {
+                (monitor.x().ok()?, monitor.y().ok()?, *x, *y)
+            }
+            TargetKind::Window(window) => (window.x().ok()?, window.y().ok()?, 0, 0),
+            // The compositor doesn't share the cursor position
+            #[cfg(target_os = "linux")]
+            TargetKind::Portal { .. } | TargetKind::ScreenCast { .. } => return None,
+        };
+
+        let x = (cursor_x - origin_x as f64) * self.pixel_ratio - offset_x as f64;
+        let y = (cursor_y - origin_y as f64) * self.pixel_ratio - offset_y as f64;
+        Some((x.round() as i32, y.round() as i32))
+    }
+}
+
+/// Draw cursor and click highlights onto a frame
+fn highlight_cursor(target: &CaptureTarget, frame: &mut RgbaImage, options: &RecordingOptions) {
+    let Ok(cursor) = query_cursor() else {
+        return;
+    };
+    let Some((x, y)) = target.frame_position(cursor.x, cursor.y) else {
+        return;
+    };
+
+    if options.highlight_cursor {
+        blend_circle(frame, x, y, CURSOR_HALO_RADIUS, 0, CURSOR_HALO_COLOR);
+    }
+    if options.highlight_clicks && cursor.pressed {
+        blend_circle(
+            frame,
+            x,
+            y,
+            CLICK_HIGHLIGHT_RADIUS,
+            CLICK_HIGHLIGHT_RADIUS - 4,
+            CLICK_HIGHLIGHT_COLOR,
+        );
+    }
+}
+
+/// Destination for encoded frames
+enum FrameEncoder {
+    Gif {
+        encoder: GifEncoder<BufWriter<File>>,
+        delay: Delay,
+    },
+    Ffmpeg {
+        child: Child,
+        stdin: ChildStdin,
+    },
+}
+
+impl FrameEncoder {
+    fn create(
+        format: RecordingFormat,
+        path: &PathBuf,
+        width: u32,
+        height: u32,
+        fps: u32,
+    ) -> AppResult<Self> {
+        match format {
+            RecordingFormat::Gif => {
+                let file =
+                    File::create(path).map_err(|e| format!("Failed to create GIF file: {}", e))?;
+                let mut encoder = GifEncoder::new_with_speed(BufWriter::new(file), 10);
+                encoder
+                    .set_repeat(Repeat::Infinite)
+                    .map_err(|e| format!("Failed to configure GIF encoder: {}", e))?;
+                Ok(FrameEncoder::Gif {
+                    encoder,
+                    delay: Delay::from_numer_denom_ms(1000, fps),
+                })
+            }
+            RecordingFormat::Mp4 | RecordingFormat::Webm => {
+                let codec_args: &[&str] = if format == RecordingFormat::Mp4 {
+                    &[
+                        "-c:v",
+                        "libx264",
+                        "-preset",
+                        "veryfast",
+                        "-movflags",
+                        "+faststart",
+                    ]
+                } else {
+                    &["-c:v", "libvpx-vp9", "-b:v", "0", "-crf", "32"]
+                };
+
+                let mut child = Command::new("ffmpeg")
+                    .args([
+                        "-y",
+                        "-loglevel",
+                        "error",
+                        "-f",
+                        "rawvideo",
+                        "-pix_fmt",
+                        "rgba",
+                    ])
+                    .arg("-s")
+                    .arg(format!("{}x{}", width, height))
+                    .arg("-r")
+                    .arg(fps.to_string())
+                    .args(["-i", "-"])
+                    // yuv420p needs even dimensions
+                    .args([
+                        "-vf",
+                        "scale=trunc(iw/2)*2:trunc(ih/2)*2",
+                        "-pix_fmt",
+                        "yuv420p",
+                    ])
+                    .args(codec_args)
+                    .arg(path)
+                    .stdin(Stdio::piped())
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::piped())
+                    .spawn()
+                    .map_err(|e| format!("Failed to start ffmpeg: {}", e))?;
+
+                let stdin = child.stdin.take().ok_or("Failed to open ffmpeg input")?;
+                Ok(FrameEncoder::Ffmpeg { child, stdin })
+            }
+        }
+    }
+
+    fn write(&mut self, frame: RgbaImage) -> AppResult<()> {
+        match self {
+            FrameEncoder::Gif { encoder, delay } => encoder
+                .encode_frame(Frame::from_parts(frame, 0, 0, *delay))
+                .map_err(|e| format!("Failed to encode GIF frame: {}", e)),
+            FrameEncoder::Ffmpeg { child, stdin } => {
+                stdin
+                    .write_all(frame.as_raw())
+                    .map_err(|e| match child.try_wait() {
+                        Ok(Some(status)) => {
+                            format!("ffmpeg stopped ({}): {}", status, read_stderr(child))
+                        }
+                        _ => format!("Failed to write frame to ffmpeg: {}", e),
+                    })
+            }
+        }
+    }
+
+    fn finish(self) -> AppResult<()> {
+        match self {
+            FrameEncoder::Gif { encoder, .. } => {
+                // Dropping the encoder writes the GIF trailer
+                drop(encoder);
+                Ok(())
+            }
+            FrameEncoder::Ffmpeg { child, stdin } => {
+                // Closing stdin tells ffmpeg the stream has ended
+                drop(stdin);
+                let output = child
+                    .wait_with_output()
+                    .map_err(|e| format!("Failed to wait for ffmpeg: {}", e))?;
+                if !output.status.success() {
+                    let stderr = String::from_utf8_lossy(&output.stderr);
+                    return Err(format!("ffmpeg failed: {}", stderr.trim()));
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Whatever an exited ffmpeg printed, to explain why it stopped
+fn read_stderr(child: &mut Child) -> String {
+    let mut stderr = String::new();
+    if let Some(mut pipe) = child.stderr.take() {
+        let _ = pipe.read_to_string(&mut stderr);
+    }
+    stderr.trim().to_string()
+}
+
+/// Make a frame match the size of the first one (windows can be resized mid-recording)
+fn fit_frame(frame: RgbaImage, width: u32, height: u32) -> RgbaImage {
+    if frame.dimensions() == (width, height) {
+        return frame;
+    }
+    let mut canvas = RgbaImage::new(width, height);
+    imageops::replace(&mut canvas, &frame, 0, 0);
+    canvas
+}
+
+/// Encode a sequence of frames into a GIF, MP4 or WebM at `path`
+///
+/// The output size is taken from the first frame; later frames are padded
+/// or cropped to match.
+pub(crate) fn encode_frames(
+    mut frames: impl Iterator<Item = AppResult<RgbaImage>>,
+    format: RecordingFormat,
+    fps: u32,
+    path: &PathBuf,
+) -> AppResult<()> {
+    let first = frames.next().ok_or("No frames were recorded")??;
+    let (width, height) = first.dimensions();
+
+    let mut encoder = FrameEncoder::create(format, path, width, height, fps)?;
+    encoder.write(first)?;
+
+    for frame in frames {
+        encoder.write(fit_frame(frame?, width, height))?;
+    }
+
+    encoder.finish()
+}
+
+fn run_encoder(
+    ring: &FrameRing,
+    format: RecordingFormat,
+    fps: u32,
+    path: &PathBuf,
+) -> AppResult<()> {
+    encode_frames(std::iter::from_fn(|| ring.pop().map(Ok)), format, fps, path)
+}
+
+/// Pause and stop flags shared with the grabber thread
+struct RecordingControl {
+    paused: AtomicBool,
+    stopped: AtomicBool,
+}
+
+struct ActiveRecording {
+    control: Arc<RecordingControl>,
+    ring: Arc<FrameRing>,
+    grabber: JoinHandle<AppResult<()>>,
+    encoder: JoinHandle<AppResult<()>>,
+    path: PathBuf,
+}
+
+fn run_grabber(
+    options: &RecordingOptions,
+    control: &RecordingControl,
+    ring: &FrameRing,
+) -> AppResult<()> {
+    let target = CaptureTarget::resolve_stream(&options.source)?;
+    let interval = Duration::from_secs_f64(1.0 / options.fps.max(1) as f64);
+    let mut next_frame = Instant::now();
+
+    while !control.stopped.load(Ordering::SeqCst) {
+        if !control.paused.load(Ordering::SeqCst) {
+            let mut frame = target.capture()?;
+            if options.highlight_cursor || options.highlight_clicks {
+                highlight_cursor(&target, &mut frame, options);
+            }
+            ring.push(frame);
+        }
+
+        next_frame += interval;
+        let now = Instant::now();
+        if next_frame > now {
+            thread::sleep(next_frame - now);
+        } else {
+            // Fell behind; don't try to catch up with a burst of frames
+            next_frame = now;
+        }
+    }
+
+    Ok(())
+}
+
+fn set_recording_state(app_handle: &AppHandle, state: RecordingState, error: Option<String>) {
+    if let Some(tray) = app_handle.tray_by_id(TRAY_ID) {
+        let (tooltip, title) = match state {
+            RecordingState::Idle => ("Better Shot", None),
+            RecordingState::Recording => ("Better Shot – Recording", Some("● REC")),
+            RecordingState::Paused => ("Better Shot – Recording paused", Some("❚❚ REC")),
+        };
+        let _ = tray.set_tooltip(Some(tooltip));
+        let _ = tray.set_title(title);
+    }
+
+    let _ = app_handle.emit(
+        RECORDING_STATE_EVENT,
+        RecordingStateChanged { state, error },
+    );
+}
+
+/// Whether an `ffmpeg` binary can be run from `PATH`
+pub(crate) fn ffmpeg_available() -> bool {
+    Command::new("ffmpeg")
+        .arg("-version")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map(|status| status.success())
+        .unwrap_or(false)
+}
+
+/// Clear a recording whose grabber has already exited, such as after a
+/// capture error, so the next recording can start
+///
+/// Its state change was reported when the grabber failed; the partial
+/// output is removed.
+fn reap_finished(active: &mut Option<ActiveRecording>) {
+    if !active
+        .as_ref()
+        .is_some_and(|recording| recording.grabber.is_finished())
+    {
+        return;
+    }
+    let Some(recording) = active.take() else {
+        return;
+    };
+    let _ = recording.grabber.join();
+    let _ = recording.encoder.join();
+    let _ = std::fs::remove_file(&recording.path);
+}
+
+/// Start recording; only one recording can run at a time
+pub fn start_recording(
+    app_handle: &AppHandle,
+    options: RecordingOptions,
+    save_dir: &str,
+) -> AppResult<String> {
+    if options.format != RecordingFormat::Gif && !ffmpeg_available() {
+        return Err(
+            "ffmpeg is required for MP4/WebM recording but was not found on PATH".to_string(),
+        );
+    }
+
+    let mut active = ACTIVE_RECORDING
+        .lock()
+        .map_err(|e| format!("Failed to lock recorder: {}", e))?;
+    reap_finished(&mut active);
+    if active.is_some() {
+        return Err("A recording is already in progress".to_string());
+    }
+
+    let save_path = PathBuf::from(save_dir);
+    ensure_dir(&save_path)?;
+    let path = save_path.join(generate_filename("recording", options.format.extension())?);
+
+    let control = Arc::new(RecordingControl {
+        paused: AtomicBool::new(false),
+        stopped: AtomicBool::new(false),
+    });
+    let ring = Arc::new(FrameRing::new(options.buffer_frames, BUFFER_BYTE_BUDGET));
+
+    let encoder = {
+        let app_handle = app_handle.clone();
+        let control = control.clone();
+        let ring = ring.clone();
+        let path = path.clone();
+        let (format, fps) = (options.format, options.fps.max(1));
+        thread::spawn(move || {
+            let result = run_encoder(&ring, format, fps, &path);
+            // An encoder that fails mid-recording, such as ffmpeg exiting,
+            // stops the grabber and is reported now rather than at stop
+            if let Err(e) = &result {
+                if !control.stopped.swap(true, Ordering::SeqCst) {
+                    set_recording_state(&app_handle, RecordingState::Idle, Some(e.clone()));
+                }
+            }
+            result
+        })
+    };
+
+    let grabber = {
+        let app_handle = app_handle.clone();
+        let control = control.clone();
+        let ring = ring.clone();
+        thread::spawn(move || {
+            let result = run_grabber(&options, &control, &ring);
+            ring.close();
+            if let Err(e) = &result {
+                control.stopped.store(true, Ordering::SeqCst);
+                set_recording_state(&app_handle, RecordingState::Idle, Some(e.clone()));
+            }
+            result
+        })
+    };
+
+    *active = Some(ActiveRecording {
+        control,
+        ring,
+        grabber,
+        encoder,
+        path: path.clone(),
+    });
+    drop(active);
+
+    set_recording_state(app_handle, RecordingState::Recording, None);
+    Ok(path.to_string_lossy().into_owned())
+}
+
+/// Pause or resume the active recording
+pub fn set_recording_paused(app_handle: &AppHandle, paused: bool) -> AppResult<()> {
+    let active = ACTIVE_RECORDING
+        .lock()
+        .map_err(|e| format!("Failed to lock recorder: {}", e))?;
+    let recording = active.as_ref().ok_or("No recording is in progress")?;
+
+    recording.control.paused.store(paused, Ordering::SeqCst);
+    drop(active);
+
+    let state = if paused {
+        RecordingState::Paused
+    } else {
+        RecordingState::Recording
+    };
+    set_recording_state(app_handle, state, None);
+    Ok(())
+}
+
+/// Stop the active recording, wait for encoding to finish and return the output path
+pub fn stop_recording(app_handle: &AppHandle) -> AppResult<String> {
+    let recording = ACTIVE_RECORDING
+        .lock()
+        .map_err(|e| format!("Failed to lock recorder: {}", e))?
+        .take()
+        .ok_or("No recording is in progress")?;
+
+    recording.control.stopped.store(true, Ordering::SeqCst);
+
+    let grab_result = recording
+        .grabber
+        .join()
+        .map_err(|_| "Recording thread panicked".to_string())?;
+    let encode_result = recording
+        .encoder
+        .join()
+        .map_err(|_| "Encoder thread panicked".to_string())?;
+
+    set_recording_state(app_handle, RecordingState::Idle, None);
+
+    grab_result?;
+    encode_result?;
+
+    let (frame_count, dropped_frames) = recording.ring.counts();
+    let path = recording.path.to_string_lossy().into_owned();
+    let _ = app_handle.emit(
+        RECORDING_FINISHED_EVENT,
+        RecordingFinished {
+            path: path.clone(),
+            frame_count,
+            dropped_frames,
+        },
+    );
+
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_ring_drops_oldest_when_full() {
+        let ring = FrameRing::new(2, usize::MAX);
+        for value in 1..=3u8 {
+            ring.push(RgbaImage::from_pixel(1, 1, Rgba([value, 0, 0, 255])));
+        }
+        ring.close();
+
+        assert_eq!(ring.pop().unwrap().get_pixel(0, 0)[0], 2);
+        assert_eq!(ring.pop().unwrap().get_pixel(0, 0)[0], 3);
+        assert!(ring.pop().is_none());
+        assert_eq!(ring.counts(), (3, 1));
+    }
+
+    #[test]
+    fn test_frame_ring_drops_oldest_over_byte_budget() {
+        // Each 2x2 frame is 16 bytes, so the budget holds two of them
+        let ring = FrameRing::new(10, 32);
+        for value in 1..=3u8 {
+            ring.push(RgbaImage::from_pixel(2, 2, Rgba([value, 0, 0, 255])));
+        }
+        ring.push(RgbaImage::from_pixel(4, 4, Rgba([4, 0, 0, 255])));
+        ring.close();
+
+        // A frame larger than the budget is still kept on its own
+        assert_eq!(ring.pop().unwrap().get_pixel(0, 0)[0], 4);
+        assert!(ring.pop().is_none());
+        assert_eq!(ring.counts(), (4, 3));
+    }
+
+    #[test]
+    fn test_frame_ring_pop_waits_for_producer() {
+        let ring = Arc::new(FrameRing::new(4, usize::MAX));
+        let producer = {
+            let ring = ring.clone();
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(20));
+                ring.push(RgbaImage::new(2, 2));
+                ring.close();
+            })
+        };
+
+        assert!(ring.pop().is_some());
+        assert!(ring.pop().is_none());
+        producer.join().unwrap();
+    }
+
+    #[test]
+    fn test_reap_clears_failed_recording_but_keeps_running_one() {
+        let recording = |grabber: JoinHandle<AppResult<()>>, path: PathBuf| ActiveRecording {
+            control: Arc::new(RecordingControl {
+                paused: AtomicBool::new(false),
+                stopped: AtomicBool::new(false),
+            }),
+            ring: Arc::new(FrameRing::new(1, usize::MAX)),
+            grabber,
+            encoder: thread::spawn(|| Ok(())),
+            path,
+        };
+
+        let (release, wait) = std::sync::mpsc::channel::<()>();
+        let mut active = Some(recording(
+            thread::spawn(move || {
+                let _ = wait.recv();
+                Ok(())
+            }),
+            std::env::temp_dir().join("bettershot_recording_running.gif"),
+        ));
+        reap_finished(&mut active);
+        assert!(active.is_some());
+        release.send(()).unwrap();
+        active.take().unwrap().grabber.join().unwrap().unwrap();
+
+        let path = std::env::temp_dir().join("bettershot_recording_failed.gif");
+        std::fs::write(&path, b"GIF").unwrap();
+        let failed = thread::spawn(|| Err("Window was closed".to_string()));
+        while !failed.is_finished() {
+            thread::sleep(Duration::from_millis(1));
+        }
+        let mut active = Some(recording(failed, path.clone()));
+        reap_finished(&mut active);
+        assert!(active.is_none());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_fit_frame_pads_to_size() {
+        let frame = RgbaImage::from_pixel(2, 2, Rgba([9, 9, 9, 255]));
+        let fitted = fit_frame(frame, 3, 4);
+
+        assert_eq!(fitted.dimensions(), (3, 4));
+        assert_eq!(*fitted.get_pixel(1, 1), Rgba([9, 9, 9, 255]));
+        assert_eq!(*fitted.get_pixel(2, 3), Rgba([0, 0, 0, 0]));
+    }
+
+    #[test]
+    fn test_gif_encoder_writes_file() {
+        let path = std::env::temp_dir().join("bettershot_recording_test.gif");
+        let ring = FrameRing::new(4, usize::MAX);
+        ring.push(RgbaImage::from_pixel(4, 4, Rgba([255, 0, 0, 255])));
+        ring.push(RgbaImage::from_pixel(4, 4, Rgba([0, 255, 0, 255])));
+        ring.close();
+
+        run_encoder(&ring, RecordingFormat::Gif, 10, &path).unwrap();
+
+        let bytes = std::fs::read(&path).unwrap();
+        assert_eq!(&bytes[0..3], b"GIF");
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_ffmpeg_exit_is_reported_when_writing() {
+        let mut child = Command::new("sh")
+            .args(["-c", "echo 'Unknown encoder' >&2; exit 1"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let stdin = child.stdin.take().unwrap();
+        child.wait().unwrap();
+
+        let mut encoder = FrameEncoder::Ffmpeg { child, stdin };
+        let error = encoder.write(RgbaImage::new(64, 64)).unwrap_err();
+        assert!(error.starts_with("ffmpeg stopped"), "{}", error);
+        assert!(error.ends_with("Unknown encoder"), "{}", error);
+    }
+
+    #[test]
+    fn test_recording_options_deserialize() {
+        let options: RecordingOptions = serde_json::from_str(
+            r#"{"source":{"type":"region","monitor_id":1,"x":0,"y":0,"width":10,"height":10},"format":"webm"}"#,
+        )
+        .unwrap();
+
+        assert_eq!(options.format, RecordingFormat::Webm);
+        assert_eq!(options.fps, 15);
+        assert!(matches!(
+            options.source,
+            RecordingSource::Region { width: 10, .. }
+        ));
+    }
+}
@@ -0,0 +1,396 @@
+//! Region history and named saved regions
+//!
+//! Every region cropped by `capture_region` is remembered so it can be
+//! captured again later with identical framing, without the selector.
+//! History and saved regions are persisted in the `regions.json` store.
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+
+use crate::cursor::{composite_cursor, pixel_ratio, CursorOverlay};
+use crate::image::{crop_frame, CropRegion};
+use crate::privacy::SensitiveWindows;
+use crate::screenshot::find_monitor;
+use crate::utils::AppResult;
+
+const REGIONS_STORE: &str = "regions.json";
+const HISTORY_KEY: &str = "history";
+const SAVED_KEY: &str = "saved";
+
+/// Number of recent regions kept in the history
+const MAX_HISTORY: usize = 20;
+
+/// A rectangle in physical pixels on a specific monitor
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CapturedRegion {
+    pub monitor_id: u32,
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    /// Monitor scale factor at the time of the selection
+    pub scale_factor: f32,
+    pub captured_at: u64,
+}
+
+impl CapturedRegion {
+    fn same_rect(&self, other: &CapturedRegion) -> bool {
+        self.monitor_id == other.monitor_id
+            && (self.x, self.y, self.width, self.height)
+                == (other.x, other.y, other.width, other.height)
+    }
+
+    /// The crop rect for a monitor now running at `scale_factor`
+    ///
+    /// The rect is stored in physical pixels, so if the display scale has
+    /// changed since it was recorded it is rescaled to cover the same area.
+    fn crop_region(&self, scale_factor: f32) -> CropRegion {
+        let ratio = if (scale_factor - self.scale_factor).abs() < 0.01 || self.scale_factor <= 0.0 {
+            1.0
+        } else {
+            scale_factor as f64 / self.scale_factor as f64
+        };
+        let scale = |value: u32| (value as f64 * ratio).round() as u32;
+
+        CropRegion {
+            x: scale(self.x),
+            y: scale(self.y),
+            width: scale(self.width),
+            height: scale(self.height),
+        }
+    }
+}
+
+/// A region saved under a user-chosen name
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedRegion {
+    pub name: String,
+    pub region: CapturedRegion,
+    /// Set while the region's monitor is disconnected; the region can be
+    /// captured again once the monitor is back
+    #[serde(default)]
+    pub monitor_disconnected: bool,
+}
+
+fn load_list<T: DeserializeOwned>(app_handle: &AppHandle, key: &str) -> AppResult<Vec<T>> {
+    let store = app_handle
+        .store(REGIONS_STORE)
+        .map_err(|e| format!("Failed to open region store: {}", e))?;
+
+    match store.get(key) {
+        Some(value) => serde_json::from_value(value)
+            .map_err(|e| format!("Failed to read saved regions: {}", e)),
+        None => Ok(Vec::new()),
+    }
+}
+
+fn save_list<T: Serialize>(app_handle: &AppHandle, key: &str, items: &[T]) -> AppResult<()> {
+    let store = app_handle
+        .store(REGIONS_STORE)
+        .map_err(|e| format!("Failed to open region store: {}", e))?;
+
+    let value =
+        serde_json::to_value(items).map_err(|e| format!("Failed to serialize regions: {}", e))?;
+    store.set(key, value);
+    store
+        .save()
+        .map_err(|e| format!("Failed to save region store: {}", e))
+}
+
+/// Drop history entries and flag saved regions on disconnected monitors,
+/// and clear the flag for monitors that were connected again
+///
+/// Returns whether anything changed.
+fn invalidate_regions(
+    history: &mut Vec<CapturedRegion>,
+    saved: &mut [SavedRegion],
+    removed_monitors: &[u32],
+    added_monitors: &[u32],
+) -> bool {
+    let history_len = history.len();
+    history.retain(|region| !removed_monitors.contains(&region.monitor_id));
+
+    let mut changed = history.len() != history_len;
+    for entry in saved.iter_mut() {
+        let monitor_id = entry.region.monitor_id;
+        let disconnected = if removed_monitors.contains(&monitor_id) {
+            true
+        } else if added_monitors.contains(&monitor_id) {
+            false
+        } else {
+            continue;
+        };
+        if entry.monitor_disconnected != disconnected {
+            entry.monitor_disconnected = disconnected;
+            changed = true;
+        }
+    }
+    changed
+}
+
+/// Put a region at the front of the history, dropping an older identical entry
+fn push_history(history: &mut Vec<CapturedRegion>, region: CapturedRegion) {
+    history.retain(|existing| !existing.same_rect(&region));
+    history.insert(0, region);
+    history.truncate(MAX_HISTORY);
+}
+
+/// Insert or replace a saved region by name
+fn upsert_saved(saved: &mut Vec<SavedRegion>, entry: SavedRegion) {
+    match saved
+        .iter_mut()
+        .find(|existing| existing.name == entry.name)
+    {
+        Some(existing) => *existing = entry,
+        None => saved.push(entry),
+    }
+}
+
+/// Record a region selection in the history
+pub fn record_region(app_handle: &AppHandle, region: CapturedRegion) -> AppResult<()> {
+    let mut history = load_list(app_handle, HISTORY_KEY)?;
+    push_history(&mut history, region);
+    save_list(app_handle, HISTORY_KEY, &history)
+}
+
+/// Recent regions, newest first
+pub fn region_history(app_handle: &AppHandle) -> AppResult<Vec<CapturedRegion>> {
+    load_list(app_handle, HISTORY_KEY)
+}
+
+/// The most recently captured region
+pub fn last_region(app_handle: &AppHandle) -> AppResult<CapturedRegion> {
+    region_history(app_handle)?
+        .into_iter()
+        .next()
+        .ok_or_else(|| "No region has been captured yet".to_string())
+}
+
+/// Saved regions in the order they were created
+pub fn saved_regions(app_handle: &AppHandle) -> AppResult<Vec<SavedRegion>> {
+    load_list(app_handle, SAVED_KEY)
+}
+
+/// Save a region under a name, replacing any region with the same name
+pub fn save_region(app_handle: &AppHandle, name: &str, region: CapturedRegion) -> AppResult<()> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Saved region name must not be empty".to_string());
+    }
+
+    let mut saved = saved_regions(app_handle)?;
+    upsert_saved(
+        &mut saved,
+        SavedRegion {
+            name: name.to_string(),
+            region,
+            monitor_disconnected: false,
+        },
+    );
+    save_list(app_handle, SAVED_KEY, &saved)
+}
+
+/// Delete a saved region by name
+pub fn delete_saved_region(app_handle: &AppHandle, name: &str) -> AppResult<()> {
+    let mut saved = saved_regions(app_handle)?;
+    let before = saved.len();
+    saved.retain(|entry| entry.name != name);
+    if saved.len() == before {
+        return Err(format!("No saved region named {}", name));
+    }
+    save_list(app_handle, SAVED_KEY, &saved)
+}
+
+/// Look up a saved region by name
+pub fn find_saved_region(app_handle: &AppHandle, name: &str) -> AppResult<CapturedRegion> {
+    let entry = saved_regions(app_handle)?
+        .into_iter()
+        .find(|entry| entry.name == name)
+        .ok_or_else(|| format!("No saved region named {}", name))?;
+
+    if entry.monitor_disconnected {
+        return Err(format!(
+            "The monitor of saved region {} is disconnected; reconnect it, or select and save the region again",
+            name
+        ));
+    }
+    Ok(entry.region)
+}
+
+/// Invalidate remembered regions after monitors are disconnected, and
+/// restore saved regions whose monitor was connected again
+pub fn invalidate_monitor_regions(
+    app_handle: &AppHandle,
+    removed_monitors: &[u32],
+    added_monitors: &[u32],
+) -> AppResult<()> {
+    let mut history = region_history(app_handle)?;
+    let mut saved = saved_regions(app_handle)?;
+    if !invalidate_regions(&mut history, &mut saved, removed_monitors, added_monitors) {
+        return Ok(());
+    }
+
+    save_list(app_handle, HISTORY_KEY, &history)?;
+    save_list(app_handle, SAVED_KEY, &saved)
+}
+
+/// Capture a remembered region again without any interaction
+pub fn capture_remembered_region(
+    region: &CapturedRegion,
+    save_dir: &str,
+    cursor: CursorOverlay,
+    sensitive: &SensitiveWindows,
+) -> AppResult<String> {
+    let monitor = find_monitor(region.monitor_id)?;
+    let scale_factor = monitor
+        .scale_factor()
+        .map_err(|e| format!("Failed to get monitor scale factor: {}", e))?;
+    let origin_x = monitor
+        .x()
+        .map_err(|e| format!("Failed to get monitor x: {}", e))?;
+    let origin_y = monitor
+        .y()
+        .map_err(|e| format!("Failed to get monitor y: {}", e))?;
+    let mut frame = monitor
+        .capture_image()
+        .map_err(|e| format!("Failed to capture monitor: {}", e))?;
+
+    sensitive.apply(&mut frame, origin_x, origin_y, pixel_ratio(scale_factor));
+    if let Some(cursor_image) = cursor.query() {
+        composite_cursor(
+            &mut frame,
+            &cursor_image,
+            origin_x,
+            origin_y,
+            pixel_ratio(scale_factor),
+            cursor,
+        );
+    }
+
+    crop_frame(&frame, region.crop_region(scale_factor), save_dir).map(|(path, _)| path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn region(monitor_id: u32, x: u32, captured_at: u64) -> CapturedRegion {
+        CapturedRegion {
+            monitor_id,
+            x,
+            y: 20,
+            width: 300,
+            height: 200,
+            scale_factor: 2.0,
+            captured_at,
+        }
+    }
+
+    #[test]
+    fn test_push_history_moves_repeat_to_front() {
+        let mut history = Vec::new();
+        push_history(&mut history, region(1, 10, 1));
+        push_history(&mut history, region(1, 50, 2));
+        push_history(&mut history, region(1, 10, 3));
+
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0], region(1, 10, 3));
+        assert_eq!(history[1].x, 50);
+    }
+
+    #[test]
+    fn test_push_history_is_bounded() {
+        let mut history = Vec::new();
+        for i in 0..(MAX_HISTORY as u32 + 5) {
+            push_history(&mut history, region(1, i, i as u64));
+        }
+
+        assert_eq!(history.len(), MAX_HISTORY);
+        assert_eq!(history[0].x, MAX_HISTORY as u32 + 4);
+    }
+
+    #[test]
+    fn test_upsert_saved_replaces_by_name() {
+        let mut saved = Vec::new();
+        upsert_saved(
+            &mut saved,
+            SavedRegion {
+                name: "Storybook viewport".to_string(),
+                region: region(1, 10, 1),
+                monitor_disconnected: false,
+            },
+        );
+        upsert_saved(
+            &mut saved,
+            SavedRegion {
+                name: "Storybook viewport".to_string(),
+                region: region(2, 30, 2),
+                monitor_disconnected: false,
+            },
+        );
+
+        assert_eq!(saved.len(), 1);
+        assert_eq!(saved[0].region.monitor_id, 2);
+    }
+
+    #[test]
+    fn test_invalidate_regions_on_removed_monitor() {
+        let mut history = vec![region(1, 10, 1), region(2, 20, 2)];
+        let mut saved = vec![SavedRegion {
+            name: "External".to_string(),
+            region: region(2, 30, 3),
+            monitor_disconnected: false,
+        }];
+
+        assert!(invalidate_regions(&mut history, &mut saved, &[2], &[]));
+        assert_eq!(history, vec![region(1, 10, 1)]);
+        assert!(saved[0].monitor_disconnected);
+
+        assert!(!invalidate_regions(&mut history, &mut saved, &[2], &[]));
+    }
+
+    #[test]
+    fn test_saved_region_is_restored_when_monitor_returns() {
+        let mut history = Vec::new();
+        let mut saved = vec![
+            SavedRegion {
+                name: "Dock monitor".to_string(),
+                region: region(2, 30, 3),
+                monitor_disconnected: false,
+            },
+            SavedRegion {
+                name: "Projector".to_string(),
+                region: region(3, 40, 4),
+                monitor_disconnected: true,
+            },
+        ];
+
+        assert!(invalidate_regions(&mut history, &mut saved, &[2], &[]));
+        assert!(saved[0].monitor_disconnected);
+
+        assert!(invalidate_regions(&mut history, &mut saved, &[], &[2]));
+        assert!(!saved[0].monitor_disconnected);
+        // Other disconnected monitors stay flagged
+        assert!(saved[1].monitor_disconnected);
+
+        assert!(!invalidate_regions(&mut history, &mut saved, &[], &[2]));
+    }
+
+    #[test]
+    fn test_crop_region_rescales_when_scale_changes() {
+        let recorded = region(1, 100, 1);
+
+        let same = recorded.crop_region(2.0);
+        assert_eq!((same.x, same.width), (100, 300));
+
+        let halved = recorded.crop_region(1.0);
+        assert_eq!(
+            (halved.x, halved.y, halved.width, halved.height),
+            (50, 10, 150, 100)
+        );
+    }
+}
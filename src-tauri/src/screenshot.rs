@@ -1,10 +1,18 @@
 //! Screenshot capture module
 
+use image::RgbaImage;
 use serde::Serialize;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Barrier;
+use std::thread;
 use xcap::Monitor;
 
-use crate::utils::{ensure_dir, generate_filename_with_id, AppResult};
+#[cfg(target_os = "linux")]
+use crate::backend::{capture_backend, CaptureBackend};
+use crate::cursor::{composite_cursor, pixel_ratio, CursorOverlay};
+use crate::frames::{insert_frame, StoredFrame};
+use crate::privacy::SensitiveWindows;
+use crate::utils::{ensure_dir, generate_filename_with_id, get_timestamp, AppResult};
 
 /// Represents a captured monitor screenshot with geometry info
 #[derive(Serialize, Clone, Debug)]
@@ -16,94 +24,453 @@ pub struct MonitorShot {
     pub height: u32,
     pub scale_factor: f32,
     pub path: String,
+    /// Unix timestamp (ms) at which this monitor was grabbed
+    pub captured_at: u64,
+}
+
+/// Represents a monitor captured into the in-memory frame store
+#[derive(Serialize, Clone, Debug)]
+pub struct MonitorFrame {
+    pub id: u32,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub scale_factor: f32,
+    pub frame_id: String,
+    /// Unix timestamp (ms) at which this monitor was grabbed
+    pub captured_at: u64,
 }
 
 /// Capture screenshots of all available monitors
-pub fn capture_all_monitors(save_dir: &str) -> AppResult<Vec<MonitorShot>> {
+///
+/// Monitors are grabbed concurrently and PNG encoding happens afterwards
+/// on worker threads, so the slow encode never delays the next grab.
+///
+/// Sensitive windows are masked and the cursor composited before anything
+/// is written to disk.
+pub fn capture_all_monitors(
+    save_dir: &str,
+    cursor: CursorOverlay,
+    sensitive: &SensitiveWindows,
+) -> AppResult<Vec<MonitorShot>> {
+    let save_path = PathBuf::from(save_dir);
+    ensure_dir(&save_path)?;
+
+    let mut captures = capture_monitors_concurrently()?;
+    mask_sensitive_windows(&mut captures, sensitive);
+    overlay_cursor(&mut captures, cursor);
+
+    thread::scope(|scope| {
+        let handles: Vec<_> = captures
+            .into_iter()
+            .map(|capture| scope.spawn(|| save_captured_monitor(capture, &save_path)))
+            .collect();
+
+        handles
+            .into_iter()
+            .map(|handle| {
+                handle
+                    .join()
+                    .map_err(|_| "Screenshot encoder thread panicked".to_string())?
+            })
+            .collect()
+    })
+}
+
+/// Capture all monitors into the frame store without touching disk
+pub fn capture_monitor_frames(
+    cursor: CursorOverlay,
+    sensitive: &SensitiveWindows,
+) -> AppResult<Vec<MonitorFrame>> {
+    let mut captures = capture_monitors_concurrently()?;
+    mask_sensitive_windows(&mut captures, sensitive);
+    overlay_cursor(&mut captures, cursor);
+
+    captures
+        .into_iter()
+        .map(|capture| {
+            let geometry = capture.geometry;
+            Ok(MonitorFrame {
+                id: geometry.id,
+                x: geometry.x,
+                y: geometry.y,
+                width: geometry.width,
+                height: geometry.height,
+                scale_factor: geometry.scale_factor,
+                frame_id: insert_frame(StoredFrame {
+                    monitor_id: geometry.id,
+                    scale_factor: geometry.scale_factor,
+                    image: capture.image,
+                })?,
+                captured_at: capture.captured_at,
+            })
+        })
+        .collect()
+}
+
+/// Find a monitor by its xcap id
+pub fn find_monitor(monitor_id: u32) -> AppResult<Monitor> {
+    let monitors = Monitor::all().map_err(|e| format!("Failed to get monitors: {}", e))?;
+
+    monitors
+        .into_iter()
+        .find(|monitor| monitor.id().ok() == Some(monitor_id))
+        .ok_or_else(|| format!("Monitor {} not found", monitor_id))
+}
+
+/// Geometry of a monitor as reported by xcap
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+pub struct MonitorGeometry {
+    pub id: u32,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub scale_factor: f32,
+}
+
+impl MonitorGeometry {
+    fn of(monitor: &Monitor) -> AppResult<Self> {
+        let id = monitor
+            .id()
+            .map_err(|e| format!("Failed to get monitor id: {}", e))?;
+        let x = monitor
+            .x()
+            .map_err(|e| format!("Failed to get monitor x: {}", e))?;
+        let y = monitor
+            .y()
+            .map_err(|e| format!("Failed to get monitor y: {}", e))?;
+        let width = monitor
+            .width()
+            .map_err(|e| format!("Failed to get monitor width: {}", e))?;
+        let height = monitor
+            .height()
+            .map_err(|e| format!("Failed to get monitor height: {}", e))?;
+        let scale_factor = monitor
+            .scale_factor()
+            .map_err(|e| format!("Failed to get monitor scale factor: {}", e))?;
+
+        Ok(Self {
+            id,
+            x,
+            y,
+            width,
+            height,
+            scale_factor,
+        })
+    }
+}
+
+/// Geometry of every connected monitor
+pub fn monitor_layout() -> AppResult<Vec<MonitorGeometry>> {
+    let monitors = Monitor::all().map_err(|e| format!("Failed to get monitors: {}", e))?;
+    monitors.iter().map(MonitorGeometry::of).collect()
+}
+
+/// Raw pixels of one monitor together with when they were grabbed
+struct CapturedMonitor {
+    geometry: MonitorGeometry,
+    image: RgbaImage,
+    captured_at: u64,
+}
+
+/// Grab every monitor at the same moment, one thread per monitor
+///
+/// xcap monitor handles are not `Send` on every platform, so each worker
+/// re-resolves its monitor from the geometry and then waits on a barrier
+/// so that all grabs start together.
+fn capture_monitors_concurrently() -> AppResult<Vec<CapturedMonitor>> {
+    #[cfg(target_os = "linux")]
+    if capture_backend() == CaptureBackend::WaylandPortal {
+        return capture_desktop_with_portal().map(|capture| vec![capture]);
+    }
+
     let monitors = Monitor::all().map_err(|e| format!("Failed to get monitors: {}", e))?;
 
     if monitors.is_empty() {
         return Err("No monitors available".into());
     }
 
-    let save_path = PathBuf::from(save_dir);
-    ensure_dir(&save_path)?;
+    let geometries = monitors
+        .iter()
+        .map(MonitorGeometry::of)
+        .collect::<AppResult<Vec<_>>>()?;
+
+    let barrier = Barrier::new(geometries.len());
+
+    thread::scope(|scope| {
+        let handles: Vec<_> = geometries
+            .iter()
+            .map(|&geometry| {
+                let barrier = &barrier;
+                scope.spawn(move || {
+                    // Always reach the barrier, even on failure, so no worker is left waiting
+                    let monitor = resolve_monitor(&geometry);
+                    barrier.wait();
 
-    let mut shots = Vec::with_capacity(monitors.len());
+                    let monitor = monitor?;
+                    let captured_at = get_timestamp()?;
+                    let image = monitor
+                        .capture_image()
+                        .map_err(|e| format!("Failed to capture monitor {}: {}", geometry.id, e))?;
 
-    for monitor in monitors {
-        let shot = capture_single_monitor(&monitor, &save_path)?;
-        shots.push(shot);
+                    Ok(CapturedMonitor {
+                        geometry,
+                        image,
+                        captured_at,
+                    })
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .map(|handle| {
+                handle
+                    .join()
+                    .map_err(|_| "Monitor capture thread panicked".to_string())?
+            })
+            .collect()
+    })
+}
+
+/// Look up the monitor with this geometry again
+///
+/// Mirrored displays share an origin, so the monitor is matched by id and
+/// only by the point at its origin if that id is gone.
+fn resolve_monitor(geometry: &MonitorGeometry) -> AppResult<Monitor> {
+    let mut monitors = Monitor::all().map_err(|e| format!("Failed to get monitors: {}", e))?;
+    let layout = monitors
+        .iter()
+        .map(MonitorGeometry::of)
+        .collect::<AppResult<Vec<_>>>()?;
+    let index = match_monitor(&layout, geometry)
+        .ok_or_else(|| format!("Monitor {} not found", geometry.id))?;
+    Ok(monitors.swap_remove(index))
+}
+
+/// Index in `layout` of the monitor with `geometry`'s id, or else of the
+/// one containing its origin
+fn match_monitor(layout: &[MonitorGeometry], geometry: &MonitorGeometry) -> Option<usize> {
+    layout
+        .iter()
+        .position(|monitor| monitor.id == geometry.id)
+        .or_else(|| {
+            layout.iter().position(|monitor| {
+                (monitor.x..monitor.x + monitor.width as i32).contains(&geometry.x)
+                    && (monitor.y..monitor.y + monitor.height as i32).contains(&geometry.y)
+            })
+        })
+}
+
+/// Grab the whole desktop through the portal as a single capture
+///
+/// The portal's image covers every monitor, so it is treated as one
+/// monitor spanning the layout.
+#[cfg(target_os = "linux")]
+fn capture_desktop_with_portal() -> AppResult<CapturedMonitor> {
+    let captured_at = get_timestamp()?;
+    let image = crate::portal::grab_desktop()?;
+    let layout = monitor_layout().unwrap_or_default();
+
+    Ok(CapturedMonitor {
+        geometry: portal_desktop_geometry(&layout, image.width(), image.height()),
+        image,
+        captured_at,
+    })
+}
+
+/// Geometry of a portal screenshot of the whole desktop
+///
+/// The portal returns physical pixels without saying how they are scaled,
+/// so the scale comes from the logical width of the monitor layout. If the
+/// layout can't be read, the image is taken as unscaled at the origin.
+#[cfg(target_os = "linux")]
+fn portal_desktop_geometry(
+    layout: &[MonitorGeometry],
+    image_width: u32,
+    image_height: u32,
+) -> MonitorGeometry {
+    let bounds = layout
+        .iter()
+        .map(|monitor| {
+            (
+                monitor.x,
+                monitor.y,
+                monitor.x + monitor.width as i32,
+                monitor.y + monitor.height as i32,
+            )
+        })
+        .reduce(|a, b| (a.0.min(b.0), a.1.min(b.1), a.2.max(b.2), a.3.max(b.3)));
+    let (x, y, scale_factor) = match bounds {
+        Some((left, top, right, _)) if right > left => {
+            (left, top, image_width as f32 / (right - left) as f32)
+        }
+        _ => (0, 0, 1.0),
+    };
+
+    MonitorGeometry {
+        id: 0,
+        x,
+        y,
+        width: (image_width as f32 / scale_factor).round() as u32,
+        height: (image_height as f32 / scale_factor).round() as u32,
+        scale_factor,
     }
+}
 
-    Ok(shots)
+/// Mask deny-listed windows on every captured monitor
+fn mask_sensitive_windows(captures: &mut [CapturedMonitor], sensitive: &SensitiveWindows) {
+    for capture in captures {
+        let geometry = capture.geometry;
+        sensitive.apply(
+            &mut capture.image,
+            geometry.x,
+            geometry.y,
+            pixel_ratio(geometry.scale_factor),
+        );
+    }
 }
 
-/// Capture a single monitor screenshot
-fn capture_single_monitor(monitor: &Monitor, save_path: &PathBuf) -> AppResult<MonitorShot> {
-    let monitor_id = monitor
-        .id()
-        .map_err(|e| format!("Failed to get monitor id: {}", e))?;
+/// Draw the cursor onto whichever captured monitors it overlaps
+fn overlay_cursor(captures: &mut [CapturedMonitor], overlay: CursorOverlay) {
+    let Some(cursor) = overlay.query() else {
+        return;
+    };
 
-    // Capture the screenshot
-    let image = monitor
-        .capture_image()
-        .map_err(|e| format!("Failed to capture monitor {}: {}", monitor_id, e))?;
+    for capture in captures {
+        let geometry = capture.geometry;
+        composite_cursor(
+            &mut capture.image,
+            &cursor,
+            geometry.x,
+            geometry.y,
+            pixel_ratio(geometry.scale_factor),
+            overlay,
+        );
+    }
+}
+
+/// Encode a captured monitor to PNG in the given directory
+fn save_captured_monitor(capture: CapturedMonitor, save_path: &Path) -> AppResult<MonitorShot> {
+    let geometry = capture.geometry;
 
     // Generate unique filename
-    let filename = generate_filename_with_id("monitor", monitor_id, "png")?;
+    let filename = generate_filename_with_id("monitor", geometry.id, "png")?;
     let screenshot_path = save_path.join(&filename);
 
     // Save the image
-    image
+    capture
+        .image
         .save(&screenshot_path)
         .map_err(|e| format!("Failed to save screenshot: {}", e))?;
 
-    // Get monitor geometry
-    let x = monitor
-        .x()
-        .map_err(|e| format!("Failed to get monitor x: {}", e))?;
-    let y = monitor
-        .y()
-        .map_err(|e| format!("Failed to get monitor y: {}", e))?;
-    let width = monitor
-        .width()
-        .map_err(|e| format!("Failed to get monitor width: {}", e))?;
-    let height = monitor
-        .height()
-        .map_err(|e| format!("Failed to get monitor height: {}", e))?;
-    let scale_factor = monitor
-        .scale_factor()
-        .map_err(|e| format!("Failed to get monitor scale factor: {}", e))?;
-
     Ok(MonitorShot {
-        id: monitor_id,
-        x,
-        y,
-        width,
-        height,
-        scale_factor,
+        id: geometry.id,
+        x: geometry.x,
+        y: geometry.y,
+        width: geometry.width,
+        height: geometry.height,
+        scale_factor: geometry.scale_factor,
         path: screenshot_path.to_string_lossy().into_owned(),
+        captured_at: capture.captured_at,
     })
 }
 
-/// Capture primary monitor using the screenshots plugin
-pub async fn capture_primary_monitor(app_handle: tauri::AppHandle) -> AppResult<PathBuf> {
-    use tauri_plugin_screenshots::{get_monitor_screenshot, get_screenshotable_monitors};
+/// Capture the primary monitor with sensitive windows already masked
+///
+/// Masking happens in memory, so the capture only reaches disk masked.
+pub fn capture_primary_monitor(
+    sensitive: &SensitiveWindows,
+    cursor: CursorOverlay,
+) -> AppResult<RgbaImage> {
+    let mut captures = [capture_primary()?];
+    mask_sensitive_windows(&mut captures, sensitive);
+    overlay_cursor(&mut captures, cursor);
+    let [capture] = captures;
+    Ok(capture.image)
+}
 
-    let monitors = get_screenshotable_monitors()
-        .await
-        .map_err(|e| format!("Failed to get monitors: {}", e))?;
+/// Grab the primary monitor, or the first one if none is marked primary
+fn capture_primary() -> AppResult<CapturedMonitor> {
+    #[cfg(target_os = "linux")]
+    if capture_backend() == CaptureBackend::WaylandPortal {
+        return capture_desktop_with_portal();
+    }
 
-    if monitors.is_empty() {
-        return Err("No monitors available".into());
+    let monitors = Monitor::all().map_err(|e| format!("Failed to get monitors: {}", e))?;
+    let primary = monitors
+        .iter()
+        .find(|monitor| monitor.is_primary().unwrap_or(false))
+        .or_else(|| monitors.first())
+        .ok_or("No monitors available")?;
+
+    let geometry = MonitorGeometry::of(primary)?;
+    let captured_at = get_timestamp()?;
+    let image = primary
+        .capture_image()
+        .map_err(|e| format!("Failed to capture monitor {}: {}", geometry.id, e))?;
+
+    Ok(CapturedMonitor {
+        geometry,
+        image,
+        captured_at,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn monitor(id: u32, x: i32, width: u32) -> MonitorGeometry {
+        MonitorGeometry {
+            id,
+            x,
+            y: 0,
+            width,
+            height: 900,
+            scale_factor: 1.0,
+        }
+    }
+
+    #[test]
+    fn test_mirrored_monitors_are_matched_by_id() {
+        let layout = [
+            monitor(1, 0, 1440),
+            monitor(2, 0, 1440),
+            monitor(3, 1440, 1440),
+        ];
+
+        assert_eq!(match_monitor(&layout, &monitor(2, 0, 1440)), Some(1));
+        assert_eq!(match_monitor(&layout, &monitor(3, 1440, 1440)), Some(2));
     }
 
-    let primary_monitor = monitors.first().ok_or("No monitor found")?;
+    #[test]
+    fn test_monitor_falls_back_to_its_origin_when_the_id_is_gone() {
+        let layout = [monitor(1, 0, 1440), monitor(3, 1440, 1440)];
 
-    let screenshot_path = get_monitor_screenshot(app_handle, primary_monitor.id)
-        .await
-        .map_err(|e| format!("Failed to capture screenshot: {}", e))?;
+        assert_eq!(match_monitor(&layout, &monitor(7, 1440, 1440)), Some(1));
+        assert_eq!(match_monitor(&layout, &monitor(7, 4000, 1440)), None);
+    }
 
-    Ok(screenshot_path)
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_portal_scale_comes_from_the_layout_width() {
+        let layout = [monitor(1, 0, 1440), monitor(2, 1440, 1440)];
+        let geometry = portal_desktop_geometry(&layout, 5760, 1800);
+
+        assert_eq!(geometry.scale_factor, 2.0);
+        assert_eq!((geometry.width, geometry.height), (2880, 900));
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_portal_capture_is_unscaled_without_a_layout() {
+        let geometry = portal_desktop_geometry(&[], 1920, 1080);
+
+        assert_eq!(geometry.scale_factor, 1.0);
+        assert_eq!((geometry.x, geometry.y), (0, 0));
+        assert_eq!((geometry.width, geometry.height), (1920, 1080));
+    }
 }
@@ -0,0 +1,551 @@
+//! Scrolling capture module
+//!
+//! Captures a fixed rectangle repeatedly while its content scrolls, then
+//! stitches the frames into one tall image. Consecutive frames are aligned
+//! by matching row hashes, and rows that stay put between frames (sticky
+//! headers and footers) are kept only once.
+
+use image::{DynamicImage, RgbaImage};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+use crate::image::save_image;
+use crate::screenshot::find_monitor;
+use crate::utils::AppResult;
+
+/// Event emitted after every frame grabbed during a scrolling capture
+pub const SCROLL_CAPTURE_PROGRESS_EVENT: &str = "scroll-capture-progress";
+
+/// Minimum number of rows two frames must share to be aligned
+const MIN_OVERLAP_ROWS: usize = 16;
+
+/// Fraction of informative rows that must match for an offset to be accepted
+const MIN_MATCH_RATIO: f32 = 0.9;
+
+static ACTIVE_SESSION: Mutex<Option<Arc<AtomicBool>>> = Mutex::new(None);
+
+/// How the content is scrolled between frames
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScrollMode {
+    /// Send synthetic scroll-wheel input after each frame
+    Synthetic,
+    /// Keep capturing while the user scrolls until stopped
+    Manual,
+}
+
+fn default_scroll_lines() -> u32 {
+    5
+}
+
+fn default_interval_ms() -> u64 {
+    250
+}
+
+fn default_max_frames() -> usize {
+    60
+}
+
+/// Options for a scrolling capture
+///
+/// The rectangle is in physical pixels relative to the monitor, matching
+/// the coordinates the region selector sends to `capture_region`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ScrollCaptureOptions {
+    pub monitor_id: u32,
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    pub mode: ScrollMode,
+    /// Wheel lines scrolled per step in synthetic mode
+    #[serde(default = "default_scroll_lines")]
+    pub scroll_lines: u32,
+    /// Delay between frames, also used to let content settle after scrolling
+    #[serde(default = "default_interval_ms")]
+    pub interval_ms: u64,
+    #[serde(default = "default_max_frames")]
+    pub max_frames: usize,
+}
+
+/// Payload of the `scroll-capture-progress` event
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ScrollCaptureProgress {
+    pub frame_count: usize,
+}
+
+/// Per-row fingerprints of a frame
+struct FrameRows {
+    hashes: Vec<u64>,
+    /// Rows made of a single colour carry no alignment information
+    uniform: Vec<bool>,
+}
+
+impl FrameRows {
+    fn of(frame: &RgbaImage) -> Self {
+        let row_len = frame.width() as usize * 4;
+        let (hashes, uniform) = frame
+            .as_raw()
+            .chunks_exact(row_len)
+            .map(|row| {
+                let mut hasher = DefaultHasher::new();
+                row.hash(&mut hasher);
+                let uniform = row.chunks_exact(4).all(|px| px == &row[0..4]);
+                (hasher.finish(), uniform)
+            })
+            .unzip();
+
+        Self { hashes, uniform }
+    }
+
+    fn len(&self) -> usize {
+        self.hashes.len()
+    }
+
+    /// Number of leading rows identical in both frames
+    fn common_prefix(&self, other: &FrameRows) -> usize {
+        self.hashes
+            .iter()
+            .zip(&other.hashes)
+            .take_while(|(a, b)| a == b)
+            .count()
+    }
+
+    /// Number of trailing rows identical in both frames
+    fn common_suffix(&self, other: &FrameRows) -> usize {
+        self.hashes
+            .iter()
+            .rev()
+            .zip(other.hashes.iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count()
+    }
+
+    /// Leading rows identical in both frames, up to the last one that isn't
+    /// a single colour; plain rows like page background aren't a header
+    fn sticky_prefix(&self, other: &FrameRows) -> usize {
+        (0..self.common_prefix(other))
+            .rev()
+            .find(|&row| !self.uniform[row])
+            .map_or(0, |row| row + 1)
+    }
+
+    /// Trailing rows identical in both frames, from the first one that isn't
+    /// a single colour
+    fn sticky_suffix(&self, other: &FrameRows) -> usize {
+        let len = self.len();
+        (len - self.common_suffix(other)..len)
+            .find(|&row| !self.uniform[row])
+            .map_or(0, |row| len - row)
+    }
+}
+
+/// Find how many rows the content moved up between `prev` and `next`
+///
+/// Only the band between the sticky header (`top` rows) and footer
+/// (`bottom` rows) is compared.
+fn find_scroll_offset(
+    prev: &FrameRows,
+    next: &FrameRows,
+    top: usize,
+    bottom: usize,
+) -> Option<usize> {
+    let band = prev.len().checked_sub(top + bottom)?;
+    let mut best: Option<(f32, usize, usize)> = None;
+
+    for offset in 1..band.saturating_sub(MIN_OVERLAP_ROWS) {
+        let overlap = band - offset;
+        let mut informative = 0;
+        let mut matched = 0;
+
+        for i in 0..overlap {
+            let prev_row = top + offset + i;
+            if prev.uniform[prev_row] {
+                continue;
+            }
+            informative += 1;
+            if prev.hashes[prev_row] == next.hashes[top + i] {
+                matched += 1;
+            }
+        }
+
+        if informative == 0 {
+            continue;
+        }
+
+        let ratio = matched as f32 / informative as f32;
+        if ratio < MIN_MATCH_RATIO {
+            continue;
+        }
+
+        let better = match best {
+            None => true,
+            Some((best_ratio, best_informative, _)) => {
+                ratio > best_ratio || (ratio == best_ratio && informative > best_informative)
+            }
+        };
+        if better {
+            best = Some((ratio, informative, offset));
+        }
+    }
+
+    best.map(|(_, _, offset)| offset)
+}
+
+/// Stitch frames of a scrolling region into one tall image
+pub fn stitch_frames(frames: &[RgbaImage]) -> AppResult<RgbaImage> {
+    let first = frames.first().ok_or("No frames to stitch")?;
+    let (width, height) = first.dimensions();
+
+    if frames
+        .iter()
+        .any(|frame| frame.dimensions() != (width, height))
+    {
+        return Err("All frames must have the same dimensions".to_string());
+    }
+
+    // Frames where nothing moved add no content
+    let mut kept: Vec<(&RgbaImage, FrameRows)> = Vec::with_capacity(frames.len());
+    for frame in frames {
+        let rows = FrameRows::of(frame);
+        let unchanged = kept
+            .last()
+            .is_some_and(|(_, prev)| prev.common_prefix(&rows) == rows.len());
+        if !unchanged {
+            kept.push((frame, rows));
+        }
+    }
+
+    if kept.len() == 1 {
+        return Ok(first.clone());
+    }
+
+    // Sticky regions are the rows that never change between any two frames
+    let rows_total = height as usize;
+    let mut top = rows_total;
+    let mut bottom = rows_total;
+    for pair in kept.windows(2) {
+        top = top.min(pair[0].1.sticky_prefix(&pair[1].1));
+        bottom = bottom.min(pair[0].1.sticky_suffix(&pair[1].1));
+    }
+
+    if top + bottom + MIN_OVERLAP_ROWS >= rows_total {
+        return Err("Scrolling area is too small to stitch".to_string());
+    }
+
+    let mut offsets = Vec::with_capacity(kept.len() - 1);
+    for pair in kept.windows(2) {
+        let offset = find_scroll_offset(&pair[0].1, &pair[1].1, top, bottom)
+            .ok_or("Could not align frames; try scrolling less between captures")?;
+        offsets.push(offset);
+    }
+
+    let row_len = width as usize * 4;
+    let band_end = rows_total - bottom;
+    let output_rows = rows_total + offsets.iter().sum::<usize>();
+    let mut output = Vec::with_capacity(output_rows * row_len);
+
+    // Header and first band come from the first frame
+    output.extend_from_slice(&first.as_raw()[..band_end * row_len]);
+
+    // Each following frame only contributes the rows scrolled into view
+    for ((frame, _), offset) in kept.iter().skip(1).zip(&offsets) {
+        output
+            .extend_from_slice(&frame.as_raw()[(band_end - offset) * row_len..band_end * row_len]);
+    }
+
+    // Footer comes from the last frame
+    let last = kept.last().map(|(frame, _)| *frame).unwrap_or(first);
+    output.extend_from_slice(&last.as_raw()[band_end * row_len..]);
+
+    RgbaImage::from_raw(width, output_rows as u32, output)
+        .ok_or_else(|| "Failed to assemble stitched image".to_string())
+}
+
+/// Scroll the content under the cursor down by a number of wheel lines
+#[cfg(target_os = "macos")]
+fn scroll_down(lines: u32) -> AppResult<()> {
+    use objc2_core_graphics::{CGEvent, CGEventTapLocation, CGScrollEventUnit};
+
+    let event =
+        CGEvent::new_scroll_wheel_event2(None, CGScrollEventUnit::Line, 1, -(lines as i32), 0, 0)
+            .ok_or("Failed to create scroll event")?;
+    CGEvent::post(CGEventTapLocation::HIDEventTap, Some(&event));
+
+    Ok(())
+}
+
+/// Scroll the content under the cursor down by a number of wheel lines
+#[cfg(target_os = "linux")]
+fn scroll_down(lines: u32) -> AppResult<()> {
+    use std::process::Command;
+
+    // Button 5 is wheel-down on X11
+    let status = Command::new("xdotool")
+        .arg("click")
+        .arg("--repeat")
+        .arg(lines.to_string())
+        .arg("5")
+        .status()
+        .map_err(|e| format!("Failed to run xdotool for synthetic scrolling: {}", e))?;
+
+    if !status.success() {
+        return Err("xdotool failed to send scroll input".to_string());
+    }
+
+    Ok(())
+}
+
+/// Whether `xdotool`, which sends synthetic scroll input on Linux, can be
+/// run from `PATH`
+#[cfg(target_os = "linux")]
+pub fn xdotool_available() -> bool {
+    std::process::Command::new("xdotool")
+        .arg("version")
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .map(|status| status.success())
+        .unwrap_or(false)
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+fn scroll_down(_lines: u32) -> AppResult<()> {
+    Err("Synthetic scrolling is not supported on this platform".to_string())
+}
+
+/// Grab frames of the configured rectangle until the content stops moving
+/// or the session is stopped
+fn collect_frames(
+    app_handle: &AppHandle,
+    options: &ScrollCaptureOptions,
+    stop: &AtomicBool,
+) -> AppResult<Vec<RgbaImage>> {
+    let monitor = find_monitor(options.monitor_id)?;
+    let interval = Duration::from_millis(options.interval_ms);
+    let mut frames: Vec<RgbaImage> = Vec::new();
+
+    while frames.len() < options.max_frames && !stop.load(Ordering::SeqCst) {
+        let frame = monitor
+            .capture_region(options.x, options.y, options.width, options.height)
+            .map_err(|e| format!("Failed to capture scrolling region: {}", e))?;
+
+        let unchanged = frames
+            .last()
+            .is_some_and(|last| last.as_raw() == frame.as_raw());
+        if unchanged && options.mode == ScrollMode::Synthetic {
+            // Scrolling no longer moves the content: end of page
+            break;
+        }
+
+        if !unchanged {
+            frames.push(frame);
+            let _ = app_handle.emit(
+                SCROLL_CAPTURE_PROGRESS_EVENT,
+                ScrollCaptureProgress {
+                    frame_count: frames.len(),
+                },
+            );
+        }
+
+        if options.mode == ScrollMode::Synthetic {
+            scroll_down(options.scroll_lines)?;
+        }
+        std::thread::sleep(interval);
+    }
+
+    Ok(frames)
+}
+
+/// Run a scrolling capture and save the stitched result
+///
+/// Blocks until the capture finishes; in manual mode that is when
+/// [`stop_active_scroll_capture`] is called or `max_frames` is reached.
+pub fn run_scroll_capture(
+    app_handle: &AppHandle,
+    options: &ScrollCaptureOptions,
+    save_dir: &str,
+) -> AppResult<String> {
+    if options.width == 0 || options.height == 0 {
+        return Err("Scrolling capture region must not be empty".to_string());
+    }
+
+    let stop = {
+        let mut session = ACTIVE_SESSION
+            .lock()
+            .map_err(|e| format!("Failed to lock scroll session: {}", e))?;
+        if session.is_some() {
+            return Err("A scrolling capture is already in progress".to_string());
+        }
+        let stop = Arc::new(AtomicBool::new(false));
+        *session = Some(stop.clone());
+        stop
+    };
+
+    let frames = collect_frames(app_handle, options, &stop);
+
+    if let Ok(mut session) = ACTIVE_SESSION.lock() {
+        *session = None;
+    }
+
+    let stitched = stitch_frames(&frames?)?;
+    save_image(&DynamicImage::ImageRgba8(stitched), save_dir, "scroll")
+}
+
+/// Ask the running scrolling capture to finish and stitch what it has
+pub fn stop_active_scroll_capture() -> AppResult<()> {
+    let session = ACTIVE_SESSION
+        .lock()
+        .map_err(|e| format!("Failed to lock scroll session: {}", e))?;
+
+    let stop = session
+        .as_ref()
+        .ok_or("No scrolling capture is in progress")?;
+    stop.store(true, Ordering::SeqCst);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{imageops, Rgba};
+
+    /// A tall page where every row is distinct
+    fn page(width: u32, height: u32) -> RgbaImage {
+        RgbaImage::from_fn(width, height, |x, y| {
+            Rgba([
+                (y % 251) as u8,
+                (y / 251) as u8,
+                ((x * 7 + y * 3) % 256) as u8,
+                255,
+            ])
+        })
+    }
+
+    /// Cut a viewport out of `page` and overlay fixed header/footer bars
+    fn viewport(page: &RgbaImage, scroll: u32, height: u32, header: u32, footer: u32) -> RgbaImage {
+        let mut frame = imageops::crop_imm(page, 0, scroll, page.width(), height).to_image();
+        for y in 0..header {
+            for x in 0..frame.width() {
+                frame.put_pixel(x, y, Rgba([200, (x % 256) as u8, 10, 255]));
+            }
+        }
+        for y in height - footer..height {
+            for x in 0..frame.width() {
+                frame.put_pixel(x, y, Rgba([10, (x % 256) as u8, 200, 255]));
+            }
+        }
+        frame
+    }
+
+    #[test]
+    fn test_stitch_single_frame_is_unchanged() {
+        let frame = page(8, 40);
+        let stitched = stitch_frames(std::slice::from_ref(&frame)).unwrap();
+        assert_eq!(stitched, frame);
+    }
+
+    #[test]
+    fn test_stitch_reconstructs_page() {
+        let page = page(16, 400);
+        let frames: Vec<_> = [0, 37, 80, 150]
+            .iter()
+            .map(|&scroll| viewport(&page, scroll, 120, 0, 0))
+            .collect();
+
+        let stitched = stitch_frames(&frames).unwrap();
+
+        let expected = imageops::crop_imm(&page, 0, 0, 16, 270).to_image();
+        assert_eq!(stitched.dimensions(), expected.dimensions());
+        assert_eq!(stitched, expected);
+    }
+
+    #[test]
+    fn test_stitch_keeps_sticky_header_and_footer_once() {
+        let page = page(16, 400);
+        let frames: Vec<_> = [0, 40, 90]
+            .iter()
+            .map(|&scroll| viewport(&page, scroll, 120, 12, 8))
+            .collect();
+
+        let stitched = stitch_frames(&frames).unwrap();
+
+        // 120 rows of the first frame plus 40 and 50 newly scrolled rows
+        assert_eq!(stitched.height(), 210);
+        assert_eq!(*stitched.get_pixel(0, 0), Rgba([200, 0, 10, 255]));
+        assert_eq!(*stitched.get_pixel(0, 209), Rgba([10, 0, 200, 255]));
+
+        // Body rows continue the page without repeating the header
+        assert_eq!(stitched.get_pixel(3, 12), page.get_pixel(3, 12));
+        assert_eq!(stitched.get_pixel(3, 201), page.get_pixel(3, 90 + 111));
+    }
+
+    #[test]
+    fn test_plain_background_is_not_a_sticky_header() {
+        // A blank top margin that is still blank after scrolling
+        let mut page = page(16, 300);
+        for y in 0..90 {
+            for x in 0..16 {
+                page.put_pixel(x, y, Rgba([255, 255, 255, 255]));
+            }
+        }
+        let frames = vec![
+            viewport(&page, 0, 100, 0, 0),
+            viewport(&page, 20, 100, 0, 0),
+        ];
+
+        let stitched = stitch_frames(&frames).unwrap();
+        assert_eq!(
+            stitched,
+            imageops::crop_imm(&page, 0, 0, 16, 120).to_image()
+        );
+    }
+
+    #[test]
+    fn test_stitch_skips_unchanged_frames() {
+        let page = page(16, 300);
+        let frames = vec![
+            viewport(&page, 0, 100, 0, 0),
+            viewport(&page, 0, 100, 0, 0),
+            viewport(&page, 30, 100, 0, 0),
+        ];
+
+        let stitched = stitch_frames(&frames).unwrap();
+        assert_eq!(stitched.height(), 130);
+    }
+
+    #[test]
+    fn test_stitch_rejects_mismatched_sizes() {
+        let frames = vec![RgbaImage::new(10, 50), RgbaImage::new(12, 50)];
+        assert!(stitch_frames(&frames).is_err());
+    }
+
+    #[test]
+    fn test_stitch_fails_without_overlap() {
+        let page = page(16, 600);
+        let frames = vec![
+            viewport(&page, 0, 100, 0, 0),
+            viewport(&page, 300, 100, 0, 0),
+        ];
+        assert!(stitch_frames(&frames).is_err());
+    }
+
+    #[test]
+    fn test_scroll_options_defaults() {
+        let options: ScrollCaptureOptions = serde_json::from_str(
+            r#"{"monitor_id":1,"x":0,"y":0,"width":100,"height":100,"mode":"manual"}"#,
+        )
+        .unwrap();
+
+        assert_eq!(options.mode, ScrollMode::Manual);
+        assert_eq!(options.scroll_lines, 5);
+        assert_eq!(options.max_frames, 60);
+    }
+}
@@ -0,0 +1,236 @@
+//! Ready handshake with the region selector window
+//!
+//! The selector's payload is staged under its window label. Once the React
+//! side has mounted, the window invokes `region_selector_ready` and receives
+//! the payload as the command's result, so the payload can't be lost to a
+//! listener that isn't attached yet and nothing waits longer than needed.
+//! If the window never reports ready the selector is closed with an error.
+//!
+//! Each handshake belongs to a capture id, so a selector that is replaced
+//! under the same label can't tear down its successor's handshake.
+
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::utils::AppResult;
+
+/// How often a pending handshake is checked
+const POLL_INTERVAL: Duration = Duration::from_millis(25);
+
+/// How long a selector window has to report ready
+pub const READY_TIMEOUT: Duration = Duration::from_secs(10);
+
+static HANDSHAKES: LazyLock<Mutex<Handshakes>> =
+    LazyLock::new(|| Mutex::new(Handshakes::default()));
+
+/// Progress of one selector window's handshake
+#[derive(Debug)]
+enum Slot {
+    /// The window was opened but its payload is still being prepared
+    Preparing,
+    /// The payload is waiting for the window to report ready
+    Staged(serde_json::Value),
+    /// The window received its payload; kept in case it reports ready
+    /// again, e.g. after a reload
+    Delivered(serde_json::Value),
+}
+
+#[derive(Debug)]
+struct Handshake {
+    capture_id: String,
+    slot: Slot,
+}
+
+#[derive(Default)]
+struct Handshakes {
+    pending: HashMap<String, Handshake>,
+}
+
+impl Handshakes {
+    fn expect(&mut self, label: &str, capture_id: &str) {
+        self.pending.insert(
+            label.to_string(),
+            Handshake {
+                capture_id: capture_id.to_string(),
+                slot: Slot::Preparing,
+            },
+        );
+    }
+
+    fn slot_of(&mut self, label: &str, capture_id: &str) -> Option<&mut Slot> {
+        self.pending
+            .get_mut(label)
+            .filter(|handshake| handshake.capture_id == capture_id)
+            .map(|handshake| &mut handshake.slot)
+    }
+
+    fn stage(
+        &mut self,
+        label: &str,
+        capture_id: &str,
+        payload: serde_json::Value,
+    ) -> AppResult<()> {
+        match self.slot_of(label, capture_id) {
+            Some(slot @ Slot::Preparing) => {
+                *slot = Slot::Staged(payload);
+                Ok(())
+            }
+            Some(_) => Err(format!("Payload for {} was already staged", label)),
+            None => Err(format!(
+                "Window {} is no longer waiting for a payload",
+                label
+            )),
+        }
+    }
+
+    /// Hand the payload to a ready window, or `None` while it's being prepared
+    fn take(&mut self, label: &str) -> AppResult<Option<serde_json::Value>> {
+        let slot = self
+            .pending
+            .get_mut(label)
+            .map(|handshake| &mut handshake.slot)
+            .ok_or_else(|| format!("No region selector payload for window {}", label))?;
+        match slot {
+            Slot::Preparing => Ok(None),
+            Slot::Staged(payload) => {
+                let payload = payload.take();
+                *slot = Slot::Delivered(payload.clone());
+                Ok(Some(payload))
+            }
+            Slot::Delivered(payload) => Ok(Some(payload.clone())),
+        }
+    }
+
+    /// Whether the window received its payload, or `None` once it's gone
+    fn is_delivered(&mut self, label: &str, capture_id: &str) -> Option<bool> {
+        self.slot_of(label, capture_id)
+            .map(|slot| matches!(slot, Slot::Delivered(_)))
+    }
+
+    fn forget(&mut self, label: &str, capture_id: &str) {
+        if self.slot_of(label, capture_id).is_some() {
+            self.pending.remove(label);
+        }
+    }
+}
+
+fn handshakes() -> MutexGuard<'static, Handshakes> {
+    HANDSHAKES.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Start a handshake for a window that is about to be created
+pub fn expect_ready(label: &str, capture_id: &str) {
+    handshakes().expect(label, capture_id);
+}
+
+/// Stage the payload delivered when the window reports ready
+pub fn stage_payload(label: &str, capture_id: &str, payload: serde_json::Value) -> AppResult<()> {
+    handshakes().stage(label, capture_id, payload)
+}
+
+/// Drop a capture's handshake, e.g. because its window was destroyed
+pub fn forget(label: &str, capture_id: &str) {
+    handshakes().forget(label, capture_id);
+}
+
+/// Wait until the window received its payload
+///
+/// Fails if the window doesn't report ready within `READY_TIMEOUT` or is
+/// closed first. A delivered payload stays available until the window is
+/// destroyed and its handshake forgotten.
+pub async fn wait_until_delivered(label: &str, capture_id: &str) -> AppResult<()> {
+    let deadline = Instant::now() + READY_TIMEOUT;
+    loop {
+        match handshakes().is_delivered(label, capture_id) {
+            Some(true) => break,
+            Some(false) => {}
+            None => return Err("Region selector was closed before it was ready".to_string()),
+        }
+        if Instant::now() >= deadline {
+            forget(label, capture_id);
+            return Err(format!(
+                "Timed out after {} seconds waiting for the region selector to become ready",
+                READY_TIMEOUT.as_secs()
+            ));
+        }
+        tauri::async_runtime::sleep(POLL_INTERVAL).await;
+    }
+    Ok(())
+}
+
+/// Wait for a window's payload once the window is ready to receive it
+pub async fn take_when_ready(label: &str) -> AppResult<serde_json::Value> {
+    let deadline = Instant::now() + READY_TIMEOUT;
+    loop {
+        if let Some(payload) = handshakes().take(label)? {
+            return Ok(payload);
+        }
+        if Instant::now() >= deadline {
+            return Err("Timed out waiting for the region selector payload".to_string());
+        }
+        tauri::async_runtime::sleep(POLL_INTERVAL).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_payload_is_delivered_when_ready() {
+        let mut handshakes = Handshakes::default();
+        handshakes.expect("region-selector", "abc");
+        assert_eq!(handshakes.take("region-selector"), Ok(None));
+        assert_eq!(
+            handshakes.is_delivered("region-selector", "abc"),
+            Some(false)
+        );
+
+        let payload = serde_json::json!({ "captureId": "abc" });
+        handshakes
+            .stage("region-selector", "abc", payload.clone())
+            .unwrap();
+        assert_eq!(
+            handshakes.take("region-selector"),
+            Ok(Some(payload.clone()))
+        );
+        assert_eq!(
+            handshakes.is_delivered("region-selector", "abc"),
+            Some(true)
+        );
+
+        // Reporting ready again gets the same payload
+        assert_eq!(handshakes.take("region-selector"), Ok(Some(payload)));
+    }
+
+    #[test]
+    fn test_unknown_or_forgotten_window_is_an_error() {
+        let mut handshakes = Handshakes::default();
+        assert!(handshakes.take("region-selector").is_err());
+        assert!(handshakes
+            .stage("region-selector", "abc", serde_json::Value::Null)
+            .is_err());
+
+        handshakes.expect("region-selector", "abc");
+        handshakes.forget("region-selector", "abc");
+        assert_eq!(handshakes.is_delivered("region-selector", "abc"), None);
+    }
+
+    #[test]
+    fn test_replaced_selector_keeps_new_handshake() {
+        let mut handshakes = Handshakes::default();
+        handshakes.expect("region-selector", "old");
+        handshakes.expect("region-selector", "new");
+
+        // The old window's destroy handler runs after the new one opened
+        handshakes.forget("region-selector", "old");
+        assert_eq!(
+            handshakes.is_delivered("region-selector", "new"),
+            Some(false)
+        );
+        assert!(handshakes
+            .stage("region-selector", "old", serde_json::Value::Null)
+            .is_err());
+    }
+}
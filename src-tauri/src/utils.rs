@@ -1,12 +1,26 @@
 //! Utility functions for common operations
 
+use serde::de::DeserializeOwned;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::{SystemTime, UNIX_EPOCH};
 
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
 /// Custom error type for better error handling
 pub type AppResult<T> = Result<T, String>;
 
+/// Store with the settings written by the preferences page
+pub const SETTINGS_STORE: &str = "settings.json";
+
+/// Deserialize a stored setting, using the default if missing or malformed
+pub fn value_or<T: DeserializeOwned>(value: Option<serde_json::Value>, default: T) -> T {
+    value
+        .and_then(|value| serde_json::from_value(value).ok())
+        .unwrap_or(default)
+}
+
 /// Get the user's Desktop directory path (cross-platform)
 pub fn get_desktop_path() -> AppResult<String> {
     let desktop = dirs::desktop_dir().ok_or("Failed to get Desktop directory")?;
@@ -21,6 +35,34 @@ pub fn get_timestamp() -> AppResult<u64> {
         .map(|d| d.as_millis() as u64)
 }
 
+/// A path for a capture that is still being processed, in a temporary
+/// directory only the current user can read
+///
+/// Captures are masked or redacted here before they reach the save
+/// directory, where sync clients and other apps may pick them up.
+pub fn private_capture_path(prefix: &str, extension: &str) -> AppResult<PathBuf> {
+    let dir = std::env::temp_dir().join("bettershot-captures");
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    builder
+        .create(&dir)
+        .map_err(|e| format!("Failed to create capture directory: {}", e))?;
+
+    Ok(dir.join(format!("{}_{}.{}", prefix, generate_id()?, extension)))
+}
+
+/// Move a file, copying it when it can't be renamed across file systems
+pub fn move_file(from: &Path, to: &Path) -> AppResult<()> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    fs::copy(from, to).map_err(|e| format!("Failed to move file: {}", e))?;
+    let _ = fs::remove_file(from);
+    Ok(())
+}
+
 /// Ensure a directory exists, creating it if necessary
 pub fn ensure_dir(path: &PathBuf) -> AppResult<()> {
     fs::create_dir_all(path).map_err(|e| format!("Failed to create directory: {}", e))
@@ -38,6 +80,16 @@ pub fn generate_filename_with_id(prefix: &str, id: u32, extension: &str) -> AppR
     Ok(format!("{}_{}_{}.{}", prefix, id, timestamp, extension))
 }
 
+/// Generate a process-unique id from the current timestamp and a counter
+pub fn generate_id() -> AppResult<String> {
+    let timestamp = get_timestamp()?;
+    Ok(format!(
+        "{}-{}",
+        timestamp,
+        NEXT_ID.fetch_add(1, Ordering::Relaxed)
+    ))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -81,6 +133,47 @@ mod tests {
         assert_ne!(filename1, filename2);
     }
 
+    #[test]
+    fn test_generate_id_uniqueness() {
+        let id1 = generate_id().unwrap();
+        let id2 = generate_id().unwrap();
+
+        // Ids generated in the same millisecond must still differ
+        assert_ne!(id1, id2);
+    }
+
+    #[test]
+    fn test_private_capture_path_is_in_an_owner_only_directory() {
+        let path = private_capture_path("test", "png").unwrap();
+        assert!(path
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with("test_"));
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(path.parent().unwrap())
+                .unwrap()
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o777, 0o700);
+        }
+    }
+
+    #[test]
+    fn test_move_file() {
+        let from = private_capture_path("move", "txt").unwrap();
+        let to = std::env::temp_dir().join(from.file_name().unwrap());
+        fs::write(&from, b"capture").unwrap();
+
+        move_file(&from, &to).unwrap();
+        assert!(!from.exists());
+        assert_eq!(fs::read(&to).unwrap(), b"capture");
+        let _ = fs::remove_file(&to);
+    }
+
     #[test]
     fn test_ensure_dir_creates_nested_directories() {
         let temp_dir = std::env::temp_dir();
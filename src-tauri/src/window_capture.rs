@@ -0,0 +1,368 @@
+//! Window enumeration and transparent window capture
+//!
+//! xcap returns windows as opaque rectangles. To match what the window
+//! actually looks like on screen, everything outside the window's shape
+//! is made transparent (XShape on X11, rounded corners elsewhere) and a
+//! synthetic soft shadow can be added around it.
+
+use image::{imageops, DynamicImage, GrayImage, Luma, Rgba, RgbaImage};
+use imageproc::filter::gaussian_blur_f32;
+use serde::Serialize;
+use xcap::Window;
+
+use crate::image::save_image;
+use crate::utils::AppResult;
+
+/// A window that can be captured
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct WindowInfo {
+    pub id: u32,
+    pub pid: u32,
+    pub app_name: String,
+    pub title: String,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub is_minimized: bool,
+    pub is_focused: bool,
+}
+
+impl WindowInfo {
+    fn of(window: &Window) -> AppResult<Self> {
+        let field_error =
+            |field: &str, e: xcap::XCapError| format!("Failed to get window {}: {}", field, e);
+
+        Ok(Self {
+            id: window.id().map_err(|e| field_error("id", e))?,
+            pid: window.pid().map_err(|e| field_error("pid", e))?,
+            app_name: window.app_name().map_err(|e| field_error("app name", e))?,
+            title: window.title().map_err(|e| field_error("title", e))?,
+            x: window.x().map_err(|e| field_error("x", e))?,
+            y: window.y().map_err(|e| field_error("y", e))?,
+            width: window.width().map_err(|e| field_error("width", e))?,
+            height: window.height().map_err(|e| field_error("height", e))?,
+            is_minimized: window.is_minimized().map_err(|e| field_error("state", e))?,
+            is_focused: window.is_focused().map_err(|e| field_error("focus", e))?,
+        })
+    }
+}
+
+/// List all windows, front-most first
+///
+/// Windows whose properties can't be read (e.g. they closed mid-query)
+/// are skipped.
+pub fn list_windows() -> AppResult<Vec<WindowInfo>> {
+    let windows = Window::all().map_err(|e| format!("Failed to get windows: {}", e))?;
+    Ok(windows
+        .iter()
+        .filter_map(|window| WindowInfo::of(window).ok())
+        .collect())
+}
+
+/// The window the user is working in, for backends without a window picker
+///
+/// This app's own windows are skipped. Falls back to the front-most visible
+/// window when none reports focus.
+pub fn active_window() -> AppResult<WindowInfo> {
+    pick_active_window(list_windows()?, std::process::id())
+        .ok_or_else(|| "No window to capture".to_string())
+}
+
+fn pick_active_window(windows: Vec<WindowInfo>, own_pid: u32) -> Option<WindowInfo> {
+    let mut candidates = windows
+        .into_iter()
+        .filter(|window| window.pid != own_pid && !window.is_minimized)
+        .peekable();
+    let front_most = candidates.peek().cloned();
+    candidates.find(|window| window.is_focused).or(front_most)
+}
+
+/// Find a window by its xcap id
+pub fn find_window(window_id: u32) -> AppResult<Window> {
+    let windows = Window::all().map_err(|e| format!("Failed to get windows: {}", e))?;
+
+    windows
+        .into_iter()
+        .find(|window| window.id().ok() == Some(window_id))
+        .ok_or_else(|| format!("Window {} not found", window_id))
+}
+
+/// Parameters of the synthetic drop shadow, in points
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct ShadowStyle {
+    blur_sigma: f32,
+    offset_y: f32,
+    opacity: f32,
+}
+
+impl ShadowStyle {
+    /// A shadow resembling the platform's own window shadow
+    fn platform() -> Self {
+        if cfg!(target_os = "macos") {
+            Self {
+                blur_sigma: 14.0,
+                offset_y: 10.0,
+                opacity: 0.5,
+            }
+        } else {
+            Self {
+                blur_sigma: 8.0,
+                offset_y: 3.0,
+                opacity: 0.35,
+            }
+        }
+    }
+}
+
+/// Window corner radius in points for platforms without a shape query
+fn platform_corner_radius() -> f32 {
+    if cfg!(target_os = "macos") {
+        10.0
+    } else if cfg!(target_os = "windows") {
+        8.0
+    } else {
+        0.0
+    }
+}
+
+/// Anti-aliased coverage mask of a rounded rectangle
+fn rounded_rect_mask(width: u32, height: u32, radius: f32) -> GrayImage {
+    let radius = radius.min(width as f32 / 2.0).min(height as f32 / 2.0);
+    if radius <= 0.0 {
+        return GrayImage::from_pixel(width, height, Luma([255]));
+    }
+
+    GrayImage::from_fn(width, height, |x, y| {
+        // Distance from the nearest corner circle's centre, if in a corner
+        let px = x as f32 + 0.5;
+        let py = y as f32 + 0.5;
+        let cx = px.clamp(radius, width as f32 - radius);
+        let cy = py.clamp(radius, height as f32 - radius);
+        let distance = ((px - cx).powi(2) + (py - cy).powi(2)).sqrt();
+
+        let coverage = (radius - distance + 0.5).clamp(0.0, 1.0);
+        Luma([(coverage * 255.0).round() as u8])
+    })
+}
+
+/// The window's bounding shape from the X Shape extension
+///
+/// Returns `None` when the window is a plain rectangle.
+#[cfg(target_os = "linux")]
+fn window_shape_mask(window_id: u32, width: u32, height: u32) -> AppResult<Option<GrayImage>> {
+    use x11rb::protocol::shape::{ConnectionExt, SK};
+
+    let (conn, _) =
+        x11rb::connect(None).map_err(|e| format!("Failed to connect to X server: {}", e))?;
+    let reply = conn
+        .shape_get_rectangles(window_id, SK::BOUNDING)
+        .map_err(|e| format!("Failed to query window shape: {}", e))?
+        .reply()
+        .map_err(|e| format!("Failed to query window shape: {}", e))?;
+
+    let covers_window = |rect: &x11rb::protocol::xproto::Rectangle| {
+        rect.x <= 0
+            && rect.y <= 0
+            && rect.x as i32 + rect.width as i32 >= width as i32
+            && rect.y as i32 + rect.height as i32 >= height as i32
+    };
+    if reply.rectangles.is_empty() || reply.rectangles.iter().any(covers_window) {
+        return Ok(None);
+    }
+
+    let mut mask = GrayImage::new(width, height);
+    for rect in &reply.rectangles {
+        let x0 = (rect.x as i32).clamp(0, width as i32) as u32;
+        let y0 = (rect.y as i32).clamp(0, height as i32) as u32;
+        let x1 = (rect.x as i32 + rect.width as i32).clamp(0, width as i32) as u32;
+        let y1 = (rect.y as i32 + rect.height as i32).clamp(0, height as i32) as u32;
+        for y in y0..y1 {
+            for x in x0..x1 {
+                mask.put_pixel(x, y, Luma([255]));
+            }
+        }
+    }
+    Ok(Some(mask))
+}
+
+/// The mask that cuts a captured window down to its on-screen shape
+fn window_mask(window: &Window, image: &RgbaImage, scale_factor: f32) -> Option<GrayImage> {
+    let (width, height) = image.dimensions();
+
+    #[cfg(target_os = "linux")]
+    if let Ok(window_id) = window.id() {
+        match window_shape_mask(window_id, width, height) {
+            Ok(mask) => return mask,
+            Err(e) => eprintln!("Falling back to rectangular window shape: {}", e),
+        }
+    }
+
+    // Maximized and fullscreen windows have square corners
+    if window.is_maximized().unwrap_or(false) {
+        return None;
+    }
+    let radius = platform_corner_radius() * scale_factor;
+    (radius > 0.0).then(|| rounded_rect_mask(width, height, radius))
+}
+
+/// Multiply the image's alpha channel by a coverage mask
+fn apply_mask(image: &mut RgbaImage, mask: &GrayImage) {
+    for (pixel, coverage) in image.pixels_mut().zip(mask.pixels()) {
+        pixel[3] = ((pixel[3] as u16 * coverage[0] as u16 + 127) / 255) as u8;
+    }
+}
+
+/// Place an image on a larger transparent canvas with a soft shadow behind it
+fn add_shadow(image: &RgbaImage, style: ShadowStyle, scale_factor: f32) -> RgbaImage {
+    let sigma = (style.blur_sigma * scale_factor).max(0.1);
+    let offset_y = (style.offset_y * scale_factor).round() as i64;
+    let margin = (sigma * 3.0).ceil() as u32 + offset_y.unsigned_abs() as u32;
+
+    let width = image.width() + margin * 2;
+    let height = image.height() + margin * 2;
+
+    // Blur the window's silhouette to get the shadow's alpha
+    let mut silhouette = GrayImage::new(width, height);
+    for (x, y, pixel) in image.enumerate_pixels() {
+        let sy = (y + margin) as i64 + offset_y;
+        if sy >= 0 && (sy as u32) < height {
+            silhouette.put_pixel(x + margin, sy as u32, Luma([pixel[3]]));
+        }
+    }
+    let silhouette = gaussian_blur_f32(&silhouette, sigma);
+
+    let mut canvas = RgbaImage::from_fn(width, height, |x, y| {
+        let alpha = silhouette.get_pixel(x, y)[0] as f32 * style.opacity;
+        Rgba([0, 0, 0, alpha.round() as u8])
+    });
+    imageops::overlay(&mut canvas, image, margin as i64, margin as i64);
+    canvas
+}
+
+/// Capture a window as RGBA with everything outside its shape transparent
+///
+/// With `shadow` set, a soft drop shadow in the platform's style is added
+/// around the window.
+pub fn capture_window_transparent(
+    window_id: u32,
+    shadow: bool,
+    save_dir: &str,
+) -> AppResult<String> {
+    let window = find_window(window_id)?;
+    if window.is_minimized().unwrap_or(false) {
+        return Err("Cannot capture a minimized window".to_string());
+    }
+
+    let scale_factor = window
+        .current_monitor()
+        .and_then(|monitor| monitor.scale_factor())
+        .unwrap_or(1.0);
+    let mut image = window
+        .capture_image()
+        .map_err(|e| format!("Failed to capture window: {}", e))?;
+
+    if let Some(mask) = window_mask(&window, &image, scale_factor) {
+        apply_mask(&mut image, &mask);
+    }
+    if shadow {
+        image = add_shadow(&image, ShadowStyle::platform(), scale_factor);
+    }
+
+    save_image(&DynamicImage::ImageRgba8(image), save_dir, "window")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(id: u32, pid: u32, is_minimized: bool, is_focused: bool) -> WindowInfo {
+        WindowInfo {
+            id,
+            pid,
+            app_name: String::new(),
+            title: String::new(),
+            x: 0,
+            y: 0,
+            width: 100,
+            height: 100,
+            is_minimized,
+            is_focused,
+        }
+    }
+
+    #[test]
+    fn test_pick_active_window_prefers_focus_over_own_windows() {
+        let windows = vec![
+            window(1, 10, false, true),
+            window(2, 20, true, true),
+            window(3, 30, false, false),
+            window(4, 40, false, true),
+        ];
+
+        let picked = pick_active_window(windows, 10).unwrap();
+        assert_eq!(picked.id, 4);
+    }
+
+    #[test]
+    fn test_pick_active_window_falls_back_to_front_most() {
+        let windows = vec![window(1, 10, false, true), window(2, 20, false, false)];
+
+        assert_eq!(pick_active_window(windows.clone(), 10).unwrap().id, 2);
+        assert!(pick_active_window(windows[..1].to_vec(), 10).is_none());
+    }
+
+    #[test]
+    fn test_rounded_rect_mask_clears_corners() {
+        let mask = rounded_rect_mask(40, 30, 10.0);
+
+        assert_eq!(mask.get_pixel(0, 0)[0], 0);
+        assert_eq!(mask.get_pixel(39, 29)[0], 0);
+        assert_eq!(mask.get_pixel(20, 0)[0], 255);
+        assert_eq!(mask.get_pixel(0, 15)[0], 255);
+        assert_eq!(mask.get_pixel(20, 15)[0], 255);
+    }
+
+    #[test]
+    fn test_rounded_rect_mask_without_radius_is_opaque() {
+        let mask = rounded_rect_mask(8, 8, 0.0);
+        assert!(mask.pixels().all(|p| p[0] == 255));
+    }
+
+    #[test]
+    fn test_apply_mask_scales_alpha() {
+        let mut image = RgbaImage::from_pixel(2, 1, Rgba([10, 20, 30, 255]));
+        let mask = GrayImage::from_raw(2, 1, vec![0, 128]).unwrap();
+        apply_mask(&mut image, &mask);
+
+        assert_eq!(image.get_pixel(0, 0)[3], 0);
+        assert_eq!(image.get_pixel(1, 0)[3], 128);
+        assert_eq!(image.get_pixel(1, 0)[0], 10);
+    }
+
+    #[test]
+    fn test_add_shadow_pads_and_darkens_below() {
+        let image = RgbaImage::from_pixel(20, 20, Rgba([255, 255, 255, 255]));
+        let style = ShadowStyle {
+            blur_sigma: 2.0,
+            offset_y: 3.0,
+            opacity: 0.5,
+        };
+        let shadowed = add_shadow(&image, style, 1.0);
+
+        // margin = ceil(2 * 3) + 3
+        let margin = 9;
+        assert_eq!(shadowed.dimensions(), (20 + margin * 2, 20 + margin * 2));
+        assert_eq!(
+            *shadowed.get_pixel(margin + 10, margin + 10),
+            Rgba([255, 255, 255, 255])
+        );
+
+        // More shadow below the window than above it
+        let above = shadowed.get_pixel(margin + 10, margin - 2)[3];
+        let below = shadowed.get_pixel(margin + 10, margin + 21)[3];
+        assert!(below > above);
+        assert_eq!(shadowed.get_pixel(0, 0)[3], 0);
+    }
+}
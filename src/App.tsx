@@ -4,6 +4,7 @@ import { Card, CardContent } from "@/components/ui/card";
 import { Switch } from "@/components/ui/switch";
 import { isAssetId, isDataUrl, migrateStoredValue } from "@/lib/asset-registry";
 import { processScreenshotWithDefaultBackground } from "@/lib/auto-process";
+import { toCaptureError } from "@/lib/capture-error";
 import { hasCompletedOnboarding } from "@/lib/onboarding";
 import { invoke } from "@tauri-apps/api/core";
 import { emitTo, listen } from "@tauri-apps/api/event";
@@ -19,6 +20,8 @@ import { AppWindowMac, Crop, Monitor, ScanText } from "lucide-react";
 import { toast } from "sonner";
 import { lazy, Suspense, useCallback, useEffect, useRef, useState } from "react";
 import type { KeyboardShortcut } from "./components/preferences/KeyboardShortcutManager";
+import type { CaptureProcessed } from "@/types/capture-events";
+import type { RecordingStateChanged } from "@/types/recording";
 import { SettingsIcon } from "./components/SettingsIcon";
 
 // Lazy load heavy components
@@ -27,7 +30,7 @@ const OnboardingFlow = lazy(() => import("./components/onboarding/OnboardingFlow
 const PreferencesPage = lazy(() => import("./components/preferences/PreferencesPage").then(m => ({ default: m.PreferencesPage })));
 
 type AppMode = "main" | "editing" | "preferences";
-type CaptureMode = "region" | "fullscreen" | "window" | "ocr";
+type CaptureMode = "region" | "freeze" | "lastRegion" | "fullscreen" | "window" | "ocr" | "ocrTable" | "scanQr" | "redact";
 
 // Loading fallback for lazy loaded components
 function LoadingFallback() {
@@ -49,8 +52,29 @@ const DEFAULT_SHORTCUTS: KeyboardShortcut[] = [
   { id: "fullscreen", action: "Capture Screen", shortcut: "CommandOrControl+Shift+F", enabled: false },
   { id: "window", action: "Capture Window", shortcut: "CommandOrControl+Shift+D", enabled: false },
   { id: "ocr", action: "OCR Region", shortcut: "CommandOrControl+Shift+O", enabled: false },
+  { id: "ocrTable", action: "OCR Table", shortcut: "CommandOrControl+Shift+T", enabled: false },
+  { id: "scanQr", action: "Scan QR Region", shortcut: "CommandOrControl+Shift+B", enabled: false },
+  { id: "redact", action: "Capture & Redact Region", shortcut: "CommandOrControl+Shift+H", enabled: false },
+  { id: "freeze", action: "Freeze & Capture Region", shortcut: "CommandOrControl+Shift+E", enabled: false },
 ];
 
+type TextCaptureMode = Extract<CaptureMode, "ocr" | "ocrTable" | "scanQr">;
+
+/** Modes that capture a region and copy text read from it */
+const TEXT_CAPTURES: Record<TextCaptureMode, { command: string; copied: string; failed: string }> = {
+  ocr: { command: "native_capture_ocr_region", copied: "Text copied to clipboard!", failed: "OCR failed" },
+  ocrTable: {
+    command: "native_capture_ocr_table",
+    copied: "Table copied to clipboard!",
+    failed: "Table extraction failed",
+  },
+  scanQr: { command: "native_capture_scan_qr_region", copied: "Code copied to clipboard!", failed: "Scan failed" },
+};
+
+function isTextCapture(mode: CaptureMode): mode is TextCaptureMode {
+  return mode in TEXT_CAPTURES;
+}
+
 function formatShortcut(shortcut: string): string {
   return shortcut
     .replace(/CommandOrControl/g, "⌘")
@@ -262,6 +286,10 @@ function App() {
   const [saveDir, setSaveDir] = useState<string>("");
   const [copyToClipboard, setCopyToClipboard] = useState(true);
   const [autoApplyBackground, setAutoApplyBackground] = useState(false);
+  const [includeCursor, setIncludeCursor] = useState(false);
+  const [cursorHalo, setCursorHalo] = useState(false);
+  const [windowShadow, setWindowShadow] = useState(true);
+  const [captureDelay, setCaptureDelay] = useState(0);
   const [error, setError] = useState<string | null>(null);
   const [isCapturing, setIsCapturing] = useState(false);
   const [tempScreenshotPath, setTempScreenshotPath] = useState<string | null>(null);
@@ -271,14 +299,14 @@ function App() {
   const [tempDir, setTempDir] = useState<string>("/tmp");
 
   // Refs to hold current values for use in callbacks that may have stale closures
-  const settingsRef = useRef({ autoApplyBackground, saveDir, copyToClipboard, tempDir });
+  const settingsRef = useRef({ autoApplyBackground, saveDir, copyToClipboard, tempDir, includeCursor, cursorHalo, windowShadow, captureDelay });
   const registeredShortcutsRef = useRef<Set<string>>(new Set());
   const lastCaptureTimeRef = useRef(0);
 
   // Keep ref in sync with state
   useEffect(() => {
-    settingsRef.current = { autoApplyBackground, saveDir, copyToClipboard, tempDir };
-  }, [autoApplyBackground, saveDir, copyToClipboard, tempDir]);
+    settingsRef.current = { autoApplyBackground, saveDir, copyToClipboard, tempDir, includeCursor, cursorHalo, windowShadow, captureDelay };
+  }, [autoApplyBackground, saveDir, copyToClipboard, tempDir, includeCursor, cursorHalo, windowShadow, captureDelay]);
 
   // Load settings function
   const loadSettings = useCallback(async () => {
@@ -301,6 +329,18 @@ function App() {
         setAutoApplyBackground(savedAutoApply);
       }
 
+      const savedIncludeCursor = await store.get<boolean>("includeCursor");
+      setIncludeCursor(savedIncludeCursor ?? false);
+
+      const savedCursorHalo = await store.get<boolean>("cursorHalo");
+      setCursorHalo(savedCursorHalo ?? false);
+
+      const savedWindowShadow = await store.get<boolean>("windowShadow");
+      setWindowShadow(savedWindowShadow ?? true);
+
+      const savedCaptureDelay = await store.get<number>("captureDelay");
+      setCaptureDelay(savedCaptureDelay ?? 0);
+
       const savedSaveDir = await store.get<string>("saveDir");
       if (savedSaveDir) {
         setSaveDir(savedSaveDir);
@@ -370,6 +410,18 @@ function App() {
           setAutoApplyBackground(savedAutoApply);
         }
 
+        const savedIncludeCursor = await store.get<boolean>("includeCursor");
+        setIncludeCursor(savedIncludeCursor ?? false);
+
+        const savedCursorHalo = await store.get<boolean>("cursorHalo");
+        setCursorHalo(savedCursorHalo ?? false);
+
+        const savedWindowShadow = await store.get<boolean>("windowShadow");
+        setWindowShadow(savedWindowShadow ?? true);
+
+        const savedCaptureDelay = await store.get<number>("captureDelay");
+        setCaptureDelay(savedCaptureDelay ?? 0);
+
         // Only use saved directory if it's a non-empty string, otherwise use desktop
         const savedSaveDir = await store.get<string>("saveDir");
         if (savedSaveDir && savedSaveDir.trim() !== "") {
@@ -457,6 +509,52 @@ function App() {
     };
   }, []);
 
+  // Warn when a capture went ahead without a step the user turned on, such as
+  // sensitive window masking on Wayland
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+
+    listen<CaptureProcessed>("capture-processed", (event) => {
+      if (event.payload.skipped) {
+        toast.warning(event.payload.skipped, { duration: 6000 });
+      }
+    })
+      .then((fn) => {
+        unlisten = fn;
+      })
+      .catch((err) => console.error("Failed to setup capture-processed listener:", err));
+
+    return () => {
+      if (unlisten) {
+        unlisten();
+      }
+    };
+  }, []);
+
+  // Report recordings that stop on their own, such as when ffmpeg exits
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+
+    listen<RecordingStateChanged>("recording-state-changed", (event) => {
+      if (event.payload.error) {
+        toast.error("Recording stopped", {
+          description: event.payload.error,
+          duration: 6000,
+        });
+      }
+    })
+      .then((fn) => {
+        unlisten = fn;
+      })
+      .catch((err) => console.error("Failed to setup recording-state-changed listener:", err));
+
+    return () => {
+      if (unlisten) {
+        unlisten();
+      }
+    };
+  }, []);
+
 
   const handleCapture = useCallback(async (captureMode: CaptureMode = "region") => {
     const now = Date.now();
@@ -473,43 +571,61 @@ function App() {
     const appWindow = getCurrentWindow();
 
     // Read current settings from ref to avoid stale closure issues
-    const { autoApplyBackground: shouldAutoApply, saveDir: currentSaveDir, copyToClipboard: shouldCopyToClipboard, tempDir: currentTempDir } = settingsRef.current;
+    const { autoApplyBackground: shouldAutoApply, saveDir: currentSaveDir, copyToClipboard: shouldCopyToClipboard, tempDir: currentTempDir, includeCursor: shouldIncludeCursor, cursorHalo: shouldShowCursorHalo, windowShadow: shouldAddWindowShadow, captureDelay: delaySecs } = settingsRef.current;
 
     try {
+      if (captureMode === "freeze") {
+        try {
+          // Grab the screens before hiding, so transient UI such as menus and
+          // tooltips is still on screen
+          await invoke("open_frozen_region_selector", {
+            saveDir: currentTempDir,
+            includeCursor: shouldIncludeCursor,
+            cursorHalo: shouldShowCursorHalo,
+          });
+          await appWindow.hide();
+          // The region selector window emits "capture-complete" when done
+          return;
+        } catch (err) {
+          console.error("Failed to open frozen region selector:", err);
+          setError(`Failed to freeze screen: ${toCaptureError(err).message}`);
+          setIsCapturing(false);
+          return;
+        }
+      }
+
       await appWindow.hide();
       await new Promise((resolve) => setTimeout(resolve, 400));
 
-      if (captureMode === "ocr") {
+      if (isTextCapture(captureMode)) {
+        const textCapture = TEXT_CAPTURES[captureMode];
         try {
-          const recognizedText = await invoke<string>("native_capture_ocr_region", {
+          const recognizedText = await invoke<string>(textCapture.command, {
             saveDir: currentTempDir,
+            delaySecs,
           });
 
-          toast.success("Text copied to clipboard!", {
+          toast.success(textCapture.copied, {
             description: recognizedText.length > 50 ? `${recognizedText.substring(0, 50)}...` : recognizedText,
             duration: 3000,
           });
 
           await appWindow.hide();
         } catch (err) {
-          const errorMessage = err instanceof Error ? err.message : String(err);
-          if (errorMessage.includes("cancelled") || errorMessage.includes("was cancelled")) {
+          const { code, message: errorMessage } = toCaptureError(err);
+          if (code === "cancelled") {
             await appWindow.hide();
-          } else if (errorMessage.includes("already in progress")) {
+          } else if (code === "busy") {
             setError("Please wait for the current screenshot to complete");
             await appWindow.hide();
-          } else if (
-            errorMessage.toLowerCase().includes("permission") ||
-            errorMessage.toLowerCase().includes("access") ||
-            errorMessage.toLowerCase().includes("denied")
-          ) {
+          } else if (code === "permission_denied") {
             setError(
               "Screen Recording permission required. Please go to System Settings > Privacy & Security > Screen Recording and enable access for Better Shot, then restart the app."
             );
             await restoreWindow();
           } else {
             setError(errorMessage);
-            toast.error("OCR failed", {
+            toast.error(textCapture.failed, {
               description: errorMessage,
               duration: 5000,
             });
@@ -526,28 +642,35 @@ function App() {
         try {
           await invoke("open_region_selector", {
             saveDir: currentTempDir,
+            delaySecs,
+            includeCursor: shouldIncludeCursor,
+            cursorHalo: shouldShowCursorHalo,
           });
           // Don't proceed - the region selector window will handle completion
           // and emit a "capture-complete" event when done
           return;
         } catch (err) {
           console.error("Failed to open region selector:", err);
-          setError(
-            `Failed to open region selector: ${err instanceof Error ? err.message : String(err)}`
-          );
+          setError(`Failed to open region selector: ${toCaptureError(err).message}`);
           setIsCapturing(false);
           return;
         }
       }
 
-      // Handle other capture modes (fullscreen, window)
-      const commandMap: Record<"fullscreen" | "window", string> = {
+      // Handle other capture modes (previous region, fullscreen, window, redacted region)
+      const commandMap: Record<"lastRegion" | "fullscreen" | "window" | "redact", string> = {
+        lastRegion: "recapture_last_region",
         fullscreen: "native_capture_fullscreen",
         window: "native_capture_window",
+        redact: "native_capture_redacted",
       };
 
       const screenshotPath = await invoke<string>(commandMap[captureMode], {
         saveDir: currentTempDir,
+        delaySecs,
+        includeCursor: shouldIncludeCursor,
+        cursorHalo: shouldShowCursorHalo,
+        windowShadow: shouldAddWindowShadow,
       });
 
       // Get mouse position IMMEDIATELY after screenshot completes
@@ -595,22 +718,18 @@ function App() {
       }
       await restoreWindowOnScreen(mouseX, mouseY);
     } catch (err) {
-      const errorMessage = err instanceof Error ? err.message : String(err);
-      if (errorMessage.includes("cancelled") || errorMessage.includes("was cancelled")) {
+      const { code, message: errorMessage } = toCaptureError(err);
+      if (code === "cancelled") {
         // Only restore window if not in auto-apply mode
         if (!shouldAutoApply) {
           await restoreWindow();
         }
-      } else if (errorMessage.includes("already in progress")) {
+      } else if (code === "busy") {
         setError("Please wait for the current screenshot to complete");
         if (!shouldAutoApply) {
           await restoreWindow();
         }
-      } else if (
-        errorMessage.toLowerCase().includes("permission") ||
-        errorMessage.toLowerCase().includes("access") ||
-        errorMessage.toLowerCase().includes("denied")
-      ) {
+      } else if (code === "permission_denied") {
         setError(
           "Screen Recording permission required. Please go to System Settings > Privacy & Security > Screen Recording and enable access for Better Shot, then restart the app."
         );
@@ -646,6 +765,10 @@ function App() {
           "Capture Screen": "fullscreen",
           "Capture Window": "window",
           "OCR Region": "ocr",
+          "OCR Table": "ocrTable",
+          "Scan QR Region": "scanQr",
+          "Capture & Redact Region": "redact",
+          "Freeze & Capture Region": "freeze",
         };
 
         for (const shortcut of shortcuts) {
@@ -694,6 +817,11 @@ function App() {
     let unlisten6: (() => void) | null = null;
     let unlisten7: (() => void) | null = null;
     let unlisten8: (() => void) | null = null;
+    let unlisten9: (() => void) | null = null;
+    let unlisten10: (() => void) | null = null;
+    let unlisten11: (() => void) | null = null;
+    let unlisten12: (() => void) | null = null;
+    let unlisten13: (() => void) | null = null;
     let mounted = true;
 
     const setupListeners = async () => {
@@ -756,6 +884,31 @@ function App() {
         }
       });
       if (!mounted) { u8(); return; } else { unlisten8 = u8; }
+
+      const u9 = await listen("capture-last-region", () => {
+        if (mounted) handleCaptureRef.current("lastRegion");
+      });
+      if (!mounted) { u9(); return; } else { unlisten9 = u9; }
+
+      const u10 = await listen("capture-freeze", () => {
+        if (mounted) handleCaptureRef.current("freeze");
+      });
+      if (!mounted) { u10(); return; } else { unlisten10 = u10; }
+
+      const u11 = await listen("capture-ocr-table", () => {
+        if (mounted) handleCaptureRef.current("ocrTable");
+      });
+      if (!mounted) { u11(); return; } else { unlisten11 = u11; }
+
+      const u12 = await listen("capture-scan-qr", () => {
+        if (mounted) handleCaptureRef.current("scanQr");
+      });
+      if (!mounted) { u12(); return; } else { unlisten12 = u12; }
+
+      const u13 = await listen("capture-redacted", () => {
+        if (mounted) handleCaptureRef.current("redact");
+      });
+      if (!mounted) { u13(); return; } else { unlisten13 = u13; }
     };
 
     void setupListeners().catch((err) =>
@@ -772,6 +925,11 @@ function App() {
       unlisten6?.();
       unlisten7?.();
       unlisten8?.();
+      unlisten9?.();
+      unlisten10?.();
+      unlisten11?.();
+      unlisten12?.();
+      unlisten13?.();
     };
   }, []); // Empty dependency array - only run once on mount
 
@@ -2,7 +2,7 @@ import { useState, useRef, useEffect, useCallback } from "react";
 import { convertFileSrc, invoke } from "@tauri-apps/api/core";
 import { getCurrentWindow } from "@tauri-apps/api/window";
 import { toast } from "sonner";
-import { Copy, ImageDown, Loader2, Redo2, Undo2 } from "lucide-react";
+import { Contrast, Copy, ImageDown, Loader2, Redo2, Undo2 } from "lucide-react";
 import { Button } from "@/components/ui/button";
 import { Card, CardContent } from "@/components/ui/card";
 import { Tooltip, TooltipContent, TooltipProvider, TooltipTrigger } from "@/components/ui/tooltip";
@@ -14,6 +14,7 @@ import { AnnotationToolbar } from "./editor/AnnotationToolbar";
 import { AnnotationCanvas } from "./editor/AnnotationCanvas";
 import { PropertiesPanel } from "./editor/PropertiesPanel";
 import { Annotation, ToolType } from "@/types/annotations";
+import type { ContrastOptions, ContrastReport } from "@/types/contrast";
 import { usePreviewGenerator } from "@/hooks/usePreviewGenerator";
 import { assetCategories } from "@/hooks/useEditorSettings";
 import {
@@ -47,6 +48,7 @@ export function ImageEditor({ imagePath, onSave, onCancel }: ImageEditorProps) {
   // Save/copy state
   const [isSaving, setIsSaving] = useState(false);
   const [isCopying, setIsCopying] = useState(false);
+  const [isAuditing, setIsAuditing] = useState(false);
   const [tempDir, setTempDir] = useState<string>("/private/tmp");
 
    // Annotation UI state (not part of undo/redo)
@@ -216,6 +218,58 @@ export function ImageEditor({ imagePath, onSave, onCancel }: ImageEditorProps) {
     }
   }, [screenshotImage, annotations, renderHighQualityCanvas, isSaving, isCopying, tempDir, imagePath]);
 
+  // Contrast audit of the original capture, before any effects
+  const handleContrastAudit = useCallback(async () => {
+    if (isAuditing) return;
+
+    setIsAuditing(true);
+    try {
+      const options: ContrastOptions = { pixelRatio: window.devicePixelRatio || 1 };
+      const report = await invoke<ContrastReport>("audit_contrast", {
+        imagePath,
+        options,
+        annotatedDir: tempDir,
+      });
+
+      if (report.failures.length === 0) {
+        toast.success("No contrast issues found", {
+          description: `${report.runsChecked} text runs meet WCAG AA`,
+          duration: 3000,
+        });
+        return;
+      }
+
+      const annotatedPath = report.annotatedPath;
+      toast.warning(
+        `${report.failures.length} of ${report.runsChecked} text runs fail WCAG AA`,
+        {
+          description: report.failures
+            .slice(0, 3)
+            .map((issue) => `"${issue.text}" ${issue.ratio}:1, needs ${issue.required}:1`)
+            .join("; "),
+          duration: 8000,
+          action: annotatedPath
+            ? {
+                label: "Copy Image",
+                onClick: () => {
+                  invoke("copy_image_file_to_clipboard", { path: annotatedPath }).catch((err) =>
+                    toast.error("Failed to copy", { description: String(err) })
+                  );
+                },
+              }
+            : undefined,
+        }
+      );
+    } catch (err) {
+      toast.error("Contrast check failed", {
+        description: err instanceof Error ? err.message : String(err),
+        duration: 3000,
+      });
+    } finally {
+      setIsAuditing(false);
+    }
+  }, [imagePath, tempDir, isAuditing]);
+
   // Annotation handlers
   const handleAnnotationAdd = useCallback((annotation: Annotation) => {
     actions.addAnnotation(annotation);
@@ -371,6 +425,27 @@ export function ImageEditor({ imagePath, onSave, onCancel }: ImageEditorProps) {
                   <p>Redo <kbd className="ml-1 text-xs opacity-70">⌘⇧Z</kbd></p>
                 </TooltipContent>
               </Tooltip>
+              <Tooltip>
+                <TooltipTrigger asChild>
+                  <Button
+                    variant="ghost"
+                    size="icon"
+                    onClick={handleContrastAudit}
+                    disabled={!imageLoaded || isAuditing}
+                    className="text-muted-foreground hover:text-foreground hover:bg-secondary disabled:opacity-30 disabled:cursor-not-allowed"
+                    aria-label="Check contrast"
+                  >
+                    {isAuditing ? (
+                      <Loader2 className="size-4 animate-spin" aria-hidden="true" />
+                    ) : (
+                      <Contrast className="size-4" aria-hidden="true" />
+                    )}
+                  </Button>
+                </TooltipTrigger>
+                <TooltipContent>
+                  <p>Check text contrast (WCAG AA)</p>
+                </TooltipContent>
+              </Tooltip>
             </div>
           </TooltipProvider>
         </div>
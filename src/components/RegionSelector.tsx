@@ -8,6 +8,11 @@ interface Region {
   height: number;
 }
 
+/** A detected window or UI element, in the same global space as monitorShots */
+export interface SelectableElement extends Region {
+  source: "window" | "edges";
+}
+
 interface RegionSelectorProps {
   onSelect: (region: Region) => void;
   onCancel: () => void;
@@ -18,15 +23,19 @@ interface RegionSelectorProps {
     width: number;
     height: number;
     scale_factor: number;
-    path: string;
+    frame_id: string;
   }[];
+  elements?: SelectableElement[];
 }
 
-export function RegionSelector({ onSelect, onCancel, monitorShots }: RegionSelectorProps) {
+const IDLE_INSTRUCTIONS = "Drag to select · Click to snap to element · ESC to cancel";
+const NO_ELEMENTS: SelectableElement[] = [];
+
+export function RegionSelector({ onSelect, onCancel, monitorShots, elements = NO_ELEMENTS }: RegionSelectorProps) {
   const containerRef = useRef<HTMLDivElement>(null);
   const canvasRef = useRef<HTMLCanvasElement>(null);
   const rafRef = useRef<number>(0);
-  const [instructionText, setInstructionText] = useState("Drag to select · ESC to cancel");
+  const [instructionText, setInstructionText] = useState(IDLE_INSTRUCTIONS);
 
   // Selection state stored in refs for performance
   const isSelectingRef = useRef(false);
@@ -35,6 +44,7 @@ export function RegionSelector({ onSelect, onCancel, monitorShots }: RegionSelec
   const currentRef = useRef({ x: 0, y: 0 });
   const needsUpdateRef = useRef(false);
   const dragHandleRef = useRef<{ type: 'corner' | 'edge'; index: number } | null>(null);
+  const hoveredElementRef = useRef<Region | null>(null); // Local coordinates
 
   // Calculate bounds for multi-monitor
   const bounds = useMemo(() => {
@@ -68,7 +78,7 @@ export function RegionSelector({ onSelect, onCancel, monitorShots }: RegionSelec
         ...shot,
         left: shot.x - bounds.minX,
         top: shot.y - bounds.minY,
-        url: convertFileSrc(shot.path),
+        url: convertFileSrc(shot.frame_id, "frame"),
       })),
     [monitorShots, bounds.minX, bounds.minY]
   );
@@ -103,7 +113,23 @@ export function RegionSelector({ onSelect, onCancel, monitorShots }: RegionSelec
       // Clear canvas
       ctx.clearRect(0, 0, bounds.width, bounds.height);
 
-      if (isSelectingRef.current || hasSelectionRef.current || needsUpdateRef.current) {
+      const hovered = hoveredElementRef.current;
+      if (hovered && !isSelectingRef.current && !hasSelectionRef.current) {
+        // Highlight the element a click would snap to
+        const { x, y, width, height } = hovered;
+        ctx.fillStyle = "rgba(0, 0, 0, 0.5)";
+        ctx.fillRect(0, 0, bounds.width, y);
+        ctx.fillRect(0, y, x, height);
+        ctx.fillRect(x + width, y, bounds.width - x - width, height);
+        ctx.fillRect(0, y + height, bounds.width, bounds.height - y - height);
+
+        ctx.strokeStyle = "#3b82f6";
+        ctx.lineWidth = 2;
+        ctx.setLineDash([6, 4]);
+        ctx.strokeRect(x, y, width, height);
+        ctx.setLineDash([]);
+        needsUpdateRef.current = false;
+      } else if (isSelectingRef.current || hasSelectionRef.current || needsUpdateRef.current) {
         const x = Math.min(startRef.current.x, currentRef.current.x);
         const y = Math.min(startRef.current.y, currentRef.current.y);
         const width = Math.abs(currentRef.current.x - startRef.current.x);
@@ -342,7 +368,52 @@ export function RegionSelector({ onSelect, onCancel, monitorShots }: RegionSelec
       needsUpdateRef.current = true;
     };
 
+    // Smallest detected element under the pointer, in local coordinates
+    const getElementAtPosition = (mouseX: number, mouseY: number): Region | null => {
+      const globalX = mouseX + bounds.minX;
+      const globalY = mouseY + bounds.minY;
+      let best: SelectableElement | null = null;
+      for (const element of elements) {
+        const contains =
+          globalX >= element.x &&
+          globalX <= element.x + element.width &&
+          globalY >= element.y &&
+          globalY <= element.y + element.height;
+        if (contains && (!best || element.width * element.height < best.width * best.height)) {
+          best = element;
+        }
+      }
+      if (!best) return null;
+      return {
+        x: best.x - bounds.minX,
+        y: best.y - bounds.minY,
+        width: best.width,
+        height: best.height,
+      };
+    };
+
+    const updateHoveredElement = (e: MouseEvent) => {
+      if (isSelectingRef.current || hasSelectionRef.current) return;
+
+      const hovered = getElementAtPosition(e.clientX, e.clientY);
+      const previous = hoveredElementRef.current;
+      const unchanged =
+        previous === hovered ||
+        (previous !== null &&
+          hovered !== null &&
+          previous.x === hovered.x &&
+          previous.y === hovered.y &&
+          previous.width === hovered.width &&
+          previous.height === hovered.height);
+      if (!unchanged) {
+        hoveredElementRef.current = hovered;
+        needsUpdateRef.current = true;
+      }
+    };
+
     const updateCursor = (e: MouseEvent) => {
+      updateHoveredElement(e);
+
       // If we are dragging, keep the cursor consistent with the drag handle
       if (isSelectingRef.current && dragHandleRef.current) {
         return; // Keep existing cursor
@@ -392,13 +463,23 @@ export function RegionSelector({ onSelect, onCancel, monitorShots }: RegionSelec
         currentRef.current = { x: x + width, y: y + height };
 
         // Valid selection - enter adjustment mode
+        hasSelectionRef.current = true;
+        setInstructionText("Drag handles to adjust · ENTER to confirm · ESC to cancel");
+        needsUpdateRef.current = true;
+      } else if (hoveredElementRef.current) {
+        // A click rather than a drag - snap to the element under the pointer
+        const element = hoveredElementRef.current;
+        startRef.current = { x: element.x, y: element.y };
+        currentRef.current = { x: element.x + element.width, y: element.y + element.height };
+        hoveredElementRef.current = null;
+
         hasSelectionRef.current = true;
         setInstructionText("Drag handles to adjust · ENTER to confirm · ESC to cancel");
         needsUpdateRef.current = true;
       } else {
         // Selection too small - reset
         hasSelectionRef.current = false;
-        setInstructionText("Drag to select · ESC to cancel");
+        setInstructionText(IDLE_INSTRUCTIONS);
         needsUpdateRef.current = true;
       }
     };
@@ -410,7 +491,7 @@ export function RegionSelector({ onSelect, onCancel, monitorShots }: RegionSelec
           hasSelectionRef.current = false;
           isSelectingRef.current = false;
           dragHandleRef.current = null;
-          setInstructionText("Drag to select · ESC to cancel");
+          setInstructionText(IDLE_INSTRUCTIONS);
           needsUpdateRef.current = true;
         } else {
           // No selection - cancel region selector
@@ -464,7 +545,7 @@ export function RegionSelector({ onSelect, onCancel, monitorShots }: RegionSelec
       window.removeEventListener("mouseup", handleMouseUp);
       window.removeEventListener("keydown", handleKeyDown);
     };
-  }, [bounds.minX, bounds.minY, onSelect, onCancel]);
+  }, [bounds.minX, bounds.minY, elements, onSelect, onCancel]);
 
   return (
     <div
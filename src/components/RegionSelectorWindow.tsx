@@ -1,11 +1,11 @@
 import { useCallback, useEffect, useState } from "react";
 import { getCurrentWindow } from "@tauri-apps/api/window";
 import { invoke } from "@tauri-apps/api/core";
-import { listen } from "@tauri-apps/api/event";
-import { RegionSelector } from "./RegionSelector";
+import { RegionSelector, type SelectableElement } from "./RegionSelector";
 
-type RegionSelectorEventPayload = {
-    screenshotPath: string;
+type RegionSelectorPayload = {
+    saveDir: string;
+    captureId: string;
     monitorShots: Array<{
         id: number;
         x: number;
@@ -13,12 +13,13 @@ type RegionSelectorEventPayload = {
         width: number;
         height: number;
         scale_factor: number;
-        path: string;
+        frame_id: string;
     }>;
+    elements?: SelectableElement[];
 };
 
 export function RegionSelectorWindow() {
-    const [screenshotData, setScreenshotData] = useState<RegionSelectorEventPayload | null>(null);
+    const [screenshotData, setScreenshotData] = useState<RegionSelectorPayload | null>(null);
     const [isReady, setIsReady] = useState(false);
 
     useEffect(() => {
@@ -41,37 +42,25 @@ export function RegionSelectorWindow() {
     }, []);
 
     useEffect(() => {
-        let unlisten: (() => void) | undefined;
         let mounted = true;
 
-        const setupListener = async () => {
-            try {
-                const unlistenFn = await listen<RegionSelectorEventPayload>(
-                    "region-selector-show",
-                    (event) => {
-                        if (mounted) {
-                            setScreenshotData(event.payload);
-                        }
-                    }
-                );
-
+        // Tell the backend we're mounted; it answers with the frames once
+        // they're ready, so there's no event to miss
+        invoke<RegionSelectorPayload>("region_selector_ready")
+            .then((payload) => {
                 if (mounted) {
-                    unlisten = unlistenFn;
-                } else {
-                    unlistenFn();
+                    setScreenshotData(payload);
                 }
-            } catch (error) {
-                console.error("[RegionSelectorWindow] Failed to set up event listener:", error);
-            }
-        };
-
-        setupListener();
+            })
+            .catch(async (error) => {
+                console.error("[RegionSelectorWindow] Failed to load region selector payload:", error);
+                if (mounted) {
+                    await getCurrentWindow().close();
+                }
+            });
 
         return () => {
             mounted = false;
-            if (unlisten) {
-                unlisten();
-            }
         };
     }, []);
 
@@ -112,32 +101,17 @@ export function RegionSelectorWindow() {
 
                 const scale = targetShot.scale_factor;
 
-                // Derive save directory from the target shot path
-                let saveDir = "";
-                const lastSepIndex = Math.max(targetShot.path.lastIndexOf("/"), targetShot.path.lastIndexOf("\\"));
-                if (lastSepIndex !== -1) {
-                    saveDir = targetShot.path.substring(0, lastSepIndex);
-                }
-
-                // Call backend to crop from the SPECIFIC monitor's screenshot
+                // Call backend to crop from the SPECIFIC monitor's in-memory frame
                 const croppedPath = await invoke<string>("capture_region", {
-                    screenshotPath: targetShot.path,
+                    frameId: targetShot.frame_id,
                     x: Math.round(localX * scale),
                     y: Math.round(localY * scale),
                     width: Math.round(localWidth * scale),
                     height: Math.round(localHeight * scale),
-                    saveDir: saveDir,
+                    saveDir: screenshotData.saveDir,
+                    captureId: screenshotData.captureId,
                 });
 
-                // Clean up ALL temp monitor screenshots now that we have the crop
-                await Promise.all(
-                    screenshotData.monitorShots.map((shot) =>
-                        invoke("cleanup_temp_file", { path: shot.path }).catch((e) =>
-                            console.error("Failed to cleanup file:", shot.path, e)
-                        )
-                    )
-                );
-
                 // Emit event back to main window with the cropped image path
                 await invoke("emit_capture_complete", {
                     path: croppedPath,
@@ -156,14 +130,7 @@ export function RegionSelectorWindow() {
 
     const handleCancel = useCallback(async () => {
         try {
-            // Clean up all temp screenshot files
-            if (screenshotData?.monitorShots) {
-                await Promise.all(
-                    screenshotData.monitorShots.map(shot =>
-                        invoke("cleanup_temp_file", { path: shot.path }).catch(e => console.error("Failed to cleanup file:", shot.path, e))
-                    )
-                );
-            }
+            // Frames are released by the backend when this window closes
 
             // Restore main window
             await invoke("restore_main_window");
@@ -174,7 +141,7 @@ export function RegionSelectorWindow() {
         } catch (error) {
             console.error("Failed to cancel region selection:", error);
         }
-    }, [screenshotData]);
+    }, []);
 
     if (!isReady || !screenshotData) {
         return (
@@ -189,6 +156,7 @@ export function RegionSelectorWindow() {
             onSelect={handleSelect}
             onCancel={handleCancel}
             monitorShots={screenshotData.monitorShots}
+            elements={screenshotData.elements}
         />
     );
 }
@@ -22,6 +22,10 @@ const DEFAULT_SHORTCUTS: KeyboardShortcut[] = [
   { id: "fullscreen", action: "Capture Screen", shortcut: "CommandOrControl+Shift+F", enabled: false },
   { id: "window", action: "Capture Window", shortcut: "CommandOrControl+Shift+D", enabled: false },
   { id: "ocr", action: "OCR Region", shortcut: "CommandOrControl+Shift+O", enabled: false },
+  { id: "ocrTable", action: "OCR Table", shortcut: "CommandOrControl+Shift+T", enabled: false },
+  { id: "scanQr", action: "Scan QR Region", shortcut: "CommandOrControl+Shift+B", enabled: false },
+  { id: "redact", action: "Capture & Redact Region", shortcut: "CommandOrControl+Shift+H", enabled: false },
+  { id: "freeze", action: "Freeze & Capture Region", shortcut: "CommandOrControl+Shift+E", enabled: false },
 ];
 
 function formatShortcut(shortcut: string): string {
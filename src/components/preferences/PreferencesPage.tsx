@@ -8,6 +8,7 @@ import { Switch } from "@/components/ui/switch";
 import { BackgroundImageSelector } from "./BackgroundImageSelector";
 import { KeyboardShortcutManager } from "./KeyboardShortcutManager";
 import type { KeyboardShortcut } from "./KeyboardShortcutManager";
+import type { SensitiveKind } from "@/types/redaction";
 
 interface PreferencesPageProps {
   onBack: () => void;
@@ -17,12 +18,78 @@ interface PreferencesPageProps {
 interface GeneralSettings {
   saveDir: string;
   copyToClipboard: boolean;
+  includeCursor: boolean;
+  cursorHalo: boolean;
+  windowShadow: boolean;
+  captureDelay: number;
+  sensitiveWindowMasking: boolean;
+  sensitiveAppNames: string[];
+  sensitiveWindowTitles: string[];
+  sensitiveMaskStyle: "pixelate" | "solid";
+  redactionDetectors: SensitiveKind[];
+  redactionPatterns: string[];
+  redactionStyle: "pixelate" | "solid";
+  ocrLanguages: string[];
+  ocrAutomaticLanguageDetection: boolean;
+  ocrRecognitionLevel: "fast" | "accurate";
+  ocrLanguageCorrection: boolean;
+  ocrCustomWords: string[];
+  ocrMinimumTextHeight: number;
+  ocrTextFormat: "plain" | "markdown";
+  ocrTableFormat: "tsv" | "csv" | "markdown";
 }
 
+// Keep in sync with DEFAULT_APP_NAMES in src-tauri/src/privacy.rs
+const DEFAULT_SENSITIVE_APP_NAMES = [
+  "1Password",
+  "Bitwarden",
+  "Dashlane",
+  "KeePassXC",
+  "Keychain Access",
+  "LastPass",
+  "Passwords",
+];
+
+const REDACTION_DETECTORS: { kind: SensitiveKind; label: string }[] = [
+  { kind: "email", label: "Email addresses" },
+  { kind: "phone", label: "Phone numbers" },
+  { kind: "ipv4", label: "IPv4 addresses" },
+  { kind: "ipv6", label: "IPv6 addresses" },
+  { kind: "credit_card", label: "Card numbers" },
+  { kind: "jwt", label: "JSON Web Tokens" },
+  { kind: "aws_key", label: "AWS keys" },
+  { kind: "github_token", label: "GitHub tokens" },
+  { kind: "slack_token", label: "Slack tokens" },
+];
+
+const DEFAULT_REDACTION_DETECTORS = REDACTION_DETECTORS.map(({ kind }) => kind);
+
+const parseList = (text: string) =>
+  text.split(/[\n,]/).map((entry) => entry.trim()).filter(Boolean);
+
 export function PreferencesPage({ onBack, onSettingsChange }: PreferencesPageProps) {
   const [settings, setSettings] = useState<GeneralSettings>({
     saveDir: "",
     copyToClipboard: true,
+    includeCursor: false,
+    cursorHalo: false,
+    windowShadow: true,
+    captureDelay: 0,
+    sensitiveWindowMasking: true,
+    sensitiveAppNames: DEFAULT_SENSITIVE_APP_NAMES,
+    sensitiveWindowTitles: [],
+    sensitiveMaskStyle: "solid",
+    redactionDetectors: DEFAULT_REDACTION_DETECTORS,
+    redactionPatterns: [],
+    redactionStyle: "solid",
+    ocrLanguages: [],
+    ocrAutomaticLanguageDetection: false,
+    ocrRecognitionLevel: "accurate",
+    ocrLanguageCorrection: true,
+    ocrCustomWords: [],
+    ocrMinimumTextHeight: 0,
+    ocrTextFormat: "plain",
+    ocrTableFormat: "tsv",
   });
   const [isLoading, setIsLoading] = useState(true);
 
@@ -34,10 +101,48 @@ export function PreferencesPage({ onBack, onSettingsChange }: PreferencesPagePro
         
         const copyToClip = await store.get<boolean>("copyToClipboard");
         const saveDir = await store.get<string>("saveDir");
+        const includeCursor = await store.get<boolean>("includeCursor");
+        const cursorHalo = await store.get<boolean>("cursorHalo");
+        const windowShadow = await store.get<boolean>("windowShadow");
+        const captureDelay = await store.get<number>("captureDelay");
+        const sensitiveWindowMasking = await store.get<boolean>("sensitiveWindowMasking");
+        const sensitiveAppNames = await store.get<string[]>("sensitiveAppNames");
+        const sensitiveWindowTitles = await store.get<string[]>("sensitiveWindowTitles");
+        const sensitiveMaskStyle = await store.get<"pixelate" | "solid">("sensitiveMaskStyle");
+        const redactionDetectors = await store.get<SensitiveKind[]>("redactionDetectors");
+        const redactionPatterns = await store.get<string[]>("redactionPatterns");
+        const redactionStyle = await store.get<"pixelate" | "solid">("redactionStyle");
+        const ocrLanguages = await store.get<string[]>("ocrLanguages");
+        const ocrAutomaticLanguageDetection = await store.get<boolean>("ocrAutomaticLanguageDetection");
+        const ocrRecognitionLevel = await store.get<"fast" | "accurate">("ocrRecognitionLevel");
+        const ocrLanguageCorrection = await store.get<boolean>("ocrLanguageCorrection");
+        const ocrCustomWords = await store.get<string[]>("ocrCustomWords");
+        const ocrMinimumTextHeight = await store.get<number>("ocrMinimumTextHeight");
+        const ocrTextFormat = await store.get<"plain" | "markdown">("ocrTextFormat");
+        const ocrTableFormat = await store.get<"tsv" | "csv" | "markdown">("ocrTableFormat");
         
         setSettings({
           saveDir: saveDir || "",
           copyToClipboard: copyToClip ?? true,
+          includeCursor: includeCursor ?? false,
+          cursorHalo: cursorHalo ?? false,
+          windowShadow: windowShadow ?? true,
+          captureDelay: captureDelay ?? 0,
+          sensitiveWindowMasking: sensitiveWindowMasking ?? true,
+          sensitiveAppNames: sensitiveAppNames ?? DEFAULT_SENSITIVE_APP_NAMES,
+          sensitiveWindowTitles: sensitiveWindowTitles ?? [],
+          sensitiveMaskStyle: sensitiveMaskStyle ?? "solid",
+          redactionDetectors: redactionDetectors ?? DEFAULT_REDACTION_DETECTORS,
+          redactionPatterns: redactionPatterns ?? [],
+          redactionStyle: redactionStyle ?? "solid",
+          ocrLanguages: ocrLanguages ?? [],
+          ocrAutomaticLanguageDetection: ocrAutomaticLanguageDetection ?? false,
+          ocrRecognitionLevel: ocrRecognitionLevel ?? "accurate",
+          ocrLanguageCorrection: ocrLanguageCorrection ?? true,
+          ocrCustomWords: ocrCustomWords ?? [],
+          ocrMinimumTextHeight: ocrMinimumTextHeight ?? 0,
+          ocrTextFormat: ocrTextFormat ?? "plain",
+          ocrTableFormat: ocrTableFormat ?? "tsv",
         });
       } catch (err) {
         console.error("Failed to load settings:", err);
@@ -146,6 +251,358 @@ export function PreferencesPage({ onBack, onSettingsChange }: PreferencesPagePro
                 onCheckedChange={(checked) => updateSetting("copyToClipboard", checked)}
               />
             </div>
+
+            {/* Include Cursor */}
+            <div className="flex items-center justify-between py-2">
+              <div>
+                <label htmlFor="include-cursor" className="text-sm font-medium text-foreground cursor-pointer block">
+                  Include cursor
+                </label>
+                <p className="text-xs text-foreground0">Show the mouse pointer in region and fullscreen captures</p>
+              </div>
+              <Switch
+                id="include-cursor"
+                checked={settings.includeCursor}
+                onCheckedChange={(checked) => updateSetting("includeCursor", checked)}
+              />
+            </div>
+
+            {/* Cursor Halo */}
+            <div className="flex items-center justify-between py-2">
+              <div>
+                <label htmlFor="cursor-halo" className="text-sm font-medium text-foreground cursor-pointer block">
+                  Highlight cursor
+                </label>
+                <p className="text-xs text-foreground0">Draw a halo around the included cursor</p>
+              </div>
+              <Switch
+                id="cursor-halo"
+                checked={settings.cursorHalo}
+                disabled={!settings.includeCursor}
+                onCheckedChange={(checked) => updateSetting("cursorHalo", checked)}
+              />
+            </div>
+
+            {/* Window Shadow */}
+            <div className="flex items-center justify-between py-2">
+              <div>
+                <label htmlFor="window-shadow" className="text-sm font-medium text-foreground cursor-pointer block">
+                  Window shadow
+                </label>
+                <p className="text-xs text-foreground0">Keep the drop shadow around window captures</p>
+              </div>
+              <Switch
+                id="window-shadow"
+                checked={settings.windowShadow}
+                onCheckedChange={(checked) => updateSetting("windowShadow", checked)}
+              />
+            </div>
+
+            {/* Capture Delay */}
+            <div className="flex items-center justify-between py-2">
+              <div>
+                <label htmlFor="capture-delay" className="text-sm font-medium text-foreground block">
+                  Capture delay
+                </label>
+                <p className="text-xs text-foreground0">Seconds to wait before capturing; the countdown shows in the menu bar</p>
+              </div>
+              <input
+                id="capture-delay"
+                type="number"
+                min={0}
+                max={30}
+                step={1}
+                defaultValue={settings.captureDelay}
+                onBlur={(e) => {
+                  const seconds = Math.min(Math.max(Math.round(Number(e.target.value) || 0), 0), 30);
+                  updateSetting("captureDelay", seconds);
+                }}
+                className="w-20 px-3 py-2 bg-secondary border border-border rounded-md text-sm text-foreground font-mono"
+              />
+            </div>
+          </CardContent>
+        </Card>
+
+        {/* Sensitive Windows */}
+        <Card className="bg-card border-border">
+          <CardHeader className="pb-4">
+            <CardTitle className="text-lg font-semibold text-card-foreground">Sensitive Windows</CardTitle>
+          </CardHeader>
+          <CardContent className="space-y-4">
+            <div className="flex items-center justify-between py-2">
+              <div>
+                <label htmlFor="sensitive-masking" className="text-sm font-medium text-foreground cursor-pointer block">
+                  Mask sensitive windows
+                </label>
+                <p className="text-xs text-foreground0">
+                  Hide matching windows in fullscreen and region captures before they are saved. Window,
+                  interactive and redacted captures that use the macOS picker are not masked, and on Wayland
+                  nothing can be masked; you are warned when that happens.
+                </p>
+              </div>
+              <Switch
+                id="sensitive-masking"
+                checked={settings.sensitiveWindowMasking}
+                onCheckedChange={(checked) => updateSetting("sensitiveWindowMasking", checked)}
+              />
+            </div>
+
+            <div className="flex items-center justify-between py-2">
+              <div>
+                <label htmlFor="sensitive-pixelate" className="text-sm font-medium text-foreground cursor-pointer block">
+                  Pixelate instead of blocking out
+                </label>
+                <p className="text-xs text-foreground0">Turn off to cover windows with a solid fill</p>
+              </div>
+              <Switch
+                id="sensitive-pixelate"
+                checked={settings.sensitiveMaskStyle === "pixelate"}
+                disabled={!settings.sensitiveWindowMasking}
+                onCheckedChange={(checked) =>
+                  updateSetting("sensitiveMaskStyle", checked ? "pixelate" : "solid")
+                }
+              />
+            </div>
+
+            <div className="space-y-2">
+              <label htmlFor="sensitive-app-names" className="text-sm font-medium text-foreground block">
+                App names
+              </label>
+              <textarea
+                id="sensitive-app-names"
+                rows={4}
+                defaultValue={settings.sensitiveAppNames.join("\n")}
+                disabled={!settings.sensitiveWindowMasking}
+                onBlur={(e) => updateSetting("sensitiveAppNames", parseList(e.target.value))}
+                className="w-full px-3 py-2 bg-secondary border border-border rounded-md text-sm text-foreground font-mono"
+              />
+              <p className="text-xs text-foreground0">One per line; matched anywhere in the app name, ignoring case</p>
+            </div>
+
+            <div className="space-y-2">
+              <label htmlFor="sensitive-window-titles" className="text-sm font-medium text-foreground block">
+                Window titles
+              </label>
+              <textarea
+                id="sensitive-window-titles"
+                rows={3}
+                defaultValue={settings.sensitiveWindowTitles.join("\n")}
+                disabled={!settings.sensitiveWindowMasking}
+                onBlur={(e) => updateSetting("sensitiveWindowTitles", parseList(e.target.value))}
+                placeholder="Online Banking"
+                className="w-full px-3 py-2 bg-secondary border border-border rounded-md text-sm text-foreground font-mono"
+              />
+              <p className="text-xs text-foreground0">Windows whose title contains any of these are masked too</p>
+            </div>
+          </CardContent>
+        </Card>
+
+        {/* Auto-Redact */}
+        <Card className="bg-card border-border">
+          <CardHeader className="pb-4">
+            <CardTitle className="text-lg font-semibold text-card-foreground">Auto-Redact</CardTitle>
+          </CardHeader>
+          <CardContent className="space-y-4">
+            <p className="text-xs text-foreground0">
+              Capture &amp; Redact Region reads the text in a capture and covers what matches before it is saved
+            </p>
+
+            <div className="grid grid-cols-2 gap-x-8 gap-y-2">
+              {REDACTION_DETECTORS.map(({ kind, label }) => (
+                <div key={kind} className="flex items-center justify-between py-1">
+                  <label htmlFor={`redact-${kind}`} className="text-sm text-foreground cursor-pointer">
+                    {label}
+                  </label>
+                  <Switch
+                    id={`redact-${kind}`}
+                    checked={settings.redactionDetectors.includes(kind)}
+                    onCheckedChange={(checked) =>
+                      updateSetting(
+                        "redactionDetectors",
+                        checked
+                          ? [...settings.redactionDetectors, kind]
+                          : settings.redactionDetectors.filter((enabled) => enabled !== kind)
+                      )
+                    }
+                  />
+                </div>
+              ))}
+            </div>
+
+            <div className="flex items-center justify-between py-2">
+              <div>
+                <label htmlFor="redact-pixelate" className="text-sm font-medium text-foreground cursor-pointer block">
+                  Pixelate instead of blocking out
+                </label>
+                <p className="text-xs text-foreground0">Turn off to cover matches with a solid fill</p>
+              </div>
+              <Switch
+                id="redact-pixelate"
+                checked={settings.redactionStyle === "pixelate"}
+                onCheckedChange={(checked) => updateSetting("redactionStyle", checked ? "pixelate" : "solid")}
+              />
+            </div>
+
+            <div className="space-y-2">
+              <label htmlFor="redact-patterns" className="text-sm font-medium text-foreground block">
+                Custom patterns
+              </label>
+              <textarea
+                id="redact-patterns"
+                rows={3}
+                defaultValue={settings.redactionPatterns.join("\n")}
+                onBlur={(e) =>
+                  updateSetting(
+                    "redactionPatterns",
+                    e.target.value.split("\n").map((pattern) => pattern.trim()).filter(Boolean)
+                  )
+                }
+                placeholder="INTERNAL-\d{6}"
+                className="w-full px-3 py-2 bg-secondary border border-border rounded-md text-sm text-foreground font-mono"
+              />
+              <p className="text-xs text-foreground0">
+                One regular expression per line; with a capture group, only the group is covered
+              </p>
+            </div>
+          </CardContent>
+        </Card>
+
+        {/* Text Recognition */}
+        <Card className="bg-card border-border">
+          <CardHeader className="pb-4">
+            <CardTitle className="text-lg font-semibold text-card-foreground">Text Recognition</CardTitle>
+          </CardHeader>
+          <CardContent className="space-y-4">
+            <div className="space-y-2">
+              <label htmlFor="ocr-languages" className="text-sm font-medium text-foreground block">
+                Languages
+              </label>
+              <input
+                id="ocr-languages"
+                type="text"
+                defaultValue={settings.ocrLanguages.join(", ")}
+                onBlur={(e) => updateSetting("ocrLanguages", parseList(e.target.value))}
+                placeholder="en-US, de-DE, ja-JP"
+                className="w-full px-3 py-2 bg-secondary border border-border rounded-md text-sm text-foreground font-mono"
+              />
+              <p className="text-xs text-foreground0">Language codes, most common first; leave empty for English only</p>
+            </div>
+
+            <div className="flex items-center justify-between py-2">
+              <div>
+                <label htmlFor="ocr-auto-language" className="text-sm font-medium text-foreground cursor-pointer block">
+                  Detect language automatically
+                </label>
+                <p className="text-xs text-foreground0">Helps with screenshots that mix languages</p>
+              </div>
+              <Switch
+                id="ocr-auto-language"
+                checked={settings.ocrAutomaticLanguageDetection}
+                onCheckedChange={(checked) => updateSetting("ocrAutomaticLanguageDetection", checked)}
+              />
+            </div>
+
+            <div className="flex items-center justify-between py-2">
+              <div>
+                <label htmlFor="ocr-fast" className="text-sm font-medium text-foreground cursor-pointer block">
+                  Fast recognition
+                </label>
+                <p className="text-xs text-foreground0">Quicker but less accurate, best for large clear text</p>
+              </div>
+              <Switch
+                id="ocr-fast"
+                checked={settings.ocrRecognitionLevel === "fast"}
+                onCheckedChange={(checked) =>
+                  updateSetting("ocrRecognitionLevel", checked ? "fast" : "accurate")
+                }
+              />
+            </div>
+
+            <div className="flex items-center justify-between py-2">
+              <div>
+                <label htmlFor="ocr-correction" className="text-sm font-medium text-foreground cursor-pointer block">
+                  Language correction
+                </label>
+                <p className="text-xs text-foreground0">Fix up words with a dictionary; turn off for code and IDs</p>
+              </div>
+              <Switch
+                id="ocr-correction"
+                checked={settings.ocrLanguageCorrection}
+                onCheckedChange={(checked) => updateSetting("ocrLanguageCorrection", checked)}
+              />
+            </div>
+
+            <div className="flex items-center justify-between py-2">
+              <div>
+                <label htmlFor="ocr-markdown" className="text-sm font-medium text-foreground cursor-pointer block">
+                  Fence code as Markdown
+                </label>
+                <p className="text-xs text-foreground0">Wrap recognized code in ``` so it pastes as a code block</p>
+              </div>
+              <Switch
+                id="ocr-markdown"
+                checked={settings.ocrTextFormat === "markdown"}
+                onCheckedChange={(checked) =>
+                  updateSetting("ocrTextFormat", checked ? "markdown" : "plain")
+                }
+              />
+            </div>
+
+            <div className="flex items-center justify-between py-2">
+              <div>
+                <label htmlFor="ocr-table-format" className="text-sm font-medium text-foreground block">
+                  OCR Table format
+                </label>
+                <p className="text-xs text-foreground0">How OCR Table copies rows and columns</p>
+              </div>
+              <select
+                id="ocr-table-format"
+                value={settings.ocrTableFormat}
+                onChange={(e) =>
+                  updateSetting("ocrTableFormat", e.target.value as GeneralSettings["ocrTableFormat"])
+                }
+                className="px-3 py-2 bg-secondary border border-border rounded-md text-sm text-foreground"
+              >
+                <option value="tsv">TSV (spreadsheets)</option>
+                <option value="csv">CSV</option>
+                <option value="markdown">Markdown table</option>
+              </select>
+            </div>
+
+            <div className="space-y-2">
+              <label htmlFor="ocr-custom-words" className="text-sm font-medium text-foreground block">
+                Custom words
+              </label>
+              <textarea
+                id="ocr-custom-words"
+                rows={3}
+                defaultValue={settings.ocrCustomWords.join("\n")}
+                onBlur={(e) => updateSetting("ocrCustomWords", parseList(e.target.value))}
+                placeholder="Better Shot"
+                className="w-full px-3 py-2 bg-secondary border border-border rounded-md text-sm text-foreground font-mono"
+              />
+              <p className="text-xs text-foreground0">Names and jargon to prefer, one per line</p>
+            </div>
+
+            <div className="space-y-2">
+              <label htmlFor="ocr-min-height" className="text-sm font-medium text-foreground block">
+                Minimum text height
+              </label>
+              <input
+                id="ocr-min-height"
+                type="number"
+                min={0}
+                max={100}
+                step={0.5}
+                defaultValue={settings.ocrMinimumTextHeight * 100}
+                onBlur={(e) => {
+                  const percent = Math.min(Math.max(Number(e.target.value) || 0, 0), 100);
+                  updateSetting("ocrMinimumTextHeight", percent / 100);
+                }}
+                className="w-32 px-3 py-2 bg-secondary border border-border rounded-md text-sm text-foreground font-mono"
+              />
+              <p className="text-xs text-foreground0">Percent of the image height; smaller text is ignored. 0 reads everything</p>
+            </div>
           </CardContent>
         </Card>
 
@@ -0,0 +1,16 @@
+import type { CaptureError } from "@/types/capture-events";
+
+/**
+ * The typed error returned by a capture command; anything else, such as a
+ * failed invoke, is treated as a generic capture failure
+ */
+export function toCaptureError(err: unknown): CaptureError {
+  if (typeof err === "object" && err !== null && "code" in err && "message" in err) {
+    return err as CaptureError;
+  }
+  return {
+    code: "capture_failed",
+    rejection: null,
+    message: err instanceof Error ? err.message : String(err),
+  };
+}
@@ -0,0 +1,28 @@
+// Results of the scan_barcodes command in src-tauri/src/barcode.rs
+
+import type { PixelRect } from "./ocr";
+
+export type BarcodeKind =
+  | "qr_code"
+  | "data_matrix"
+  | "aztec"
+  | "pdf417"
+  | "ean13"
+  | "ean8"
+  | "upc_a"
+  | "upc_e"
+  | "code128"
+  | "code39"
+  | "code93"
+  | "codabar"
+  | "itf"
+  | "other";
+
+export interface Barcode {
+  kind: BarcodeKind;
+  text: string;
+  /** Finder pattern centers for QR codes, corners for Data Matrix, and the ends of the scanned row for 1D barcodes */
+  points: [number, number][];
+  /** Bounding box of `points` */
+  bbox: PixelRect;
+}
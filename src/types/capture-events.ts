@@ -0,0 +1,113 @@
+// Payloads of the capture lifecycle events emitted by src-tauri/src/events.rs
+
+export type CaptureMode =
+  | "primary_monitor"
+  | "all_monitors"
+  | "region"
+  | "frozen_region"
+  | "last_region"
+  | "saved_region"
+  | "window"
+  | "scrolling"
+  | "native_interactive"
+  | "native_fullscreen"
+  | "native_window"
+  | "ocr_region"
+  | "ocr_table"
+  | "scan_qr_region"
+  | "redacted_region";
+
+export type CaptureErrorCode =
+  | "permission_denied"
+  | "busy"
+  | "timeout"
+  | "not_found"
+  | "save_failed"
+  | "capture_failed"
+  /** The capture isn't possible on this platform or display server */
+  | "unsupported"
+  /** Only returned by capture commands; cancelled captures emit capture-cancelled */
+  | "cancelled";
+
+/** Why the capture coordinator turned a request away */
+export type CaptureRejection =
+  | "selection_in_progress"
+  | "queue_full"
+  | "queue_timeout"
+  | "cancelled"
+  | "timed_out";
+
+/** Error returned by capture commands */
+export interface CaptureError {
+  code: CaptureErrorCode;
+  rejection: CaptureRejection | null;
+  message: string;
+}
+
+export interface CaptureWindow {
+  id: number;
+  appName: string;
+  title: string;
+}
+
+export interface CaptureOutput {
+  path: string;
+  width: number;
+  height: number;
+  scaleFactor: number | null;
+  monitorId: number | null;
+}
+
+export type ClipboardContent =
+  | { type: "image"; path: string }
+  | { type: "text"; characters: number };
+
+export type ProcessingOperation = "ocr" | "sensitive_window_mask" | "barcode_scan" | "redaction";
+
+interface CaptureEventBase {
+  captureId: string;
+  mode: CaptureMode;
+}
+
+export interface CaptureStarted extends CaptureEventBase {
+  startedAt: number;
+}
+
+export interface CaptureCancelled extends CaptureEventBase {
+  durationMs: number;
+}
+
+export interface CaptureFailed extends CaptureEventBase {
+  code: CaptureErrorCode;
+  message: string;
+  durationMs: number;
+}
+
+export interface CaptureSaved extends CaptureEventBase {
+  window: CaptureWindow | null;
+  outputs: CaptureOutput[];
+  durationMs: number;
+}
+
+export interface CaptureCopied extends CaptureEventBase {
+  content: ClipboardContent;
+  durationMs: number;
+}
+
+export interface CaptureProcessed extends CaptureEventBase {
+  operation: ProcessingOperation;
+  inputPath: string | null;
+  outputPath: string | null;
+  /** Why the operation was skipped; the capture went ahead without it */
+  skipped: string | null;
+  durationMs: number;
+}
+
+export interface CaptureEventMap {
+  "capture-started": CaptureStarted;
+  "capture-cancelled": CaptureCancelled;
+  "capture-failed": CaptureFailed;
+  "capture-saved": CaptureSaved;
+  "capture-copied": CaptureCopied;
+  "capture-processed": CaptureProcessed;
+}
@@ -0,0 +1,29 @@
+// Options and results of the audit_contrast command in src-tauri/src/contrast.rs
+
+import type { PixelRect } from "./ocr";
+
+export type WcagLevel = "aa" | "aaa";
+
+export interface ContrastOptions {
+  level?: WcagLevel;
+  /** Image pixels per CSS pixel, such as 2 for a Retina capture */
+  pixelRatio?: number;
+}
+
+export interface ContrastIssue {
+  text: string;
+  bbox: PixelRect;
+  /** Estimated colours as `#rrggbb` */
+  foreground: string;
+  background: string;
+  ratio: number;
+  required: number;
+  largeText: boolean;
+}
+
+export interface ContrastReport {
+  runsChecked: number;
+  failures: ContrastIssue[];
+  /** Copy of the image with the failures outlined, when requested */
+  annotatedPath: string | null;
+}
@@ -0,0 +1,93 @@
+// Results of the recognize_text command in src-tauri/src/ocr.rs
+
+/** Pixels from the top-left corner of the image */
+export interface PixelRect {
+  x: number;
+  y: number;
+  width: number;
+  height: number;
+}
+
+/** Fractions of the image size, from the top-left corner */
+export type NormalizedRect = PixelRect;
+
+export interface OcrAlternative {
+  text: string;
+  confidence: number;
+}
+
+export interface OcrWord {
+  text: string;
+  bbox: PixelRect;
+  normalizedBbox: NormalizedRect;
+  /** From 0 to 1 */
+  confidence: number;
+  alternatives: OcrAlternative[];
+}
+
+export interface OcrLine {
+  text: string;
+  bbox: PixelRect;
+  normalizedBbox: NormalizedRect;
+  confidence: number;
+  alternatives: OcrAlternative[];
+  words: OcrWord[];
+}
+
+export interface OcrBlock {
+  bbox: PixelRect;
+  normalizedBbox: NormalizedRect;
+  confidence: number;
+  lines: OcrLine[];
+}
+
+export interface OcrResult {
+  engine: string;
+  width: number;
+  height: number;
+  blocks: OcrBlock[];
+}
+
+export type OcrExportFormat = "hocr" | "alto";
+
+/** OCR settings; omitted fields use the defaults */
+export interface OcrOptions {
+  /** BCP 47 codes such as "en-US", most likely first */
+  languages?: string[];
+  automaticLanguageDetection?: boolean;
+  recognitionLevel?: "fast" | "accurate";
+  languageCorrection?: boolean;
+  customWords?: string[];
+  /** Fraction of the image height */
+  minimumTextHeight?: number;
+}
+
+/** Results of the recognize_layout command in src-tauri/src/ocr_layout.rs */
+export interface LayoutBlock {
+  kind: "paragraph" | "code";
+  bbox: PixelRect;
+  /** Code lines keep their indentation; blank lines are empty strings */
+  lines: string[];
+}
+
+export interface LayoutColumn {
+  bbox: PixelRect;
+  blocks: LayoutBlock[];
+}
+
+export interface OcrLayout {
+  /** In reading order */
+  columns: LayoutColumn[];
+}
+
+export type OcrTextFormat = "plain" | "markdown";
+
+/** Result of the extract_table command in src-tauri/src/ocr_table.rs */
+export interface OcrTable {
+  /** Row-major cell text; every row has the same length */
+  rows: string[][];
+  /** Whether ruling lines in the image decided the rows or columns */
+  ruled: boolean;
+}
+
+export type TableFormat = "tsv" | "csv" | "markdown";
@@ -0,0 +1,10 @@
+// Events emitted by the recorder in src-tauri/src/recording.rs
+
+export type RecordingState = "idle" | "recording" | "paused";
+
+/** Payload of recording-state-changed */
+export interface RecordingStateChanged {
+  state: RecordingState;
+  /** Why the recording ended early, such as ffmpeg exiting */
+  error: string | null;
+}
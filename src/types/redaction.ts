@@ -0,0 +1,22 @@
+// Results of the suggest_redactions command in src-tauri/src/redaction.rs
+
+import type { PixelRect } from "./ocr";
+
+export type SensitiveKind =
+  | "email"
+  | "phone"
+  | "ipv4"
+  | "ipv6"
+  | "credit_card"
+  | "jwt"
+  | "aws_key"
+  | "github_token"
+  | "slack_token"
+  | "custom";
+
+export interface RedactionSuggestion {
+  bbox: PixelRect;
+  kind: SensitiveKind;
+  /** Such as "Email address", or the custom pattern that matched */
+  reason: string;
+}